@@ -0,0 +1,155 @@
+use std::fmt;
+use std::sync::Arc;
+
+use chrono_period::NaivePeriod;
+
+use super::resources::Resource;
+use super::volume::Volume;
+
+/// The names of the built-in allocation strategies, as used in the `allocationStrategy` and
+/// `allocationStrategies` of a BPD file.
+pub const ALLOCATION_STRATEGY_NAMES: [&str; 5] = ["lowest-id", "best-fit", "least-recently-used",
+                                                  "round-robin", "balanced"];
+
+/// A way of choosing which of several free `Resource`s of the same type to allocate to a phase.
+///
+/// Strategies are set for all resource types, or for a single one, on a
+/// [ResourceTracker](super::resources::ResourceTracker), or on a
+/// [ProductionSchedule](super::ProductionSchedule) with
+/// [set_allocation_strategy](super::ProductionSchedule::set_allocation_strategy).
+///
+/// # Notes
+/// Strategies should be deterministic, so that the same BPD data always produces the same
+/// schedule. Anything a strategy needs to know about earlier allocations, such as which `Resource`
+/// was used last, should be derived from the allocations of the `Resource`s themselves. That way,
+/// it stays correct when the scheduler undoes allocations to try a recipe at another date.
+///
+/// Strategies are asked to choose for every allocation, so rather than go through all the working
+/// periods of each `Resource` every time, they should rely on the summaries a `Resource` keeps of
+/// its allocations, such as
+/// [get_working_duration](super::resources::Resource::get_working_duration) and
+/// [get_last_working_period_before](super::resources::Resource::get_last_working_period_before).
+pub trait AllocationStrategy: fmt::Debug + Send + Sync {
+    /// Choose the `Resource` to allocate for a period.
+    ///
+    /// # Arguments
+    /// - `candidates`: The `Resource`s that are free during `period` and able to hold
+    ///   `batch_size`, in `id` order. There is always at least one.
+    /// - `resources`: All `Resource`s of the same type, whether they are free or not, in `id`
+    ///   order.
+    /// - `period`: The [NaivePeriod](chrono_period::NaivePeriod) of the requested allocation.
+    /// - `batch_size`: An `Option` containing the size of the batch the `Resource` must hold, or
+    ///   `None` if it is not known.
+    ///
+    /// # Returns
+    /// - The index within `candidates` of the `Resource` to allocate. An index that is out of
+    ///   bounds causes the allocation to fail.
+    fn choose(&self, candidates: &[&Resource], resources: &[&Resource], period: NaivePeriod,
+              batch_size: Option<&Volume>) -> usize;
+}
+
+/// Allocate the free `Resource` with the minimum `id`. This is the default.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct LowestId;
+
+impl AllocationStrategy for LowestId {
+    fn choose(&self, _candidates: &[&Resource], _resources: &[&Resource], _period: NaivePeriod,
+              _batch_size: Option<&Volume>) -> usize {
+        0
+    }
+}
+
+/// Allocate the free `Resource` with the smallest capacity, so that large vessels are kept for
+/// large batches. `Resource`s without a capacity come last.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct BestFit;
+
+impl AllocationStrategy for BestFit {
+    fn choose(&self, candidates: &[&Resource], _resources: &[&Resource], _period: NaivePeriod,
+              _batch_size: Option<&Volume>) -> usize {
+        get_index_of_minimum(candidates, |res| {
+            res.capacity().map_or(f64::INFINITY, |capacity| capacity.litres())
+        })
+    }
+}
+
+/// Allocate the free `Resource` whose last allocation before the requested period ended the
+/// longest time ago. `Resource`s that have not been allocated yet come first.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct LeastRecentlyUsed;
+
+impl AllocationStrategy for LeastRecentlyUsed {
+    fn choose(&self, candidates: &[&Resource], _resources: &[&Resource], period: NaivePeriod,
+              _batch_size: Option<&Volume>) -> usize {
+        get_index_of_minimum(candidates, |res| {
+            res.get_last_working_period_before(period.start).map(|x| x.end)
+        })
+    }
+}
+
+/// Allocate the free `Resource` that follows, in `id` order, the one whose allocation started
+/// last, up to the start of the requested period, wrapping around to the minimum `id`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RoundRobin;
+
+impl AllocationStrategy for RoundRobin {
+    fn choose(&self, candidates: &[&Resource], resources: &[&Resource], period: NaivePeriod,
+              _batch_size: Option<&Volume>) -> usize {
+        let last_used_id: Option<usize> = resources.iter()
+          .filter_map(|res| {
+              res.get_last_working_period_before(period.start).map(|x| (x.start, res.id))
+          })
+          .max()
+          .map(|(_, id)| id);
+
+        match last_used_id {
+            Some(last_used_id) => candidates.iter().position(|res| res.id > last_used_id)
+                                            .unwrap_or(0),
+            None => 0
+        }
+    }
+}
+
+/// Allocate the free `Resource` that has been allocated for the least time so far, so that all
+/// `Resource`s of a type see about the same amount of use.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Balanced;
+
+impl AllocationStrategy for Balanced {
+    fn choose(&self, candidates: &[&Resource], _resources: &[&Resource], _period: NaivePeriod,
+              _batch_size: Option<&Volume>) -> usize {
+        get_index_of_minimum(candidates, |res| res.get_working_duration())
+    }
+}
+
+/// Retrieve a built-in allocation strategy by name.
+///
+/// # Arguments
+/// - `name`: One of [ALLOCATION_STRATEGY_NAMES](ALLOCATION_STRATEGY_NAMES), e.g. `best-fit`.
+///
+/// # Returns
+/// - An `Option` containing the `AllocationStrategy`, or `None` if there is no strategy called
+///   `name`.
+pub fn get_allocation_strategy(name: &str) -> Option<Arc<dyn AllocationStrategy>> {
+    match name {
+        "lowest-id" => Some(Arc::new(LowestId)),
+        "best-fit" => Some(Arc::new(BestFit)),
+        "least-recently-used" => Some(Arc::new(LeastRecentlyUsed)),
+        "round-robin" => Some(Arc::new(RoundRobin)),
+        "balanced" => Some(Arc::new(Balanced)),
+        _ => None
+    }
+}
+
+/// Retrieve the index of the first `Resource` for which a key is minimal.
+fn get_index_of_minimum<K, F>(candidates: &[&Resource], get_key: F) -> usize
+  where K: PartialOrd, F: Fn(&Resource) -> K {
+    let mut index_of_minimum: usize = 0;
+    for (index, candidate) in candidates.iter().enumerate().skip(1) {
+        if get_key(candidate) < get_key(candidates[index_of_minimum]) {
+            index_of_minimum = index;
+        }
+    }
+
+    index_of_minimum
+}
@@ -0,0 +1,215 @@
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Weekday};
+
+use chrono_period::NaivePeriod;
+
+use serde::{Deserialize, Serialize};
+
+use super::error::Error;
+use super::timezone::TimeZone;
+
+/// The number of days searched for working time before giving up, e.g. because every day is a
+/// holiday.
+const MAX_DAYS_WITHOUT_WORKING_TIME: i64 = 3660;
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+/// A specification of when staff are available to work on phases, such as brewing, that cannot
+/// run unattended.
+///
+/// Deserialized from the `calendar` block of a BPD file, e.g.
+/// `{ "weekdays": ["mon", "tue", "wed", "thu"], "hours": "08:00-17:00", "holidays": ["2020-12-25"] }`.
+pub struct WorkingCalendar {
+    /// The days of the week on which staff work, e.g. `mon` or `monday`. Defaults to Monday to
+    /// Friday.
+    #[serde(default = "get_default_weekdays")]
+    pub weekdays: Vec<String>,
+
+    /// The hours during which staff work on a working day, e.g. `08:00-17:00`. A shift that ends
+    /// at or before it starts runs past midnight. Defaults to the whole day.
+    #[serde(rename="hours")]
+    #[serde(default)]
+    pub hours_string: Option<String>,
+
+    /// The dates, in `YYYY-MM-DD` format, on which staff do not work, even if they fall on a
+    /// working day. A shift belongs to the date it starts on.
+    #[serde(default)]
+    pub holidays: Vec<String>
+}
+
+fn get_default_weekdays() -> Vec<String> {
+    ["mon", "tue", "wed", "thu", "fri"].iter().map(|x| x.to_string()).collect()
+}
+
+impl WorkingCalendar {
+    /// Resolve this `WorkingCalendar` into the `WorkingTime` it describes.
+    ///
+    /// # Arguments
+    /// - `path`: The path of this `WorkingCalendar` within the BPD file, used for error reporting.
+    ///
+    /// # Returns
+    /// - A `Result` containing either the `WorkingTime`, or an `Error` describing the first part of
+    ///   this `WorkingCalendar` that could not be parsed.
+    pub fn working_time(&self, path: &str) -> Result<WorkingTime, Error> {
+        let mut weekdays: Vec<Weekday> = vec![];
+        for (index, weekday) in self.weekdays.iter().enumerate() {
+            match weekday.parse::<Weekday>() {
+                Ok(x) => weekdays.push(x),
+                Err(_) => return Err(Error::InvalidCalendar {
+                    path: format!("{}.weekdays[{}]", path, index),
+                    reason: format!("unknown day of the week \"{}\"", weekday)
+                })
+            }
+        }
+
+        let (start, length): (NaiveTime, Duration) = match &self.hours_string {
+            Some(x) => get_working_hours(&x[..]).ok_or_else(|| Error::InvalidCalendar {
+                path: format!("{}.hours", path),
+                reason: format!("unable to parse working hours \"{}\"; expected e.g. \
+                                 \"08:00-17:00\"", x)
+            })?,
+            None => (NaiveTime::from_hms_opt(0, 0, 0).unwrap(), Duration::days(1))
+        };
+
+        let mut holidays: Vec<NaiveDate> = vec![];
+        for (index, holiday) in self.holidays.iter().enumerate() {
+            match NaiveDate::parse_from_str(&holiday[..], "%Y-%m-%d") {
+                Ok(x) => holidays.push(x),
+                Err(e) => return Err(Error::DateParse {
+                    path: format!("{}.holidays[{}]", path, index),
+                    value: holiday.clone(),
+                    source: e
+                })
+            }
+        }
+
+        if weekdays.is_empty() {
+            return Err(Error::InvalidCalendar {
+                path: format!("{}.weekdays", path),
+                reason: "there are no working days".to_string()
+            });
+        }
+
+        Ok(WorkingTime { weekdays, start, length, holidays, time_zone: None })
+    }
+}
+
+/// Parse working hours such as `08:00-17:00` into the time at which work starts and how long it
+/// lasts.
+fn get_working_hours(hours_string: &str) -> Option<(NaiveTime, Duration)> {
+    let (start_string, end_string) = hours_string.split_once('-')?;
+    let start: NaiveTime = NaiveTime::parse_from_str(start_string.trim(), "%H:%M").ok()?;
+    let end: NaiveTime = match end_string.trim() {
+        "24:00" => NaiveTime::from_hms_opt(0, 0, 0).unwrap(),
+        x => NaiveTime::parse_from_str(x, "%H:%M").ok()?
+    };
+
+    match end > start {
+        true => Some((start, end - start)),
+        false => Some((start, end - start + Duration::days(1)))
+    }
+}
+
+/// The times at which staff are available to work, as described by a
+/// [WorkingCalendar](WorkingCalendar).
+#[derive(Clone, PartialEq, Debug)]
+pub struct WorkingTime {
+    weekdays: Vec<Weekday>,
+    start: NaiveTime,
+    length: Duration,
+    holidays: Vec<NaiveDate>,
+
+    /// The time zone in which the working hours and holidays are given, or `None` if dates are
+    /// local times already.
+    time_zone: Option<TimeZone>
+}
+
+impl WorkingTime {
+    /// Retrieve this `WorkingTime` at a site in a specific time zone.
+    ///
+    /// # Arguments
+    /// - `time_zone`: The time zone of the site, or `None` if dates are local times already.
+    ///
+    /// # Returns
+    /// - A copy of this `WorkingTime` that takes and returns dates in UTC, and whose working hours
+    ///   and holidays are those of the local time of `time_zone`.
+    pub fn in_time_zone(&self, time_zone: Option<&TimeZone>) -> WorkingTime {
+        WorkingTime { time_zone: time_zone.cloned(), ..self.clone() }
+    }
+
+    /// Retrieve the working time that is in progress at, or follows, a specific date.
+    ///
+    /// # Returns
+    /// - An `Option` containing the part of the shift that remains from `date`, or `None` if there
+    ///   is no working time within ten years of `date`.
+    pub fn get_working_period_from(&self, date: NaiveDateTime) -> Option<NaivePeriod> {
+        let local_date: NaiveDateTime = match &self.time_zone {
+            Some(x) => x.to_local(date),
+            None => date
+        };
+
+        // A shift that runs past midnight may have started on the previous day.
+        let first_day: NaiveDate = local_date.date().pred_opt()?;
+        for day in first_day.iter_days().take(MAX_DAYS_WITHOUT_WORKING_TIME as usize) {
+            if !self.weekdays.contains(&day.weekday()) || self.holidays.contains(&day) {
+                continue;
+            }
+
+            let shift = NaivePeriod::from_start_duration(day.and_time(self.start), self.length);
+            if shift.end > local_date {
+                let (start, end) = match &self.time_zone {
+                    Some(x) => (x.to_utc(shift.start), x.to_utc(shift.end)),
+                    None => (shift.start, shift.end)
+                };
+
+                return Some(NaivePeriod::new(start.max(date), end));
+            }
+        }
+
+        None
+    }
+
+    /// Determine the first moment at or after a specific date at which staff are working.
+    pub fn get_next_working_date(&self, date: NaiveDateTime) -> Option<NaiveDateTime> {
+        self.get_working_period_from(date).map(|period| period.start)
+    }
+
+    /// Determine when work of a specific duration is done, if it starts at a specific date and
+    /// only progresses during working time.
+    ///
+    /// # Arguments
+    /// - `start`: The date at which the work starts. It does not have to be in working time.
+    /// - `work`: The amount of working time needed.
+    ///
+    /// # Returns
+    /// - An `Option` containing the date at which the work is done, or `None` if there is not
+    ///   enough working time to do it.
+    pub fn get_end_of_work(&self, start: NaiveDateTime, work: Duration) -> Option<NaiveDateTime> {
+        self.get_periods_of_work(start, work)
+            .map(|periods| periods.last().map_or(start, |period| period.end))
+    }
+
+    /// Retrieve the parts of working time during which work of a specific duration is done, if it
+    /// starts at a specific date.
+    ///
+    /// # Returns
+    /// - An `Option` containing the periods, in chronological order, whose durations add up to
+    ///   `work`, or `None` if there is not enough working time to do it.
+    pub fn get_periods_of_work(&self, start: NaiveDateTime,
+                               work: Duration) -> Option<Vec<NaivePeriod>> {
+        let mut periods: Vec<NaivePeriod> = vec![];
+        let mut remaining: Duration = work;
+        let mut date: NaiveDateTime = start;
+        while remaining > Duration::zero() {
+            let period: NaivePeriod = self.get_working_period_from(date)?;
+            if remaining <= period.duration() {
+                periods.push(NaivePeriod::from_start_duration(period.start, remaining));
+                break;
+            }
+
+            remaining = remaining - period.duration();
+            date = period.end;
+            periods.push(period);
+        }
+
+        Some(periods)
+    }
+}
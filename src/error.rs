@@ -0,0 +1,457 @@
+use std::fmt;
+
+use chrono::{NaiveDateTime, ParseError};
+
+use super::allocation::ALLOCATION_STRATEGY_NAMES;
+use super::resources::{ResourceSelector, ResourceType};
+use super::volume::Volume;
+
+/// An error that occurred while loading or scheduling a
+/// [ProductionSchedule](super::ProductionSchedule).
+///
+/// Errors that refer to a specific element of the BPD file carry a `path` that points at the
+/// offending element, e.g. `recipes["Damned Squirrel Mk. II"].phases[2].duration`.
+#[derive(Debug)]
+pub enum Error {
+    /// The BPD data could not be read from its source.
+    Io(std::io::Error),
+
+    /// The BPD data is not valid JSON, or does not match the expected structure.
+    Json(serde_json::Error),
+
+    /// The `configuration` of the timeline is neither `calendar` nor `relative`.
+    UnknownTimelineConfiguration {
+        path: String,
+        configuration: String
+    },
+
+    /// A date string could not be parsed.
+    DateParse {
+        path: String,
+        value: String,
+        source: ParseError
+    },
+
+    /// A duration string could not be parsed.
+    DurationParse {
+        path: String,
+        value: String,
+        reason: String
+    },
+
+    /// A volume string could not be parsed.
+    VolumeParse {
+        path: String,
+        value: String
+    },
+
+    /// A phase refers to a phase template id that is not defined in `phaseTemplates`.
+    UnknownTemplate {
+        path: String,
+        template: String
+    },
+
+    /// A phase needs a `ResourceType` for which no `Resource` is defined.
+    MissingResourceType {
+        path: String,
+        resource_type: ResourceType
+    },
+
+    /// `Resource`s of the requested `ResourceType` exist, but they cannot hold the batch, even
+    /// when it is split across all of them.
+    InsufficientCapacity {
+        path: String,
+        resource_type: ResourceType,
+        batch_size: Volume
+    },
+
+    /// A phase carries over a `ResourceType` that the previous phase does not use.
+    NothingToCarryOver {
+        path: String,
+        resource_type: ResourceType
+    },
+
+    /// A `Resource` carried over from the previous phase is already booked for something else
+    /// during the phase.
+    CarryOver {
+        path: String,
+        resource_name: String
+    },
+
+    /// A phase depends on a phase that is not defined in its recipe.
+    UnknownPhase {
+        path: String,
+        phase: String
+    },
+
+    /// A phase depends on a phase id that is shared by several phases of its recipe.
+    AmbiguousPhase {
+        path: String,
+        phase: String
+    },
+
+    /// The dependencies between phases form a cycle, so none of the phases in it can be
+    /// scheduled.
+    DependencyCycle {
+        path: String,
+        phases: Vec<String>
+    },
+
+    /// A phase depends on a phase of a recipe that is not defined in `recipes`.
+    UnknownRecipe {
+        path: String,
+        recipe: String
+    },
+
+    /// A phase of a recipe that is not repeated depends on a repeated recipe by its name, rather
+    /// than on one of its instances.
+    AmbiguousRecipe {
+        path: String,
+        recipe: String
+    },
+
+    /// The dependencies between phases of different recipes form a cycle, so none of the recipes
+    /// in it can be scheduled.
+    RecipeCycle {
+        path: String,
+        recipes: Vec<String>
+    },
+
+    /// A phase cannot start within its `maxGap` of its predecessors, even when its recipe is
+    /// shifted later.
+    GapExceeded {
+        path: String,
+        max_gap: String
+    },
+
+    /// The `maxGap` of a phase is shorter than its `minGap`, so no start date can satisfy both.
+    InvalidGap {
+        path: String,
+        min_gap: String,
+        max_gap: String
+    },
+
+    /// A phase, or a resource held over it, would end beyond the latest date that can be
+    /// represented.
+    DateOutOfRange {
+        path: String
+    },
+
+    /// A downtime window of a `Resource` cannot be expanded into periods, e.g. because it ends
+    /// before it starts, or repeats without saying when to stop repeating.
+    InvalidDowntime {
+        path: String,
+        reason: String
+    },
+
+    /// The working calendar cannot be parsed, or has no working time.
+    InvalidCalendar {
+        path: String,
+        reason: String
+    },
+
+    /// A time zone cannot be found, or cannot be used.
+    InvalidTimeZone {
+        path: String,
+        reason: String
+    },
+
+    /// A recipe or resource refers to a site that is not defined in `sites`.
+    UnknownSite {
+        path: String,
+        site: String
+    },
+
+    /// The `repeat` block of a recipe cannot be expanded into instances, e.g. because it does not
+    /// say when to stop repeating.
+    InvalidRepeat {
+        path: String,
+        reason: String
+    },
+
+    /// A recipe cannot be ready by its `readyBy` date, even when it starts as early as it is
+    /// allowed to. The dates are in the local time of the site of the recipe, like `readyBy`.
+    ReadyByUnreachable {
+        path: String,
+        ready_by: NaiveDateTime,
+        earliest_start: NaiveDateTime,
+        earliest_ready: NaiveDateTime
+    },
+
+    /// A phase is pinned to start at a specific date, but the phases before it cannot end by
+    /// then, even when the recipe starts as early as it is allowed to.
+    PinnedPhaseUnreachable {
+        path: String,
+        start: NaiveDateTime,
+        earliest_start: NaiveDateTime
+    },
+
+    /// A phase is pinned to start at a specific date, but cannot start then, e.g. because its
+    /// resources are already taken.
+    PinnedPhaseUnavailable {
+        path: String,
+        start: NaiveDateTime,
+        reason: String
+    },
+
+    /// A phase needs more staff with a skill than are defined.
+    MissingStaff {
+        path: String,
+        skill: Option<String>,
+        count: usize,
+        available: usize
+    },
+
+    /// Enough staff with the skills a phase needs are defined, but they are never free together
+    /// within their maximum working hours.
+    InsufficientStaff {
+        path: String
+    },
+
+    /// An allocation strategy is named that does not exist.
+    UnknownAllocationStrategy {
+        path: String,
+        strategy: String
+    },
+
+    /// A phase is assigned a specific `Resource`, by name or id, that is not defined.
+    UnknownResource {
+        path: String,
+        resource: ResourceSelector
+    },
+
+    /// A phase is assigned a specific `Resource` that is already taken, or out of service, when
+    /// the phase is able to start.
+    ResourceBusy {
+        path: String,
+        resource_name: String,
+        start: NaiveDateTime
+    },
+
+    /// A `Resource` of the requested `ResourceType` exists, but none could be allocated for the
+    /// phase.
+    Allocation {
+        path: String,
+        resource_type: ResourceType
+    }
+}
+
+impl Error {
+    /// Retrieve the path of the element within the BPD file that this `Error` refers to, or `None`
+    /// if it does not refer to a specific element.
+    pub fn path(&self) -> Option<&str> {
+        match self {
+            Error::Io(_) | Error::Json(_) => None,
+            Error::UnknownTimelineConfiguration { path, .. }
+              | Error::DateParse { path, .. }
+              | Error::DurationParse { path, .. }
+              | Error::VolumeParse { path, .. }
+              | Error::UnknownTemplate { path, .. }
+              | Error::MissingResourceType { path, .. }
+              | Error::InsufficientCapacity { path, .. }
+              | Error::NothingToCarryOver { path, .. }
+              | Error::CarryOver { path, .. }
+              | Error::UnknownPhase { path, .. }
+              | Error::AmbiguousPhase { path, .. }
+              | Error::DependencyCycle { path, .. }
+              | Error::UnknownRecipe { path, .. }
+              | Error::AmbiguousRecipe { path, .. }
+              | Error::RecipeCycle { path, .. }
+              | Error::GapExceeded { path, .. }
+              | Error::InvalidGap { path, .. }
+              | Error::DateOutOfRange { path }
+              | Error::InvalidDowntime { path, .. }
+              | Error::InvalidRepeat { path, .. }
+              | Error::InvalidTimeZone { path, .. }
+              | Error::UnknownSite { path, .. }
+              | Error::InvalidCalendar { path, .. }
+              | Error::ReadyByUnreachable { path, .. }
+              | Error::PinnedPhaseUnreachable { path, .. }
+              | Error::PinnedPhaseUnavailable { path, .. }
+              | Error::MissingStaff { path, .. }
+              | Error::InsufficientStaff { path, .. }
+              | Error::UnknownAllocationStrategy { path, .. }
+              | Error::UnknownResource { path, .. }
+              | Error::ResourceBusy { path, .. }
+              | Error::Allocation { path, .. } => Some(&path[..])
+        }
+    }
+
+    /// Retrieve a human-readable description of this `Error`, without its path.
+    pub fn message(&self) -> String {
+        match self {
+            Error::Io(e) => format!("unable to read production schedule: {}", e),
+            Error::Json(e) => format!("unable to parse production schedule: {}", e),
+            Error::UnknownTimelineConfiguration { configuration, .. } => {
+                format!("unknown timeline configuration \"{}\"; expected \"calendar\" or \
+                         \"relative\"", configuration)
+            },
+            Error::DateParse { value, source, .. } => {
+                format!("unable to parse date \"{}\": {}", value, source)
+            },
+            Error::DurationParse { value, reason, .. } => {
+                format!("unable to parse duration \"{}\": {}", value, reason)
+            },
+            Error::VolumeParse { value, .. } => {
+                format!("unable to parse volume \"{}\"", value)
+            },
+            Error::UnknownTemplate { template, .. } => {
+                format!("unknown phase template \"{}\"", template)
+            },
+            Error::MissingResourceType { resource_type, .. } => {
+                format!("no resources of type {:?} are defined", resource_type)
+            },
+            Error::InsufficientCapacity { resource_type, batch_size, .. } => {
+                format!("resources of type {:?} cannot hold a batch of {}, even when combined",
+                        resource_type, batch_size)
+            },
+            Error::NothingToCarryOver { resource_type, .. } => {
+                format!("the previous phase does not use a resource of type {:?} to carry over",
+                        resource_type)
+            },
+            Error::CarryOver { resource_name, .. } => {
+                format!("unable to keep \"{}\" from the previous phase, as it is already booked",
+                        resource_name)
+            },
+            Error::UnknownPhase { phase, .. } => {
+                format!("unknown phase \"{}\"", phase)
+            },
+            Error::AmbiguousPhase { phase, .. } => {
+                format!("several phases are identified by \"{}\"; give them distinct ids", phase)
+            },
+            Error::DependencyCycle { phases, .. } => {
+                let quoted: Vec<String> = phases.iter().map(|x| format!("{:?}", x)).collect();
+                format!("phases depend on each other in a cycle: {} -> {}", quoted.join(" -> "),
+                        quoted[0])
+            },
+            Error::UnknownRecipe { recipe, .. } => {
+                format!("unknown recipe \"{}\"", recipe)
+            },
+            Error::AmbiguousRecipe { recipe, .. } => {
+                format!("\"{}\" is repeated; depend on one of its instances, e.g. \"{} #1\"",
+                        recipe, recipe)
+            },
+            Error::RecipeCycle { recipes, .. } => {
+                let quoted: Vec<String> = recipes.iter().map(|x| format!("{:?}", x)).collect();
+                format!("recipes depend on each other in a cycle: {} -> {}", quoted.join(" -> "),
+                        quoted[0])
+            },
+            Error::GapExceeded { max_gap, .. } => {
+                format!("unable to start the phase within its maxGap of \"{}\"", max_gap)
+            },
+            Error::InvalidGap { min_gap, max_gap, .. } => {
+                format!("the maxGap \"{}\" is shorter than the minGap \"{}\"", max_gap, min_gap)
+            },
+            Error::DateOutOfRange { .. } => {
+                "the phase would end beyond the latest date that can be represented".to_string()
+            },
+            Error::InvalidDowntime { reason, .. } => {
+                format!("invalid downtime window: {}", reason)
+            },
+            Error::InvalidCalendar { reason, .. } => {
+                format!("invalid working calendar: {}", reason)
+            },
+            Error::InvalidTimeZone { reason, .. } => {
+                format!("invalid time zone: {}", reason)
+            },
+            Error::UnknownSite { site, .. } => {
+                format!("unknown site \"{}\"", site)
+            },
+            Error::InvalidRepeat { reason, .. } => {
+                format!("invalid repeat: {}", reason)
+            },
+            Error::ReadyByUnreachable { ready_by, earliest_start, earliest_ready, .. } => {
+                format!("unable to be ready by {}; starting at {}, the recipe can be ready by {} \
+                         at the earliest", ready_by.format("%Y-%m-%d %H:%M"),
+                        earliest_start.format("%Y-%m-%d %H:%M"),
+                        earliest_ready.format("%Y-%m-%d %H:%M"))
+            },
+            Error::PinnedPhaseUnreachable { start, earliest_start, .. } => {
+                format!("unable to start at {}, as the phases before it cannot end by then, even \
+                         if the recipe starts at {}", start.format("%Y-%m-%d %H:%M"),
+                        earliest_start.format("%Y-%m-%d %H:%M"))
+            },
+            Error::PinnedPhaseUnavailable { start, reason, .. } => {
+                format!("unable to start at {}, as {}", start.format("%Y-%m-%d %H:%M"), reason)
+            },
+            Error::MissingStaff { skill, count, available, .. } => {
+                let staff: String = match skill {
+                    Some(x) => format!("staff with skill \"{}\"", x),
+                    None => "staff".to_string()
+                };
+                format!("{} {} are needed, but only {} are defined", count, staff, available)
+            },
+            Error::InsufficientStaff { .. } => {
+                "unable to find a time at which the staff needed are free together within their \
+                 maximum working hours".to_string()
+            },
+            Error::UnknownAllocationStrategy { strategy, .. } => {
+                format!("unknown allocation strategy \"{}\"; expected one of {}", strategy,
+                        ALLOCATION_STRATEGY_NAMES.join(", "))
+            },
+            Error::UnknownResource { resource, .. } => {
+                format!("there is no resource {}", resource)
+            },
+            Error::ResourceBusy { resource_name, start, .. } => {
+                format!("\"{}\" is taken or out of service at {}, when the phase is able to start",
+                        resource_name, start.format("%Y-%m-%d %H:%M"))
+            },
+            Error::Allocation { resource_type, .. } => {
+                format!("unable to allocate a resource of type {:?}", resource_type)
+            }
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.path() {
+            Some(path) => write!(f, "{}: {}", path, self.message()),
+            None => write!(f, "{}", self.message())
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            Error::Json(e) => Some(e),
+            Error::DateParse { source, .. } => Some(source),
+            _ => None
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Json(e)
+    }
+}
+
+/// Retrieve the path of a recipe within a BPD file, for use in error messages.
+pub(crate) fn recipe_path(recipe_name: &str) -> String {
+    format!("recipes[{:?}]", recipe_name)
+}
+
+/// Retrieve the path of a phase within a recipe of a BPD file, for use in error messages.
+pub(crate) fn phase_path(recipe_name: &str, phase_index: usize) -> String {
+    format!("{}.phases[{}]", recipe_path(recipe_name), phase_index)
+}
+
+/// Retrieve the path of a downtime window of a resource within a BPD file, for use in error
+/// messages.
+pub(crate) fn downtime_path(resource_index: usize, downtime_index: usize) -> String {
+    format!("resources[{}].downtime[{}]", resource_index, downtime_index)
+}
+
+/// Retrieve the path of a phase template within a BPD file, for use in error messages.
+pub(crate) fn template_path(template_id: &str) -> String {
+    format!("phaseTemplates[{:?}]", template_id)
+}
@@ -1,9 +1,11 @@
+use std::collections::BTreeMap;
 use std::default::Default;
 use std::io::prelude::*;
 use std::iter::Iterator;
+use std::sync::Arc;
 
 extern crate chrono;
-use chrono::{Duration, NaiveDateTime};
+use chrono::{DateTime, Duration, FixedOffset, Months, NaiveDateTime, TimeZone as _};
 use chrono::format::ParseError;
 
 extern crate chrono_period;
@@ -15,33 +17,93 @@ extern crate string_builder;
 use string_builder::Builder;
 
 pub mod util;
-use util::get_naive_date_time_from_string;
+use util::{convert_string_to_calendar_duration, convert_string_to_duration, get_date_string,
+           get_naive_date_time_from_string, CalendarDuration};
+
+pub mod timezone;
+use timezone::TimeZone;
+
+pub mod calendar;
+use calendar::{WorkingCalendar, WorkingTime};
+
+pub mod error;
+pub use error::Error;
+use error::{downtime_path, phase_path, recipe_path, template_path};
+
+pub mod allocation;
+use allocation::{get_allocation_strategy, AllocationStrategy};
 
 pub mod resources;
+use resources::DowntimeSpec;
 use resources::Resource;
 use resources::ResourceTracker;
 use resources::ResourceType;
 
 pub mod phases;
+use phases::DependencyType;
 use phases::PhaseInstance;
+use phases::PhaseInstanceSpec;
 use phases::ProductionPhaseTemplate;
 
 pub mod recipes;
 use recipes::RecipeSpec;
 use recipes::Recipe;
+use recipes::RepeatSpec;
+
+pub mod optimize;
+
+pub mod validation;
+
+pub mod volume;
+use volume::Volume;
+
+/// The color of the tasks showing the turnaround time of `Resource`s in PLA output.
+const TURNAROUND_COLOR: &str = "#9E9E9E";
+
+/// The number of times a recipe is shifted later to keep its phases within their maximum gaps, or
+/// to keep resources carried over between its phases, before giving up.
+const MAX_RECIPE_SHIFTS: usize = 1000;
+
+/// The greatest number of times a recipe or a downtime window may recur, so that a `repeat` or
+/// `every` that goes on for too long is reported, rather than filling up memory.
+const MAX_RECURRENCES: usize = 10000;
+
+/// The color of the tasks showing the downtime of `Resource`s in PLA output.
+const DOWNTIME_COLOR: &str = "#D32F2F";
+
+/// The color of the phases of a recipe that end after its due date in PLA output.
+const LATE_COLOR: &str = "#FF6F00";
+
+/// The way in which dates are expressed in the output of a production schedule.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TimelineConfiguration {
+    /// Dates are expressed as calendar dates, e.g. `2020-01-15`. Specified as `calendar`.
+    Calendar,
+
+    /// Dates are expressed as the number of days since the start of the timeline, which is day
+    /// `0`, e.g. `14`. This is useful for generic brew plans that are not tied to specific dates.
+    /// Specified as `relative`.
+    Relative
+}
 
 #[derive(Serialize, Deserialize)]
 /// Configuration options for the timeline of the production schedule.
 ///
-/// At some point in the future, we're going to allow for the gantt-chart-creation software to take
-/// configuration options that allow it to either start at a specific date and start counting from
-/// that date , or start at a specific number, and just count generic days. Currently, since the
-/// gantt-chart-creation part of the application does not include this, it's not implemented other
-/// than to create these configuration variables as part of deserialization.
+/// The `configuration` determines whether the output counts calendar dates, starting at the
+/// `start` of the timeline, or just counts generic days, where the `start` of the timeline is day
+/// `0`. See [TimelineConfiguration](TimelineConfiguration).
 ///
+/// If a `timezone` is given, dates are local times of that time zone, and phases are scheduled in
+/// absolute time, so that a phase that spans a daylight saving time transition lasts as long as
+/// its duration says. Otherwise, dates are naive, and every day lasts 24 hours.
 pub struct ProductionTimeline {
     pub configuration: String,
-    start: String
+    start: String,
+
+    /// The time zone of the dates of the production schedule, e.g. `America/Denver`. See
+    /// [TimeZone](timezone::TimeZone) for the supported forms.
+    #[serde(default)]
+    pub timezone: Option<String>
 }
 
 impl ProductionTimeline {
@@ -55,6 +117,53 @@ impl ProductionTimeline {
     pub fn start_date(&self) -> std::result::Result<NaiveDateTime, ParseError> {
         get_naive_date_time_from_string(&self.start[..])
     }
+
+    /// Retrieve the configuration of this timeline, as a `TimelineConfiguration`.
+    ///
+    /// # Returns
+    /// * An `Option` containing the `TimelineConfiguration`, or `None` if `configuration` is
+    ///   neither `calendar` nor `relative`.
+    pub fn timeline_configuration(&self) -> Option<TimelineConfiguration> {
+        match &self.configuration[..] {
+            "calendar" => Some(TimelineConfiguration::Calendar),
+            "relative" => Some(TimelineConfiguration::Relative),
+            _ => None
+        }
+    }
+
+    /// Retrieve the time zone of this timeline.
+    ///
+    /// # Returns
+    /// - A `Result` containing either the `TimeZone`, or `None` if dates are naive, or an `Error`
+    ///   if the time zone cannot be found.
+    pub fn time_zone(&self) -> std::result::Result<Option<TimeZone>, Error> {
+        match &self.timezone {
+            Some(x) => TimeZone::from_name(&x[..]).map(Some).map_err(|reason| {
+                Error::InvalidTimeZone { path: "timeline.timezone".to_string(), reason }
+            }),
+            None => Ok(None)
+        }
+    }
+
+    /// Retrieve the date that dates in the output are counted from, or `None` if dates in the
+    /// output are calendar dates.
+    pub fn day_zero(&self) -> Option<NaiveDateTime> {
+        match self.timeline_configuration() {
+            Some(TimelineConfiguration::Relative) => self.start_date().ok(),
+            _ => None
+        }
+    }
+}
+
+/// A place at which recipes are brewed, e.g. one of several breweries, which may be in a time zone
+/// of its own.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ProductionSite {
+    pub name: String,
+
+    /// The time zone of the site, if it differs from the time zone of the timeline.
+    #[serde(default)]
+    pub timezone: Option<String>
 }
 
 #[derive(Serialize, Deserialize)]
@@ -71,6 +180,70 @@ pub struct ProductionSchedule {
     //            ResourceTracker instance.
     resources: Vec<Resource>,
 
+    /// The time needed after each allocation of a `Resource` before it can be allocated again,
+    /// keyed by `ResourceType`, e.g. `{ "fermentor": "1d" }`. A `turnaround` specified on an
+    /// individual `Resource` takes precedence.
+    #[serde(default)]
+    pub turnarounds: BTreeMap<String, String>,
+
+    /// The name of the strategy used to choose which of several free `Resource`s of the same
+    /// type to allocate, e.g. `best-fit`. Defaults to `lowest-id`. See
+    /// [ALLOCATION_STRATEGY_NAMES](allocation::ALLOCATION_STRATEGY_NAMES).
+    #[serde(rename="allocationStrategy")]
+    #[serde(default)]
+    pub allocation_strategy: Option<String>,
+
+    /// The names of the strategies used for specific resource types, keyed by `ResourceType`,
+    /// e.g. `{ "fermentor": "least-recently-used" }`. These take precedence over
+    /// `allocationStrategy`.
+    #[serde(rename="allocationStrategies")]
+    #[serde(default)]
+    pub allocation_strategies: BTreeMap<String, String>,
+
+    /// Alternative names for resource types, mapped to the names of the types they stand for,
+    /// e.g. `{ "brite": "brighttank" }`. An alias can be used wherever a resource type is named.
+    #[serde(rename="resourceTypeAliases")]
+    #[serde(default)]
+    pub resource_type_aliases: BTreeMap<String, String>,
+
+    /// The resource types whose resources can be used in place of those of other types, keyed by
+    /// the substitute, e.g. `{ "unitank": ["fermentor", "brighttank"] }`. A resource of the very
+    /// type that is needed is always allocated in preference to a substitute, if one is free, and
+    /// a substitute only fills a single need of a phase.
+    #[serde(default)]
+    pub substitutes: BTreeMap<String, Vec<String>>,
+
+    /// The strategies set with
+    /// [set_allocation_strategy](ProductionSchedule::set_allocation_strategy), for all resource
+    /// types or a single one. These take precedence over those named in the BPD file for the same
+    /// resource types.
+    #[serde(skip_serializing, skip_deserializing)]
+    custom_allocation_strategies: Vec<(Option<ResourceType>, Arc<dyn AllocationStrategy>)>,
+
+    /// When staff are available to work on phases whose template is `staffed`, or needs specific
+    /// staff. If not specified, staff are always available.
+    #[serde(default)]
+    pub calendar: Option<WorkingCalendar>,
+
+    #[serde(skip_serializing, skip_deserializing)]
+    working_time: Option<WorkingTime>,
+
+    /// The sites at which recipes are brewed, which recipes and resources refer to by name.
+    #[serde(default)]
+    pub sites: Vec<ProductionSite>,
+
+    #[serde(skip_serializing, skip_deserializing)]
+    time_zone: Option<TimeZone>,
+
+    /// The time zone of each site that has one.
+    #[serde(skip_serializing, skip_deserializing)]
+    site_time_zones: BTreeMap<String, TimeZone>,
+
+    /// Whether the PLA output should include the turnaround time of each `Resource` as tasks of
+    /// their own.
+    #[serde(skip_serializing, skip_deserializing)]
+    pub show_turnarounds: bool,
+
     #[serde(skip_serializing, skip_deserializing)]
     recipes: Vec<Recipe>,
 
@@ -80,29 +253,277 @@ pub struct ProductionSchedule {
     #[serde(skip_serializing, skip_deserializing)]
     last_id_used: usize,
 
+    /// The indices of the recipes in the order in which they should preferably be scheduled. If
+    /// empty, they are scheduled by priority and due date.
+    #[serde(skip_serializing, skip_deserializing)]
+    recipe_preference: Vec<usize>,
+
+    /// The id of the `Resource` that a phase should preferably be given, keyed by the id of the
+    /// phase. It is preferred over the `preferredResources` of the phase.
+    #[serde(skip_serializing, skip_deserializing)]
+    resource_choices: BTreeMap<usize, usize>,
+
     #[serde(skip_serializing, skip_deserializing, default = "ResourceTracker::new")]
     tracker: ResourceTracker
 }
 
 impl ProductionSchedule {
+    /// Create a new `ProductionSchedule` from a string slice containing BPD data.
+    ///
+    /// # Panics
+    /// - If the BPD data cannot be loaded or scheduled. Use
+    ///   [try_new](ProductionSchedule::try_new) to handle these errors instead.
     pub fn new(json_data: &str) -> Self {
-        let result: serde_json::Result<ProductionSchedule> = serde_json::from_str(json_data);
-        match result {
-            Ok(mut x) => {
-                x.init();
-                x
-            },
+        match ProductionSchedule::try_new(json_data) {
+            Ok(x) => x,
             Err(e) => {
                 panic!("Unable to parse due to: {}", e);
             }
         }
     }
 
-    pub fn init(&mut self) {
+    /// Create a new `ProductionSchedule` from a string slice containing BPD data.
+    ///
+    /// # Returns
+    /// - A `Result` containing either the fully scheduled `ProductionSchedule`, or an
+    ///   [Error](Error) describing the first problem encountered while loading or scheduling.
+    pub fn try_new(json_data: &str) -> std::result::Result<Self, Error> {
+        let mut schedule: ProductionSchedule = serde_json::from_str(json_data)?;
+        schedule.init()?;
+
+        Ok(schedule)
+    }
+
+    /// Create a new `ProductionSchedule` by reading BPD data from a reader.
+    ///
+    /// # Arguments
+    /// - `reader`: Any implementation of `Read` from which the BPD data can be read in full.
+    ///
+    /// # Returns
+    /// - A `Result` containing either the fully scheduled `ProductionSchedule`, or an
+    ///   [Error](Error) describing the first problem encountered while reading, loading or
+    ///   scheduling.
+    pub fn from_reader<R: Read>(mut reader: R) -> std::result::Result<Self, Error> {
+        let mut json_data: String = String::new();
+        reader.read_to_string(&mut json_data)?;
+
+        ProductionSchedule::try_new(&json_data[..])
+    }
+
+    pub fn init(&mut self) -> std::result::Result<(), Error> {
         self.last_id_used = 0;
-        self.track_resources();
-        self.verify_recipe_start_dates();
-        self.rebuild_recipes_from_specs();
+        self.resolve_resource_type_aliases();
+        self.resolve_time_zones()?;
+        self.working_time = match &self.calendar {
+            Some(x) => Some(x.working_time("calendar")?),
+            None => None
+        };
+        self.track_resources()?;
+        self.verify_recipe_start_dates()?;
+        self.expand_recurring_recipes()?;
+        self.rebuild_recipes_from_specs()
+    }
+
+    /// Schedule all recipes again from scratch, preferring to schedule them in a specific order.
+    ///
+    /// # Arguments
+    /// - `order`: The indices of all recipes, in the order in which they should be scheduled. A
+    ///   recipe is still scheduled after the recipes it depends on, even if it comes earlier in
+    ///   `order`.
+    ///
+    /// # Returns
+    /// - A `Result` that is `Ok` if all recipes could be scheduled, or contains an [Error](Error)
+    ///   describing the first problem encountered.
+    ///
+    /// # Notes
+    /// Recipes scheduled earlier get the first choice of resources, so the order can change when
+    /// each recipe finishes. Recipes keep their ids regardless of the order. Resource choices set
+    /// with [reschedule_with_resource_choices](ProductionSchedule::reschedule_with_resource_choices)
+    /// are kept.
+    pub fn reschedule_in_order(&mut self, order: &[usize]) -> std::result::Result<(), Error> {
+        self.recipe_preference = order.to_vec();
+        self.tracker = ResourceTracker::new();
+        self.init()
+    }
+
+    /// Schedule all recipes again from scratch, preferring to schedule them in a specific order,
+    /// and to give specific resources to some phases.
+    ///
+    /// # Arguments
+    /// - `order`: The indices of all recipes, in the order in which they should be scheduled. See
+    ///   [reschedule_in_order](ProductionSchedule::reschedule_in_order).
+    /// - `resource_choices`: The id of the `Resource` that a phase should preferably be given,
+    ///   keyed by the id of the phase.
+    ///
+    /// # Returns
+    /// - A `Result` that is `Ok` if all recipes could be scheduled, or contains an [Error](Error)
+    ///   describing the first problem encountered.
+    ///
+    /// # Notes
+    /// A chosen `Resource` is treated like the first of the `preferredResources` of its phase: it
+    /// is only given to the phase if it is free when the phase is able to start, and can be used
+    /// as one of the resource types the phase needs.
+    pub fn reschedule_with_resource_choices(&mut self, order: &[usize],
+                                            resource_choices: &BTreeMap<usize, usize>)
+      -> std::result::Result<(), Error> {
+        self.resource_choices = resource_choices.clone();
+        self.reschedule_in_order(order)
+    }
+
+    /// Retrieve the id of the `Resource` that each phase should preferably be given, keyed by the
+    /// id of the phase.
+    pub fn resource_choices(&self) -> &BTreeMap<usize, usize> {
+        &self.resource_choices
+    }
+
+    /// Use a custom [AllocationStrategy](allocation::AllocationStrategy) to choose between free
+    /// resources, and schedule all recipes again with it.
+    ///
+    /// # Arguments
+    /// - `resource_type`: The `ResourceType` to use `strategy` for, or `None` to use it for all
+    ///   resource types without a strategy of their own.
+    /// - `strategy`: The `AllocationStrategy` to use.
+    ///
+    /// # Returns
+    /// - A `Result` that is `Ok` if all recipes could be scheduled, or contains an [Error](Error)
+    ///   describing the first problem encountered.
+    pub fn set_allocation_strategy(&mut self, resource_type: Option<ResourceType>,
+                                   strategy: Arc<dyn AllocationStrategy>)
+      -> std::result::Result<(), Error> {
+        self.custom_allocation_strategies.push((resource_type, strategy));
+        self.reschedule_in_order(&self.recipe_preference.clone())
+    }
+
+    /// Retrieve the indices of the recipes in the order in which they are preferably scheduled.
+    ///
+    /// # Notes
+    /// If no order was set, or the order set does not contain every recipe exactly once, recipes
+    /// are ordered by their priority, highest first, then by their due date (or ready-by date),
+    /// earliest first.
+    /// Recipes without a due date come after those with one, and recipes that are otherwise equal
+    /// keep the order in which they appear in the BPD file.
+    pub fn recipe_order(&self) -> Vec<usize> {
+        let mut prioritized_order: Vec<usize> = (0..self.recipe_specs.len()).collect();
+        prioritized_order.sort_by_key(|index| {
+            let recipe_spec: &RecipeSpec = &self.recipe_specs[*index];
+            let site: Option<&str> = recipe_spec.site.as_deref();
+            let due_date: NaiveDateTime = recipe_spec.due_date()
+                                                     .ok()
+                                                     .flatten()
+                                                     .or(recipe_spec.ready_by().ok().flatten())
+                                                     .map(|x| {
+                                                         self.get_schedule_date(x, site)
+                                                     })
+                                                     .unwrap_or(NaiveDateTime::MAX);
+
+            (std::cmp::Reverse(recipe_spec.priority), due_date)
+        });
+
+        let mut sorted_preference: Vec<usize> = self.recipe_preference.clone();
+        sorted_preference.sort_unstable();
+
+        match sorted_preference.iter().cloned().eq(0..self.recipe_specs.len()) {
+            true => self.recipe_preference.clone(),
+            false => prioritized_order
+        }
+    }
+
+    /// Retrieve a human-readable representation of a date, as configured by the `configuration`
+    /// of the timeline, e.g. `2020-01-15 06:00` or `day 14 06:00`.
+    ///
+    /// # Arguments
+    /// - `date`: A date of the schedule, such as the start of a phase.
+    pub fn get_date_string(&self, date: NaiveDateTime) -> String {
+        get_date_string(self.get_local_date(date), self.timeline.day_zero())
+    }
+
+    /// Convert a date given in the BPD file into the time in which phases are scheduled.
+    ///
+    /// # Arguments
+    /// - `date`: The date, in the local time of `site`.
+    /// - `site`: The name of the site the date belongs to, or `None` for the timeline.
+    ///
+    /// # Returns
+    /// - The date in UTC, if the timeline has a time zone; `date` itself, otherwise.
+    pub fn get_schedule_date(&self, date: NaiveDateTime, site: Option<&str>) -> NaiveDateTime {
+        match self.get_time_zone(site) {
+            Some(x) => x.to_utc(date),
+            None => date
+        }
+    }
+
+    /// Convert a date of the schedule, such as the start of a phase, into the local time of the
+    /// timeline, in which dates are output.
+    pub fn get_local_date(&self, date: NaiveDateTime) -> NaiveDateTime {
+        match &self.time_zone {
+            Some(x) => x.to_local(date),
+            None => date
+        }
+    }
+
+    /// Convert a date of the schedule into the local time of a site, in which the dates of its
+    /// recipes are given in the BPD file, e.g. for reporting them back.
+    ///
+    /// # Arguments
+    /// - `date`: A date of the schedule, such as the start of a phase.
+    /// - `site`: The name of the site, or `None` for the timeline.
+    pub fn get_site_local_date(&self, date: NaiveDateTime, site: Option<&str>) -> NaiveDateTime {
+        match self.get_time_zone(site) {
+            Some(x) => x.to_local(date),
+            None => date
+        }
+    }
+
+    /// Retrieve a date of the schedule, such as the start of a phase, in the local time of the
+    /// timeline, along with its offset from UTC, e.g. for exporting it to a calendar.
+    ///
+    /// # Returns
+    /// - An `Option` containing the date, or `None` if the timeline has no time zone, and so the
+    ///   offset is unknown.
+    pub fn get_date_time_with_offset(&self, date: NaiveDateTime) -> Option<DateTime<FixedOffset>> {
+        self.time_zone.as_ref().map(|x| x.get_offset_at(date).from_utc_datetime(&date))
+    }
+
+    /// Retrieve the `Recipe`s whose last phase ends after their due date.
+    pub fn get_late_recipes(&self) -> Vec<Recipe> {
+        self.recipes.iter().filter(|recipe| recipe.is_late()).cloned().collect()
+    }
+
+    /// Retrieve a human-readable report of the number of hours each member of staff is allocated
+    /// to phases in each week, from Monday to Sunday.
+    ///
+    /// # Returns
+    /// - A `String` containing one section per member of staff, in `id` order, listing the start
+    ///   of each week in which they work, the hours they work in it, and their total hours.
+    pub fn get_staff_load_report(&self) -> String {
+        let mut staff: Vec<Resource> = self.resources().into_iter()
+                                           .filter(|res| res.resource_type == ResourceType::Staff)
+                                           .collect();
+        staff.sort_by_key(|res| res.id);
+
+        let mut report: String = String::new();
+        for member in staff {
+            match member.skills.is_empty() {
+                true => report.push_str(&format!("{}\n", member.name)),
+                false => report.push_str(&format!("{} ({})\n", member.name,
+                                                  member.skills.join(", ")))
+            }
+
+            let weeks: Vec<(NaiveDateTime, f64)> = member.get_hours_worked_per_week();
+            // Weeks start on Monday in the local time of the site of the member of staff.
+            for (week_start, hours) in weeks.iter() {
+                let week_start: NaiveDateTime
+                  = self.get_site_local_date(*week_start, member.site.as_deref());
+                report.push_str(&format!("  week of {}: {:.2} hours\n",
+                                         get_date_string(week_start, self.timeline.day_zero()),
+                                         hours));
+            }
+
+            let total: f64 = weeks.iter().map(|(_, hours)| hours).sum();
+            report.push_str(&format!("  total: {:.2} hours\n", total));
+        }
+
+        report
     }
 
     pub fn resources(&self) -> Vec<Resource> {
@@ -131,10 +552,9 @@ impl ProductionSchedule {
 
     pub fn get_available_resource_by_type(&self, resource_type: ResourceType) -> Option<Resource> {
         let resources: Vec<Resource> = self.resources.clone();
-        if resources.iter().any(|x| x.resource_type == resource_type) {
+        if resources.iter().any(|x| x.is_of_type(&resource_type)) {
             return resources.into_iter()
-                            .filter(|x| x.resource_type == resource_type)
-                            .next();
+                            .find(|x| x.is_of_type(&resource_type));
         }
 
         None
@@ -171,214 +591,2283 @@ impl ProductionSchedule {
         None
     }
 
-    pub fn get_recipe_iterator(&self) -> std::slice::Iter<Recipe> {
+    pub fn get_recipe_iterator(&self) -> std::slice::Iter<'_, Recipe> {
         self.recipes.iter()
     }
 
+    /// Retrieve a `String` representing this `ProductionSchedule` in PLA format.
+    ///
+    /// # Notes
+    /// Dates are expressed as configured by the `configuration` of the timeline: either as
+    /// calendar dates, or as days since the start of the timeline.
     pub fn get_string_in_pla_format(&self) -> String {
+        let day_zero: Option<NaiveDateTime> = self.timeline.day_zero();
         let mut builder = Builder::default();
         for next_recipe in self.get_recipe_iterator() {
-            builder.append(next_recipe.get_string_in_pla_format(1));
+            let next_recipe: Recipe = self.get_recipe_in_local_time(next_recipe);
+            builder.append(next_recipe.get_string_in_pla_format_from_day_zero(1, day_zero));
 
             for next_phase in next_recipe.get_phase_iterator() {
-                builder.append(next_phase.get_string_in_pla_format(1));
+                builder.append(next_phase.get_string_in_pla_format_from_day_zero(1, day_zero));
             }
         }
 
-        let final_pla: String = builder.string().unwrap();
+        // Downtime is always shown, as it explains gaps in the schedule, whereas turnaround time is
+        // only shown on request.
+        let mut next_id: usize = self.last_id_used + 1;
+        let mut resource_recipes: Vec<Recipe> = vec![];
+        if let Some(downtime_recipe) = self.get_downtime_recipe(next_id) {
+            next_id += downtime_recipe.phases.len() + 1;
+            resource_recipes.push(downtime_recipe);
+        }
 
-        // Remove the last newline at the end of the file, as it's unnecessary
-        final_pla[..final_pla.len() - 1].to_string()
-    }
+        if self.show_turnarounds {
+            if let Some(turnaround_recipe) = self.get_turnaround_recipe(next_id) {
+                resource_recipes.push(turnaround_recipe);
+            }
+        }
 
-    pub fn write_pla_file(&self, mut output_stream: Box<dyn Write>) -> std::io::Result<()> {
-        let pla_data = self.get_string_in_pla_format();
+        for next_recipe in resource_recipes.iter() {
+            let next_recipe: Recipe = self.get_recipe_in_local_time(next_recipe);
+            builder.append(next_recipe.get_string_in_pla_format_from_day_zero(1, day_zero));
 
-        output_stream.write_all(pla_data.as_bytes())
+            for next_phase in next_recipe.get_phase_iterator() {
+                builder.append(next_phase.get_string_in_pla_format_from_day_zero(1, day_zero));
+            }
+        }
+
+        let mut final_pla: String = builder.string().unwrap();
+
+        // Remove the last newline at the end of the file, as it's unnecessary
+        if final_pla.ends_with('\n') {
+            final_pla.pop();
+        }
+
+        final_pla
     }
 
-    fn get_next_id(&mut self) -> usize {
-        self.last_id_used += 1;
+    /// Retrieve a copy of a `Recipe` whose dates are in the local time of the timeline, rather
+    /// than in the time in which phases are scheduled.
+    fn get_recipe_in_local_time(&self, recipe: &Recipe) -> Recipe {
+        let mut local_recipe: Recipe = recipe.clone();
+        local_recipe.start_date = self.get_local_date(recipe.start_date);
+        local_recipe.due_date = recipe.due_date.map(|x| self.get_local_date(x));
+        for phase in local_recipe.phases.iter_mut() {
+            phase.start_date = self.get_local_date(phase.start_date);
+        }
 
-        self.last_id_used
+        local_recipe
     }
 
-    fn verify_recipe_start_dates(&mut self) {
-        let mut new_recipe_vec: Vec<RecipeSpec> = vec![];
+    pub fn write_pla_file(&self, mut output_stream: Box<dyn Write>) -> std::io::Result<()> {
+        let pla_data = self.get_string_in_pla_format();
 
-        self.recipe_specs.clone().into_iter().for_each(|recipe_spec| {
-            match recipe_spec.start_string.clone() {
-                Some(_x) => new_recipe_vec.push(recipe_spec),
-                None => {
-                    let new_recipe_spec = RecipeSpec {
-                        name: recipe_spec.name,
-                        color_hex: recipe_spec.color_hex,
-                        phase_specs: recipe_spec.phase_specs,
-                        start_string: Some(self.timeline.start.clone())
-                    };
+        output_stream.write_all(pla_data.as_bytes())
+    }
 
-                    new_recipe_vec.push(new_recipe_spec);
+    /// Retrieve a `Recipe` containing a phase for each time a `Resource` is out of service, so
+    /// that it can be shown alongside the scheduled `Recipe`s.
+    ///
+    /// # Arguments
+    /// - `first_id`: The id of the `Recipe`. Its phases are given the ids following it.
+    ///
+    /// # Returns
+    /// - An `Option` containing the `Recipe`, or `None` if no `Resource` is ever out of service.
+    fn get_downtime_recipe(&self, first_id: usize) -> Option<Recipe> {
+        self.get_resource_recipe(first_id, "Downtime", DOWNTIME_COLOR, |res| {
+            res.downtime_periods.iter().map(|(description, period)| {
+                match description.is_empty() {
+                    true => (format!("Downtime: {}", res.name), *period),
+                    false => (format!("Downtime: {} ({})", res.name, description), *period)
                 }
-            }
-
-            self.recipe_specs = new_recipe_vec.clone();
+            }).collect()
         })
     }
 
-    fn rebuild_recipes_from_specs(&mut self) {
-        let mut recipes_vec = vec![];
-        let recipes = self.recipe_specs.clone();
-        for next_recipe_spec in recipes {
-            let recipe_start_date: NaiveDateTime = match next_recipe_spec.start_date() {
-                Ok(x) => x,
+    /// Retrieve a `Recipe` containing a phase for each time a `Resource` is being turned around
+    /// after an allocation, so that it can be shown alongside the scheduled `Recipe`s.
+    ///
+    /// # Arguments
+    /// - `first_id`: The id of the `Recipe`. Its phases are given the ids following it.
+    ///
+    /// # Returns
+    /// - An `Option` containing the `Recipe`, or `None` if no `Resource` is ever turned around.
+    fn get_turnaround_recipe(&self, first_id: usize) -> Option<Recipe> {
+        self.get_resource_recipe(first_id, "Turnaround", TURNAROUND_COLOR, |res| {
+            res.get_turnaround_periods().into_iter()
+               .map(|period| (format!("Turnaround: {}", res.name), period))
+               .collect()
+        })
+    }
 
-                // If the next line causes an error, there's no telling what we can do...
-                Err(_e) => self.timeline.start_date().unwrap()
-            };
+    /// Retrieve a `Recipe` whose phases each occupy a single `Resource` for a period, rather than
+    /// being part of the schedule itself, e.g. to show when `Resource`s are out of service.
+    ///
+    /// # Arguments
+    /// - `first_id`: The id of the `Recipe`. Its phases are given the ids following it.
+    /// - `name`: The name of the `Recipe`.
+    /// - `color_hex`: The color of the `Recipe` and its phases.
+    /// - `get_periods`: A function retrieving the description and `NaivePeriod` of each phase to
+    ///   create for a `Resource`.
+    ///
+    /// # Returns
+    /// - An `Option` containing the `Recipe`, or `None` if it would have no phases.
+    fn get_resource_recipe<F>(&self, first_id: usize, name: &str, color_hex: &str,
+                              get_periods: F) -> Option<Recipe>
+      where F: Fn(&Resource) -> Vec<(String, NaivePeriod)> {
+        let mut resources: Vec<Resource> = self.resources();
+        resources.sort_by_key(|res| res.id);
+
+        let mut next_id: usize = first_id;
+        let mut recipe: Recipe = Recipe {
+            id: first_id,
+            name: name.to_string(),
+            color: color_hex.to_string(),
+            phases: vec![],
+            start_date: NaiveDateTime::MAX,
+            due_date: None
+        };
+
+        for next_resource in resources {
+            for (description, period) in get_periods(&next_resource) {
+                next_id += 1;
+                recipe.start_date = recipe.start_date.min(period.start);
+                recipe.phases.push(PhaseInstance::new(next_id, description, color_hex.to_string(),
+                                                      period.duration(), period.start,
+                                                      vec![next_resource.clone()]));
+            }
+        }
 
-            let mut recipe_template: Recipe = Recipe {
-                id: self.get_next_id(),
-                name: next_recipe_spec.name.clone(),
-                color: next_recipe_spec.color_hex.clone(),
-                phases: vec![],
-                start_date: recipe_start_date
-            };
+        match recipe.phases.is_empty() {
+            true => None,
+            false => Some(recipe)
+        }
+    }
 
-            recipe_template.phases = self.rebuild_phases_from_specs(&next_recipe_spec);
+    /// Expand a `DowntimeSpec` into the periods during which a `Resource` is out of service.
+    ///
+    /// # Arguments
+    /// - `spec`: The `DowntimeSpec` to expand.
+    /// - `path`: The path of `spec` within the BPD file, used for error reporting.
+    ///
+    /// # Returns
+    /// - A `Result` containing either the `NaivePeriod` of each downtime window, in chronological
+    ///   order, or an `Error` describing why `spec` could not be expanded.
+    pub(crate) fn get_downtime_periods(spec: &DowntimeSpec, path: &str)
+      -> std::result::Result<Vec<NaivePeriod>, Error> {
+        let parse_date = |value: &str, field: &str| {
+            get_naive_date_time_from_string(value).map_err(|e| Error::DateParse {
+                path: format!("{}.{}", path, field),
+                value: value.to_string(),
+                source: e
+            })
+        };
+
+        let start: NaiveDateTime = parse_date(&spec.start_string[..], "start")?;
+        let duration: Duration = match (&spec.end_string, &spec.duration_string) {
+            (Some(end), _) => parse_date(&end[..], "end")? - start,
+            (None, Some(duration)) => {
+                convert_string_to_calendar_duration(&duration[..])
+                  .and_then(|x| {
+                      x.get_duration_from(start).ok_or_else(|| {
+                          "it ends beyond the latest date that can be represented".to_string()
+                      })
+                  })
+                  .and_then(get_positive_duration)
+                  .map_err(|reason| Error::DurationParse {
+                      path: format!("{}.duration", path),
+                      value: duration.clone(),
+                      reason
+                  })?
+            },
+            (None, None) => Duration::days(1)
+        };
+
+        if duration <= Duration::zero() {
+            return Err(Error::InvalidDowntime {
+                path: path.to_string(),
+                reason: "it ends before it starts".to_string()
+            });
+        }
 
-            recipes_vec.push(recipe_template);
+        let every: &str = match &spec.every_string {
+            Some(x) => &x[..],
+            None => return Ok(vec![NaivePeriod::from_start_duration(start, duration)])
+        };
+
+        let until: Option<NaiveDateTime> = match &spec.until_string {
+            Some(x) => Some(parse_date(&x[..], "until")?),
+            None => None
+        };
+
+        if until.is_none() && spec.count.is_none() {
+            return Err(Error::InvalidDowntime {
+                path: path.to_string(),
+                reason: "it repeats, but has neither an \"until\" date nor a \"count\"".to_string()
+            });
         }
 
-        self.recipes = recipes_vec;
+        let step: CalendarDuration = get_recurrence_step(every, path)?;
+        let dates: Vec<NaiveDateTime> = get_recurring_dates(start, &step, spec.count, until)
+          .map_err(|reason| Error::InvalidDowntime { path: path.to_string(), reason })?;
+
+        Ok(dates.into_iter().map(|x| NaivePeriod::from_start_duration(x, duration)).collect())
     }
 
-    /// Rebuild all `PhaseInstance's from a set of [RecipeSpec](chronogrog::recipes::RecipeSpec)
-    /// and  a [ProductionPhaseTemplate](chrono::phases::ProductionPhaseTemplate).
-    ///
-    /// This places all the necessary data into the data structures necessary to convert the
-    /// JSON specification to a PLA formatted file.
+    /// Determine the start date of each instance of a `RecipeSpec` with a `repeat` block.
     ///
     /// # Arguments
-    /// - `self` : A _mutable_ reference to this
-    ///   [ProductionSchedule](chronogrog::ProductionSchedule). It needs to be mutable because
-    ///   internal references are updated as part of this function call.
-    /// - `recipe_spec` : A borrowed reference to a [RecipeSpec](chronogrog::recipes::RecipeSpec)
-    ///    defining the containing recipe specification that will be used to construct the
-    ///    instances of production phases.
+    /// - `spec`: The `RecipeSpec` to expand. Its start date must be set.
     ///
     /// # Returns
-    /// - A `Vec` of `PhaseInstance` objects.
-    fn rebuild_phases_from_specs(&mut self, recipe_spec: &RecipeSpec) -> Vec<PhaseInstance> {
-        let mut phases: Vec<PhaseInstance> = vec![];
+    /// - A `Result` containing either the start date of each instance, in chronological order, or
+    ///   an `Error` describing why `spec` could not be expanded. A `RecipeSpec` without a `repeat`
+    ///   block has a single instance, starting at its start date.
+    pub(crate) fn get_recipe_instance_dates(spec: &RecipeSpec)
+      -> std::result::Result<Vec<NaiveDateTime>, Error> {
+        let start: NaiveDateTime = spec.start_date().map_err(|e| Error::DateParse {
+            path: format!("{}.start", recipe_path(&spec.name[..])),
+            value: spec.start_string.clone().unwrap_or_default(),
+            source: e
+        })?;
+
+        let repeat: &RepeatSpec = match &spec.repeat {
+            Some(x) => x,
+            None => return Ok(vec![start])
+        };
+
+        let path = format!("{}.repeat", recipe_path(&spec.name[..]));
+        let until: Option<NaiveDateTime> = match &repeat.until_string {
+            Some(x) => Some(get_naive_date_time_from_string(&x[..]).map_err(|e| Error::DateParse {
+                path: format!("{}.until", path),
+                value: x.clone(),
+                source: e
+            })?),
+            None => None
+        };
+
+        if until.is_none() && repeat.count.is_none() {
+            return Err(Error::InvalidRepeat {
+                path,
+                reason: "it has neither an \"until\" date nor a \"count\"".to_string()
+            });
+        }
 
-        // The start date of the next phase
-        let mut next_start_date: NaiveDateTime = recipe_spec.start_date().unwrap();
+        let step: CalendarDuration = get_recurrence_step(&repeat.every_string[..], &path[..])?;
+        let dates: Vec<NaiveDateTime> = get_recurring_dates(start, &step, repeat.count, until)
+          .map_err(|reason| Error::InvalidRepeat { path: path.clone(), reason })?;
+        if dates.is_empty() {
+            return Err(Error::InvalidRepeat {
+                path,
+                reason: "it is never brewed, as its \"count\" is 0 or its \"until\" date is \
+                         before its start".to_string()
+            });
+        }
 
-        for next_spec in recipe_spec.phase_specs.iter() {
-            let id: usize = self.get_next_id();
+        Ok(dates)
+    }
 
-            // If the duration is specified in the spec, use that duration.
-            // Otherwise, use the default duration by looking up from the template.
-            let mut dur: Option<Duration> = next_spec.duration();
-            dur = match dur {
-                Some(x) => Some(x),
-                None => {
-                    let template: ProductionPhaseTemplate = self.get_phase_by_id(&next_spec.template[..]).unwrap();
-                    template.default_duration()
+    /// Expand a `RecipeSpec` with a `repeat` block into a `RecipeSpec` for each of its instances.
+    ///
+    /// # Arguments
+    /// - `spec`: The `RecipeSpec` to expand. Its start date must be set if it is repeated.
+    /// - `instance_counts`: The number of instances of each recipe of the BPD file that is
+    ///   repeated, by name.
+    ///
+    /// # Returns
+    /// - A `Result` containing either the `RecipeSpec` of each instance, in chronological order,
+    ///   or an `Error` describing why `spec` could not be expanded. A `RecipeSpec` without a
+    ///   `repeat` block is its only instance.
+    ///
+    /// # Notes
+    /// Instance `n` is named `"<name> #n"`, counting from `1`, and its start, due and ready-by
+    /// dates, as well as the start dates of its pinned phases, are moved by as much as its start is
+    /// after the start of the first instance. A dependency of an instance on a repeated recipe,
+    /// including its own recipe, refers to the instance of that recipe with the same number, which
+    /// must exist. A recipe that is not repeated must name the instance of a repeated recipe it
+    /// depends on, e.g. `"House IPA #2"`.
+    pub(crate) fn get_recipe_instance_specs(spec: &RecipeSpec,
+                                            instance_counts: &BTreeMap<String, usize>)
+      -> std::result::Result<Vec<RecipeSpec>, Error> {
+        if spec.repeat.is_none() {
+            for (phase_index, dependency_index, recipe) in get_dependency_recipes(spec) {
+                if instance_counts.contains_key(recipe) {
+                    return Err(Error::AmbiguousRecipe {
+                        path: format!("{}.after[{}].recipe",
+                                      phase_path(&spec.name[..], phase_index), dependency_index),
+                        recipe: recipe.clone()
+                    });
                 }
-            };
-
-            // The production phase template we're going to use to construct this instance.
-            let template: ProductionPhaseTemplate = self.get_phase_by_id(&next_spec.template[..]).unwrap();
-
-            // If the description is specified in the spec, use that description.
-            // Otherwise, use the description by looking up from the template.
-            let mut description: String = next_spec.description.clone();
+            }
 
-            description = match description.is_empty()  {
-                true => {
-                    template.description.clone()
-                },
-                false => description
-            };
+            return Ok(vec![spec.clone()]);
+        }
 
-            let duration: Duration = match dur {
-                Some(x) => x,
+        let dates: Vec<NaiveDateTime> = ProductionSchedule::get_recipe_instance_dates(spec)?;
+        let start: NaiveDateTime = dates[0];
+
+        // Each instance depends on the instance of a repeated recipe with the same number.
+        for (phase_index, dependency_index, recipe) in get_dependency_recipes(spec) {
+            if instance_counts.get(recipe).is_some_and(|x| *x < dates.len()) {
+                return Err(Error::UnknownRecipe {
+                    path: format!("{}.after[{}].recipe", phase_path(&spec.name[..], phase_index),
+                                  dependency_index),
+                    recipe: format!("{} #{}", recipe, dates.len())
+                });
+            }
+        }
 
-                // Default to a single day if nothing else works
-                None => Duration::days(1)
+        // Dates have already been checked by verify_recipe_start_dates().
+        let shift_date = |date_string: &Option<String>, offset: Duration| {
+            date_string.as_ref().map(|x| {
+                (get_naive_date_time_from_string(&x[..]).unwrap() + offset)
+                  .format("%Y-%m-%d %H:%M:%S").to_string()
+            })
+        };
+
+        Ok(dates.into_iter().enumerate().map(|(index, date)| {
+            let number: usize = index + 1;
+            let offset: Duration = date - start;
+            let mut instance: RecipeSpec = RecipeSpec {
+                name: format!("{} #{}", spec.name, number),
+                start_string: Some(date.format("%Y-%m-%d %H:%M:%S").to_string()),
+                due_date_string: shift_date(&spec.due_date_string, offset),
+                ready_by_string: shift_date(&spec.ready_by_string, offset),
+                repeat: None,
+                ..spec.clone()
             };
 
-            let mut resources_used : Vec<Resource> = vec![];
-            let mut result_start_date;
-
-            // We need to look through all the resources to determine if we have to push the start
-            // date back due to resource allocation constraints.
-            for next_resource_type in template.resources_needed.clone() {
-                let requested_start_date = next_start_date.clone();
-
-                let requested_period = NaivePeriod::from_start_duration(requested_start_date,
-                                                                        duration);
-
-                result_start_date = match self.tracker
-                  .get_next_available_resource_date_for_type_over_period(&next_resource_type,
-                                                                         requested_period) {
-                  Some(date) => date,
-                  None => panic!("{} {:?}, {} {:?}", "No resources of type", next_resource_type,
-                                 "which is required by phase", template.description.clone())
-                };
+            for phase_spec in instance.phase_specs.iter_mut() {
+                phase_spec.start_string = shift_date(&phase_spec.start_string, offset);
+            }
 
-                // If we can't allocate a resource in the given timeframe, we need to push back
-                // the start date of the phase.
-                if result_start_date.timestamp() > next_start_date.timestamp() {
-                    next_start_date = result_start_date;
+            for dependency_spec in instance.phase_specs.iter_mut()
+                                           .flat_map(|x| x.after.iter_mut().flatten()) {
+                if let Some(recipe) = &dependency_spec.recipe {
+                    if instance_counts.contains_key(recipe) {
+                        dependency_spec.recipe = Some(format!("{} #{}", recipe, number));
+                    }
                 }
             }
 
-            for next_resource_type in template.resources_needed {
-                // Allocate the resource
-                let allocation_period = NaivePeriod::from_start_duration(next_start_date,
-                                                                         duration);
+            instance
+        }).collect())
+    }
 
-                let allocated_resource
-                  = self.tracker.allocate_resource_of_type_for_period(&next_resource_type,
-                                                                      allocation_period);
+    fn get_next_id(&mut self) -> usize {
+        self.last_id_used += 1;
 
-                if (allocated_resource).is_none() {
-                    panic!("Unable to allocate a resource of type {:?}", next_resource_type);
-                }
+        self.last_id_used
+    }
 
-                // Put the allocated resource into the vector
-                resources_used.push(allocated_resource.unwrap().clone());
+    /// Look up the time zones of the timeline and of each site.
+    pub(crate) fn resolve_time_zones(&mut self) -> std::result::Result<(), Error> {
+        self.time_zone = self.timeline.time_zone()?;
+        self.site_time_zones = BTreeMap::new();
+        for index in 0..self.sites.len() {
+            if let Some(x) = self.get_site_time_zone(index)? {
+                self.site_time_zones.insert(self.sites[index].name.clone(), x);
             }
-
-            phases.push(PhaseInstance::new(id, description, recipe_spec.color_hex.clone(),
-                                           duration, next_start_date, resources_used));
-            next_start_date = next_start_date + duration;
         }
 
-        // We have to actally run through the phases from the back and add the next phase id
-        // as a dependency to the previous phase id. This is a weird nuance of pla that tasks X
-        // that are dependent on some task Y are actually defined in the definition of Y, not X. It
-        // basically means you have to specify that there will be defined a task with id X, but
-        // that task hasn't been defined yet.
-        let mut phases_new : Vec<PhaseInstance> = vec![];
-        let mut prev_phase_id : usize = 0;
-        for next_phase in phases.iter().rev() {
-            let mut phase = next_phase.clone();
-            if prev_phase_id > 0 {
-                phase.add_dependency(prev_phase_id);
-            }
-
-            prev_phase_id = phase.id;
+        Ok(())
+    }
 
-            phases_new.push(phase);
+    /// Look up the time zone of a site.
+    ///
+    /// # Arguments
+    /// - `index`: The index of the site within `sites`.
+    ///
+    /// # Returns
+    /// - A `Result` containing either the `TimeZone` of the site, or `None` if it uses the time
+    ///   zone of the timeline, or an `Error` if the time zone cannot be found. Sites can only have
+    ///   a time zone if the timeline has one, as naive dates cannot be compared with them.
+    pub(crate) fn get_site_time_zone(&self, index: usize)
+      -> std::result::Result<Option<TimeZone>, Error> {
+        let timezone: &str = match &self.sites[index].timezone {
+            Some(x) => &x[..],
+            None => return Ok(None)
+        };
+
+        let path = format!("sites[{}].timezone", index);
+        if self.timeline.timezone.is_none() {
+            return Err(Error::InvalidTimeZone {
+                path,
+                reason: "a site can only have a time zone if the timeline has one".to_string()
+            });
         }
 
-        phases_new.into_iter().rev().collect()
+        TimeZone::from_name(timezone).map(Some)
+                                     .map_err(|reason| Error::InvalidTimeZone { path, reason })
+    }
+
+    /// Verify that the site a recipe or resource refers to, if any, is defined in `sites`.
+    ///
+    /// # Arguments
+    /// - `site`: The name of the site.
+    /// - `path`: The path of the recipe or resource within the BPD file, used for error reporting.
+    pub(crate) fn verify_site(&self, site: &Option<String>, path: &str)
+      -> std::result::Result<(), Error> {
+        match site {
+            Some(x) if !self.sites.iter().any(|y| y.name == *x) => Err(Error::UnknownSite {
+                path: format!("{}.site", path),
+                site: x.clone()
+            }),
+            _ => Ok(())
+        }
     }
 
-    fn track_resources(&mut self) {
-        self.resources.clone().into_iter().for_each(|e| self.tracker.track_resource(e));
+    /// Retrieve the time zone in which the dates of a site are given, or `None` if dates are
+    /// naive.
+    fn get_time_zone(&self, site: Option<&str>) -> Option<&TimeZone> {
+        site.and_then(|x| self.site_time_zones.get(x)).or(self.time_zone.as_ref())
     }
+
+    fn verify_recipe_start_dates(&mut self) -> std::result::Result<(), Error> {
+        if let Err(e) = self.timeline.start_date() {
+            return Err(Error::DateParse {
+                path: "timeline.start".to_string(),
+                value: self.timeline.start.clone(),
+                source: e
+            });
+        }
+
+        if self.timeline.timeline_configuration().is_none() {
+            return Err(Error::UnknownTimelineConfiguration {
+                path: "timeline.configuration".to_string(),
+                configuration: self.timeline.configuration.clone()
+            });
+        }
+
+        let mut new_recipe_vec: Vec<RecipeSpec> = vec![];
+
+        for recipe_spec in self.recipe_specs.clone() {
+            self.verify_site(&recipe_spec.site, &recipe_path(&recipe_spec.name[..])[..])?;
+
+            if let Err(e) = recipe_spec.due_date() {
+                return Err(Error::DateParse {
+                    path: format!("{}.dueDate", recipe_path(&recipe_spec.name[..])),
+                    value: recipe_spec.due_date_string.clone().unwrap_or_default(),
+                    source: e
+                });
+            }
+
+            if let Err(e) = recipe_spec.ready_by() {
+                return Err(Error::DateParse {
+                    path: format!("{}.readyBy", recipe_path(&recipe_spec.name[..])),
+                    value: recipe_spec.ready_by_string.clone().unwrap_or_default(),
+                    source: e
+                });
+            }
+
+            for (phase_index, phase_spec) in recipe_spec.phase_specs.iter().enumerate() {
+                if let Err(e) = phase_spec.start_date() {
+                    return Err(Error::DateParse {
+                        path: format!("{}.start", phase_path(&recipe_spec.name[..], phase_index)),
+                        value: phase_spec.start_string.clone().unwrap_or_default(),
+                        source: e
+                    });
+                }
+            }
+
+            match recipe_spec.start_string.clone() {
+                Some(x) => {
+                    if let Err(e) = recipe_spec.start_date() {
+                        return Err(Error::DateParse {
+                            path: format!("{}.start", recipe_path(&recipe_spec.name[..])),
+                            value: x,
+                            source: e
+                        });
+                    }
+
+                    new_recipe_vec.push(recipe_spec)
+                },
+                None => {
+                    // The timeline starts at the same moment everywhere, which is a different local
+                    // time at sites in other time zones.
+                    let site_time_zone: Option<&TimeZone>
+                      = recipe_spec.site.as_ref().and_then(|x| self.site_time_zones.get(x));
+                    let start_string: String = match site_time_zone {
+                        Some(x) => {
+                            let start: NaiveDateTime = self.get_schedule_date(
+                                self.timeline.start_date().unwrap(), None);
+                            x.to_local(start).format("%Y-%m-%d %H:%M:%S").to_string()
+                        },
+                        None => self.timeline.start.clone()
+                    };
+
+                    let new_recipe_spec = RecipeSpec {
+                        start_string: Some(start_string),
+                        ..recipe_spec
+                    };
+
+                    new_recipe_vec.push(new_recipe_spec);
+                }
+            }
+        }
+
+        self.recipe_specs = new_recipe_vec;
+
+        Ok(())
+    }
+
+    /// Replace each `RecipeSpec` with a `repeat` block by the `RecipeSpec`s of its instances, so
+    /// that each instance is scheduled as a recipe of its own.
+    fn expand_recurring_recipes(&mut self) -> std::result::Result<(), Error> {
+        let mut instance_counts: BTreeMap<String, usize> = BTreeMap::new();
+        for recipe_spec in self.recipe_specs.iter().filter(|x| x.repeat.is_some()) {
+            let dates = ProductionSchedule::get_recipe_instance_dates(recipe_spec)?;
+            instance_counts.insert(recipe_spec.name.clone(), dates.len());
+        }
+
+        let mut new_recipe_vec: Vec<RecipeSpec> = vec![];
+        for recipe_spec in self.recipe_specs.iter() {
+            new_recipe_vec.extend(ProductionSchedule::get_recipe_instance_specs(recipe_spec,
+                                                                                &instance_counts)?);
+        }
+
+        self.recipe_specs = new_recipe_vec;
+
+        Ok(())
+    }
+
+    fn rebuild_recipes_from_specs(&mut self) -> std::result::Result<(), Error> {
+        let recipes = self.recipe_specs.clone();
+
+        // Recipes are scheduled once all the recipes they depend on have been, but are numbered
+        // in the order in which they appear in the BPD file.
+        let dependencies: Vec<Vec<Vec<PhaseDependency>>> = recipes.iter()
+          .map(|next_recipe_spec| self.get_phase_dependencies(next_recipe_spec))
+          .collect::<std::result::Result<_, Error>>()?;
+        let order: Vec<usize> = self.get_recipe_order(&dependencies)?;
+
+        let mut recipe_ids: Vec<usize> = vec![];
+        let mut phase_ids: Vec<Vec<usize>> = vec![];
+        for next_recipe_spec in recipes.iter() {
+            recipe_ids.push(self.get_next_id());
+            phase_ids.push(next_recipe_spec.phase_specs.iter().map(|_| self.get_next_id()).collect());
+        }
+
+        let mut recipes_vec: Vec<Option<Recipe>> = vec![None; recipes.len()];
+        for recipe_index in order {
+            let next_recipe_spec = &recipes[recipe_index];
+
+            // Start dates have already been checked by verify_recipe_start_dates().
+            let site: Option<&str> = next_recipe_spec.site.as_deref();
+            let recipe_start_date: NaiveDateTime
+              = self.get_schedule_date(next_recipe_spec.start_date().unwrap(), site);
+
+            let mut recipe_template: Recipe = Recipe {
+                id: recipe_ids[recipe_index],
+                name: next_recipe_spec.name.clone(),
+                color: next_recipe_spec.color_hex.clone(),
+                phases: vec![],
+                start_date: recipe_start_date,
+                // Due dates have already been checked by verify_recipe_start_dates(). A recipe
+                // that has to be ready by a date is due at that date, unless told otherwise.
+                due_date: next_recipe_spec.due_date().unwrap()
+                                          .or(next_recipe_spec.ready_by().unwrap())
+                                          .map(|x| self.get_schedule_date(x, site))
+            };
+
+            // Ready-by dates and the start dates of pinned phases have already been checked by
+            // verify_recipe_start_dates(). Pinned phases decide when a recipe is brewed, even if it
+            // also has to be ready by a date.
+            let ready_by: Option<NaiveDateTime>
+              = next_recipe_spec.ready_by().unwrap().map(|x| self.get_schedule_date(x, site));
+            let first_pinned_start_date: Option<NaiveDateTime>
+              = next_recipe_spec.phase_specs.iter()
+                                .filter_map(|x| x.start_date().unwrap())
+                                .map(|x| self.get_schedule_date(x, site))
+                                .min();
+            let (start_date, phases) = match (first_pinned_start_date, ready_by) {
+                (Some(pinned_start_date), _) => {
+                    self.schedule_recipe_around_pinned_phases(next_recipe_spec,
+                                                              &phase_ids[recipe_index],
+                                                              &dependencies[recipe_index],
+                                                              &recipes_vec, recipe_start_date,
+                                                              pinned_start_date)?
+                },
+                (None, Some(ready_by)) => self.schedule_recipe_backward(next_recipe_spec,
+                                                                        &phase_ids[recipe_index],
+                                                                        &dependencies[recipe_index],
+                                                                        &recipes_vec,
+                                                                        recipe_start_date,
+                                                                        ready_by)?,
+                (None, None) => self.schedule_recipe_forward(next_recipe_spec,
+                                                             &phase_ids[recipe_index],
+                                                             &dependencies[recipe_index],
+                                                             &recipes_vec, recipe_start_date)?
+            };
+
+            recipe_template.start_date = start_date;
+            recipe_template.phases = phases;
+
+            // Phases that end after the due date are highlighted, so that it's clear which part of
+            // a late recipe overruns.
+            if let Some(due_date) = recipe_template.due_date {
+                recipe_template.phases.iter_mut()
+                               .filter(|phase| phase.start_date + phase.duration > due_date)
+                               .for_each(|phase| phase.color_hex = LATE_COLOR.to_string());
+            }
+
+            // Predecessors in other recipes have already been scheduled, so the dependency can be
+            // added to them right away.
+            for (phase_index, phase_dependencies) in dependencies[recipe_index].iter().enumerate() {
+                for dependency in phase_dependencies.iter() {
+                    if let Some(other_recipe_index) = dependency.recipe_index {
+                        recipes_vec[other_recipe_index].as_mut().unwrap()
+                          .phases[dependency.phase_index]
+                          .add_dependency(phase_ids[recipe_index][phase_index]);
+                    }
+                }
+            }
+
+            recipes_vec[recipe_index] = Some(recipe_template);
+        }
+
+        self.recipes = recipes_vec.into_iter().map(|x| x.unwrap()).collect();
+
+        Ok(())
+    }
+
+    /// Schedule the phases of a recipe, starting no earlier than a specific date.
+    ///
+    /// # Arguments
+    /// - `recipe_spec`: The `RecipeSpec` to schedule.
+    /// - `phase_ids`: The ids to give the phases of the recipe, in the order of its phases.
+    /// - `dependencies`: The `PhaseDependency`s of each phase of the recipe.
+    /// - `recipes`: The recipes of this `ProductionSchedule` that have already been scheduled.
+    /// - `start_date`: The date at which the recipe should start.
+    ///
+    /// # Returns
+    /// - A `Result` containing either the date at which the recipe starts and its scheduled phases,
+    ///   or an `Error` describing why they could not be scheduled.
+    ///
+    /// # Notes
+    /// If a phase can't start within its maximum gap, or can't keep the resources carried over
+    /// from the previous phase, the whole recipe is shifted later and scheduled again, starting
+    /// from the same resource allocations.
+    fn schedule_recipe_forward(&mut self, recipe_spec: &RecipeSpec, phase_ids: &[usize],
+                               dependencies: &[Vec<PhaseDependency>], recipes: &[Option<Recipe>],
+                               start_date: NaiveDateTime)
+      -> std::result::Result<(NaiveDateTime, Vec<PhaseInstance>), Error> {
+        let tracker_before_recipe: ResourceTracker = self.tracker.clone();
+        let mut recipe_start_date: NaiveDateTime = start_date;
+        let mut shift_count: usize = 0;
+        loop {
+            let attempt = self.rebuild_phases_from_specs(recipe_spec, phase_ids, dependencies,
+                                                         recipes, recipe_start_date)?;
+
+            let (error, delay): (Error, Duration) = match attempt {
+                PhaseScheduleAttempt::Scheduled(phases) => return Ok((recipe_start_date, phases)),
+                PhaseScheduleAttempt::GapExceeded { path, max_gap, delay } => {
+                    (Error::GapExceeded { path, max_gap }, delay)
+                },
+                PhaseScheduleAttempt::CarryOverBlocked { path, resource_name, delay } => {
+                    (Error::CarryOver { path, resource_name }, delay)
+                },
+                // Recipes with pinned phases are scheduled around them instead.
+                PhaseScheduleAttempt::PinMissed { path, start, .. } => {
+                    return Err(Error::PinnedPhaseUnreachable {
+                        path,
+                        start: self.get_local_date(start),
+                        earliest_start: self.get_local_date(recipe_start_date)
+                    });
+                }
+            };
+
+            shift_count += 1;
+            if shift_count > MAX_RECIPE_SHIFTS {
+                return Err(error);
+            }
+
+            self.tracker = tracker_before_recipe.clone();
+            recipe_start_date += delay;
+        }
+    }
+
+    /// Schedule the phases of a recipe around its pinned phases, which have to start at specific
+    /// dates, so that the phases before them end as late as possible.
+    ///
+    /// # Arguments
+    /// - `recipe_spec`: The `RecipeSpec` to schedule.
+    /// - `phase_ids`: The ids to give the phases of the recipe, in the order of its phases.
+    /// - `dependencies`: The `PhaseDependency`s of each phase of the recipe.
+    /// - `recipes`: The recipes of this `ProductionSchedule` that have already been scheduled.
+    /// - `earliest_start_date`: The earliest date at which the recipe is allowed to start.
+    /// - `pinned_start_date`: The date at which the earliest pinned phase of the recipe starts.
+    ///
+    /// # Returns
+    /// - A `Result` containing either the date at which the recipe starts and its scheduled phases,
+    ///   or an `Error` describing why they could not be scheduled. If the phases before a pinned
+    ///   phase cannot end by the time it starts, even when the recipe starts at
+    ///   `earliest_start_date`, this is an `Error::PinnedPhaseUnreachable`. If a pinned phase
+    ///   cannot start at its date, e.g. because its resources are taken, this is an
+    ///   `Error::PinnedPhaseUnavailable`.
+    ///
+    /// # Notes
+    /// Like [schedule_recipe_backward](ProductionSchedule::schedule_recipe_backward), the recipe is
+    /// scheduled forward from `pinned_start_date`, and then moved earlier by however much the
+    /// phases before a pinned phase overrun it, until none do. Pinned phases never move, and the
+    /// phases after them are scheduled forward from them as usual.
+    fn schedule_recipe_around_pinned_phases(&mut self, recipe_spec: &RecipeSpec,
+                                            phase_ids: &[usize],
+                                            dependencies: &[Vec<PhaseDependency>],
+                                            recipes: &[Option<Recipe>],
+                                            earliest_start_date: NaiveDateTime,
+                                            pinned_start_date: NaiveDateTime)
+      -> std::result::Result<(NaiveDateTime, Vec<PhaseInstance>), Error> {
+        let tracker_before_recipe: ResourceTracker = self.tracker.clone();
+        let mut start_date: NaiveDateTime = pinned_start_date.max(earliest_start_date);
+        let mut shift_count: usize = 0;
+        loop {
+            let attempt = self.rebuild_phases_from_specs(recipe_spec, phase_ids, dependencies,
+                                                         recipes, start_date)?;
+
+            let (error, next_start_date): (Error, NaiveDateTime) = match attempt {
+                PhaseScheduleAttempt::Scheduled(phases) => return Ok((start_date, phases)),
+                PhaseScheduleAttempt::GapExceeded { path, max_gap, delay } => {
+                    (Error::GapExceeded { path, max_gap }, start_date + delay)
+                },
+                PhaseScheduleAttempt::CarryOverBlocked { path, resource_name, delay } => {
+                    (Error::CarryOver { path, resource_name }, start_date + delay)
+                },
+                PhaseScheduleAttempt::PinMissed { path, start, delay } => {
+                    let error = Error::PinnedPhaseUnreachable {
+                        path,
+                        start: self.get_local_date(start),
+                        earliest_start: self.get_local_date(earliest_start_date)
+                    };
+
+                    match start_date == earliest_start_date {
+                        true => {
+                            self.tracker = tracker_before_recipe;
+                            return Err(error);
+                        },
+                        false => (error, (start_date - delay).max(earliest_start_date))
+                    }
+                }
+            };
+
+            shift_count += 1;
+            if shift_count > MAX_RECIPE_SHIFTS {
+                self.tracker = tracker_before_recipe;
+                return Err(error);
+            }
+
+            self.tracker = tracker_before_recipe.clone();
+            start_date = next_start_date;
+        }
+    }
+
+    /// Schedule the phases of a recipe so that its last phase ends by a specific date, starting it
+    /// as late as possible.
+    ///
+    /// # Arguments
+    /// - `recipe_spec`: The `RecipeSpec` to schedule.
+    /// - `phase_ids`: The ids to give the phases of the recipe, in the order of its phases.
+    /// - `dependencies`: The `PhaseDependency`s of each phase of the recipe.
+    /// - `recipes`: The recipes of this `ProductionSchedule` that have already been scheduled.
+    /// - `earliest_start_date`: The earliest date at which the recipe is allowed to start.
+    /// - `ready_by`: The date by which the last phase of the recipe has to end.
+    ///
+    /// # Returns
+    /// - A `Result` containing either the date at which the recipe starts and its scheduled phases,
+    ///   or an `Error` describing why they could not be scheduled. If the recipe cannot end by
+    ///   `ready_by`, even when started at `earliest_start_date`, this is an
+    ///   `Error::ReadyByUnreachable`.
+    ///
+    /// # Notes
+    /// The recipe is scheduled forward from `ready_by`, and then moved earlier by however much it
+    /// overruns, until it no longer does. This way, each phase follows the same duration,
+    /// dependency and resource rules as in a recipe with a start date.
+    fn schedule_recipe_backward(&mut self, recipe_spec: &RecipeSpec, phase_ids: &[usize],
+                                dependencies: &[Vec<PhaseDependency>], recipes: &[Option<Recipe>],
+                                earliest_start_date: NaiveDateTime, ready_by: NaiveDateTime)
+      -> std::result::Result<(NaiveDateTime, Vec<PhaseInstance>), Error> {
+        let tracker_before_recipe: ResourceTracker = self.tracker.clone();
+        let mut start_date: NaiveDateTime = ready_by.max(earliest_start_date);
+        let mut shift_count: usize = 0;
+        loop {
+            let (actual_start_date, phases) = self.schedule_recipe_forward(recipe_spec, phase_ids,
+                                                                           dependencies, recipes,
+                                                                           start_date)?;
+            let end_date: NaiveDateTime = phases.iter()
+                                                .map(|phase| phase.start_date + phase.duration)
+                                                .max()
+                                                .unwrap_or(actual_start_date);
+
+            if end_date <= ready_by {
+                return Ok((actual_start_date, phases));
+            }
+
+            self.tracker = tracker_before_recipe.clone();
+            if start_date == earliest_start_date {
+                let site: Option<&str> = recipe_spec.site.as_deref();
+                return Err(Error::ReadyByUnreachable {
+                    path: format!("{}.readyBy", recipe_path(&recipe_spec.name[..])),
+                    ready_by: self.get_site_local_date(ready_by, site),
+                    earliest_start: self.get_site_local_date(earliest_start_date, site),
+                    earliest_ready: self.get_site_local_date(end_date, site)
+                });
+            }
+
+            // If moving the recipe earlier keeps running into other allocations, try the earliest
+            // start right away, so that the recipe either fits or is reported as unreachable.
+            shift_count += 1;
+            start_date = match shift_count >= MAX_RECIPE_SHIFTS {
+                true => earliest_start_date,
+                false => (start_date - (end_date - ready_by)).max(earliest_start_date)
+            };
+        }
+    }
+
+    /// Determine the order in which recipes should be scheduled, so that each recipe is scheduled
+    /// after all recipes containing predecessors of its phases.
+    ///
+    /// Recipes that do not depend on each other are scheduled in the order given by
+    /// [recipe_order](ProductionSchedule::recipe_order).
+    ///
+    /// # Arguments
+    /// - `dependencies`: The `PhaseDependency`s of each phase of each recipe, in the order of the
+    ///   recipes in this `ProductionSchedule`.
+    ///
+    /// # Returns
+    /// - A `Result` containing either the indices of the recipes in the order in which they should
+    ///   be scheduled, or an `Error::RecipeCycle` if the recipes depend on each other in a cycle.
+    pub(crate) fn get_recipe_order(&self, dependencies: &[Vec<Vec<PhaseDependency>>])
+      -> std::result::Result<Vec<usize>, Error> {
+        get_topological_order(&self.recipe_order(), |index| {
+            dependencies[index].iter().flatten().filter_map(|x| x.recipe_index).collect()
+        }).map_err(|cycle| Error::RecipeCycle {
+            path: "recipes".to_string(),
+            recipes: cycle.into_iter().map(|index| self.recipe_specs[index].name.clone()).collect()
+        })
+    }
+
+    /// Determine the `Duration` of a phase.
+    ///
+    /// If the duration is specified in the `PhaseInstanceSpec`, that duration is used. Otherwise,
+    /// the default duration of the `ProductionPhaseTemplate` is used. If neither is specified,
+    /// the phase lasts a single day.
+    ///
+    /// # Arguments
+    /// - `spec`: The `PhaseInstanceSpec` describing the phase.
+    /// - `template`: The `ProductionPhaseTemplate` the phase is constructed from.
+    /// - `path`: The path of `spec` within the BPD file, used for error reporting.
+    ///
+    /// # Returns
+    /// - A `Result` containing either the `CalendarDuration` of the phase, whose length depends on
+    ///   the date the phase starts, or an `Error::DurationParse` if a non-empty duration string
+    ///   could not be parsed, or is not longer than zero.
+    pub(crate) fn get_phase_duration(spec: &PhaseInstanceSpec, template: &ProductionPhaseTemplate,
+                          path: &str) -> std::result::Result<CalendarDuration, Error> {
+        if !spec.duration_string.is_empty() {
+            return Self::parse_phase_duration(&spec.duration_string[..],
+                                              &format!("{}.duration", path)[..]);
+        }
+
+        if !template.default_duration_string().is_empty() {
+            let path: String = format!("{}.defaultDuration", template_path(&template.id[..]));
+            return Self::parse_phase_duration(template.default_duration_string(), &path[..]);
+        }
+
+        // Default to a single day if nothing else works
+        Ok(CalendarDuration::new(0, Duration::days(1)))
+    }
+
+    /// Parse the duration of a phase, or the default duration of a phase template.
+    ///
+    /// # Arguments
+    /// - `value`: The duration string, e.g. `33d` or `1mo 2w`.
+    /// - `path`: The path of `value` within the BPD file, used for error reporting.
+    ///
+    /// # Returns
+    /// - A `Result` containing either the `CalendarDuration` described by `value`, or an
+    ///   `Error::DurationParse` if `value` cannot be parsed, or does not last longer than zero.
+    pub(crate) fn parse_phase_duration(value: &str, path: &str)
+      -> std::result::Result<CalendarDuration, Error> {
+        convert_string_to_calendar_duration(value)
+          .and_then(|x| get_positive_duration(x.approximate()).map(|_| x))
+          .map_err(|reason| Error::DurationParse {
+              path: path.to_string(),
+              value: value.to_string(),
+              reason
+          })
+    }
+
+    /// Verify that a pinned phase can start at its pinned date, as far as the working calendar and
+    /// the resources allocated so far are concerned.
+    ///
+    /// # Arguments
+    /// - `recipe_spec`: The `RecipeSpec` containing the phase.
+    /// - `templates`: The `ProductionPhaseTemplate` of each phase in `recipe_spec`.
+    /// - `durations`: The `CalendarDuration` of each phase in `recipe_spec`.
+    /// - `phase_index`: The index of the phase within `recipe_spec`.
+    /// - `start_date`: The date at which the phase is pinned to start.
+    /// - `batch_size`: The batch size of the recipe, if specified.
+    ///
+    /// # Returns
+    /// - A `Result` that is either empty, or an `Error::PinnedPhaseUnavailable` explaining why the
+    ///   phase cannot start at `start_date`.
+    ///
+    /// # Notes
+    /// This is checked before any phase of the recipe is allocated. Resources carried over from
+    /// the previous phase are checked once that phase is scheduled.
+    fn verify_pinned_phase_is_available(&mut self, recipe_spec: &RecipeSpec,
+                                        templates: &[ProductionPhaseTemplate],
+                                        durations: &[CalendarDuration], phase_index: usize,
+                                        start_date: NaiveDateTime, batch_size: Option<&Volume>)
+      -> std::result::Result<(), Error> {
+        let template: &ProductionPhaseTemplate = &templates[phase_index];
+        let local_start_date: NaiveDateTime = self.get_local_date(start_date);
+        let unavailable = |reason: String| Error::PinnedPhaseUnavailable {
+            path: phase_path(&recipe_spec.name[..], phase_index),
+            start: local_start_date,
+            reason
+        };
+
+        let working_time: Option<WorkingTime> = match template.is_staffed() {
+            true => self.working_time.as_ref().map(|x| {
+                x.in_time_zone(self.get_time_zone(recipe_spec.site.as_deref()))
+            }),
+            false => None
+        };
+
+        let path: String = phase_path(&recipe_spec.name[..], phase_index);
+        let duration: Duration = durations[phase_index].get_duration_from(start_date)
+                                   .ok_or_else(|| Error::DateOutOfRange { path: path.clone() })?;
+        let elapsed_duration: Duration = match &working_time {
+            Some(x) => match x.get_next_working_date(start_date) == Some(start_date) {
+                true => x.get_end_of_work(start_date, duration).unwrap_or(start_date) - start_date,
+                false => return Err(unavailable("it is outside of working time".to_string()))
+            },
+            None => duration
+        };
+
+        let spec: &PhaseInstanceSpec = &recipe_spec.phase_specs[phase_index];
+        let carry_over: Vec<ResourceType> = spec.carry_over(template);
+        let resources_needed: Vec<ResourceType> = spec.resources_needed(template);
+        let assigned_resources: Vec<(Resource, ResourceType)>
+          = ProductionSchedule::get_assigned_resources(&self.tracker, spec, &resources_needed,
+                                                       &carry_over, &path[..])?;
+        for (next_resource, next_resource_type) in assigned_resources.iter() {
+            let hold_duration = ProductionSchedule::get_hold_duration(recipe_spec, templates,
+                                                                      durations, phase_index,
+                                                                      start_date, elapsed_duration,
+                                                                      next_resource_type);
+            let requested_period = get_period(start_date, hold_duration, &path[..])?;
+            if next_resource.is_allocated_over_period(requested_period) {
+                return Err(unavailable(format!("\"{}\" is taken or out of service then",
+                                               next_resource.name)));
+            }
+        }
+
+        for resource_type in resources_needed.iter() {
+            // Missing resource types are reported when the phase is scheduled.
+            if carry_over.contains(resource_type)
+               || assigned_resources.iter().any(|(_, x)| x == resource_type)
+               || !self.tracker.has_resource_of_type(resource_type) {
+                continue;
+            }
+
+            let hold_duration = ProductionSchedule::get_hold_duration(recipe_spec, templates,
+                                                                      durations, phase_index,
+                                                                      start_date, elapsed_duration,
+                                                                      resource_type);
+            let requested_period = get_period(start_date, hold_duration, &path[..])?;
+            let is_available: bool = self.tracker
+              .get_next_available_date_for_resources_of_type_over_period(resource_type,
+                                                                         requested_period,
+                                                                         batch_size)
+              .is_some_and(|x| x == start_date);
+
+            if !is_available {
+                return Err(unavailable(format!("every resource of type {:?} is taken or out of \
+                                                service then", resource_type)));
+            }
+        }
+
+        if !template.staff_needed.is_empty() {
+            let staff_periods: Vec<NaivePeriod>
+              = ProductionSchedule::get_staff_periods(working_time.as_ref(), start_date, duration);
+            if self.tracker.get_staff_ids_for_periods(&template.staff_needed,
+                                                      &staff_periods).is_none() {
+                return Err(unavailable("not enough of the staff needed are free then".to_string()));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Retrieve copies of the `Resource`s that are assigned to a phase by name or id, along with
+    /// the resource type each of them is used as.
+    ///
+    /// # Arguments
+    /// - `tracker`: The `ResourceTracker` tracking the resources.
+    /// - `spec`: The `PhaseInstanceSpec` of the phase.
+    /// - `resources_needed`: The resource types the phase needs.
+    /// - `carry_over`: The resource types the phase carries over from the previous phase.
+    /// - `path`: The path of the phase within the BPD file, used for error reporting.
+    ///
+    /// # Returns
+    /// - A `Result` containing either the assigned `Resource`s, in the order in which they are
+    ///   listed, or an `Error::UnknownResource` for the first one that is not defined.
+    ///
+    /// # Notes
+    /// Each `Resource` is used as one of its own types, if the phase needs it, or otherwise as the
+    /// first type needed that it is a substitute for, and that no other assigned `Resource` is used
+    /// as. It also fills any other types of its own that the phase needs, without being returned
+    /// again for them. A resource that is listed more than once is only returned once. Resources
+    /// used as a type that is carried over from the previous phase are not returned, as that phase
+    /// decides which of them are used.
+    pub(crate) fn get_assigned_resources(tracker: &ResourceTracker, spec: &PhaseInstanceSpec,
+                                         resources_needed: &[ResourceType],
+                                         carry_over: &[ResourceType], path: &str)
+      -> std::result::Result<Vec<(Resource, ResourceType)>, Error> {
+        let mut assigned_resources: Vec<(Resource, ResourceType)> = vec![];
+        for (index, selector) in spec.resources.iter().enumerate() {
+            let res: &Resource = match tracker.find_resource(selector) {
+                Some(x) => x,
+                None => return Err(Error::UnknownResource {
+                    path: format!("{}.resources[{}]", path, index),
+                    resource: selector.clone()
+                })
+            };
+
+            if assigned_resources.iter().any(|(x, _)| x.id == res.id) {
+                continue;
+            }
+
+            let unfilled_types: Vec<&ResourceType> = resources_needed.iter()
+              .filter(|x| !carry_over.contains(x))
+              .filter(|x| !assigned_resources.iter().any(|(y, z)| z == *x || y.is_of_type(x)))
+              .collect();
+            let resource_type: ResourceType = unfilled_types.iter()
+              .find(|x| res.is_of_type(x))
+              .or_else(|| unfilled_types.iter().find(|x| tracker.is_resource_usable_as(res, x)))
+              .map_or_else(|| res.resource_type.clone(), |x| (*x).clone());
+
+            if !carry_over.contains(&resource_type) {
+                assigned_resources.push((res.clone(), resource_type));
+            }
+        }
+
+        Ok(assigned_resources)
+    }
+
+    /// Retrieve the ids of the `Resource`s that a phase prefers, by name or id, most preferred
+    /// first.
+    ///
+    /// # Returns
+    /// - A `Result` containing either the ids, or an `Error::UnknownResource` for the first
+    ///   preferred resource that is not defined.
+    pub(crate) fn get_preferred_resource_ids(tracker: &ResourceTracker, spec: &PhaseInstanceSpec,
+                                             path: &str)
+      -> std::result::Result<Vec<usize>, Error> {
+        spec.preferred_resources.iter().enumerate().map(|(index, selector)| {
+            tracker.find_resource(selector)
+                   .map(|res| res.id)
+                   .ok_or_else(|| Error::UnknownResource {
+                       path: format!("{}.preferredResources[{}]", path, index),
+                       resource: selector.clone()
+                   })
+        }).collect()
+    }
+
+    /// Determine how long a resource of a given type, allocated for a phase, is needed.
+    ///
+    /// This is the duration of the phase itself, plus the duration of every directly following
+    /// phase that carries over resources of that type.
+    ///
+    /// # Arguments
+    /// - `recipe_spec`: The `RecipeSpec` containing the phase.
+    /// - `templates`: The `ProductionPhaseTemplate` of each phase in `recipe_spec`.
+    /// - `durations`: The `CalendarDuration` of each phase in `recipe_spec`.
+    /// - `phase_index`: The index of the phase within `recipe_spec`.
+    /// - `start_date`: The date at which the phase starts.
+    /// - `duration`: The `Duration` of the phase itself.
+    /// - `resource_type`: The `ResourceType` of the resource.
+    ///
+    /// # Notes
+    /// Each following phase is assumed to start as soon as the one before it ends, which decides
+    /// how long any calendar months in its duration last.
+    #[allow(clippy::too_many_arguments)]
+    fn get_hold_duration(recipe_spec: &RecipeSpec, templates: &[ProductionPhaseTemplate],
+                         durations: &[CalendarDuration], phase_index: usize,
+                         start_date: NaiveDateTime, duration: Duration,
+                         resource_type: &ResourceType) -> Option<Duration> {
+        let mut hold_duration: Duration = duration;
+
+        for next_index in (phase_index + 1)..recipe_spec.phase_specs.len() {
+            let carry_over = recipe_spec.phase_specs[next_index].carry_over(&templates[next_index]);
+            if !carry_over.contains(resource_type) {
+                break;
+            }
+
+            let next_start_date: NaiveDateTime = start_date.checked_add_signed(hold_duration)?;
+            hold_duration = hold_duration.checked_add(
+                &durations[next_index].get_duration_from(next_start_date)?)?;
+        }
+
+        Some(hold_duration)
+    }
+
+    /// Rebuild all `PhaseInstance's from a set of [RecipeSpec](chronogrog::recipes::RecipeSpec)
+    /// and  a [ProductionPhaseTemplate](chrono::phases::ProductionPhaseTemplate).
+    ///
+    /// This places all the necessary data into the data structures necessary to convert the
+    /// JSON specification to a PLA formatted file.
+    ///
+    /// # Arguments
+    /// - `self` : A _mutable_ reference to this
+    ///   [ProductionSchedule](chronogrog::ProductionSchedule). It needs to be mutable because
+    ///   internal references are updated as part of this function call.
+    /// - `recipe_spec` : A borrowed reference to a [RecipeSpec](chronogrog::recipes::RecipeSpec)
+    ///   defining the containing recipe specification that will be used to construct the
+    ///   instances of production phases.
+    /// - `ids`: The id of each phase of `recipe_spec`.
+    /// - `dependencies`: The `PhaseDependency`s of each phase of `recipe_spec`.
+    /// - `recipes`: The `Recipe`s scheduled so far, in the order of their `RecipeSpec`s in this
+    ///   `ProductionSchedule`. Every recipe containing a predecessor of a phase of `recipe_spec`
+    ///   must already be scheduled.
+    ///
+    /// # Returns
+    /// - A `Result` containing either a `Vec` of `PhaseInstance` objects, or an `Error`
+    ///   describing why the phases could not be scheduled.
+    fn rebuild_phases_from_specs(&mut self, recipe_spec: &RecipeSpec, ids: &[usize],
+                                 dependencies: &[Vec<PhaseDependency>],
+                                 recipes: &[Option<Recipe>], recipe_start_date: NaiveDateTime)
+      -> std::result::Result<PhaseScheduleAttempt, Error> {
+        // Phases are scheduled once all of their predecessors have been.
+        let order: Vec<usize> = ProductionSchedule::get_phase_order(recipe_spec, dependencies)?;
+
+        let mut phases: Vec<Option<PhaseInstance>> = vec![None; recipe_spec.phase_specs.len()];
+
+        // The resources used by each phase, along with the resource type each of them is used as,
+        // and the start date of their allocation.
+        let mut phase_allocations: Vec<Vec<(Resource, ResourceType, NaiveDateTime)>>
+          = vec![vec![]; recipe_spec.phase_specs.len()];
+
+        let batch_size: Option<Volume> = match recipe_spec.batch_size() {
+            Ok(x) => x,
+            Err(value) => return Err(Error::VolumeParse {
+                path: format!("{}.batchSize", recipe_path(&recipe_spec.name[..])),
+                value
+            })
+        };
+
+        // Resolve the template and duration of every phase up front, so that resources that are
+        // carried over into later phases can be reserved for all of those phases at once.
+        let mut templates: Vec<ProductionPhaseTemplate> = vec![];
+        let mut durations: Vec<CalendarDuration> = vec![];
+        let mut gaps: Vec<(Duration, Option<Duration>)> = vec![];
+        for (phase_index, next_spec) in recipe_spec.phase_specs.iter().enumerate() {
+            let path = phase_path(&recipe_spec.name[..], phase_index);
+
+            let template: ProductionPhaseTemplate = match self.get_phase_by_id(&next_spec.template[..]) {
+                Some(x) => x,
+                None => return Err(Error::UnknownTemplate {
+                    path: format!("{}.template", path),
+                    template: next_spec.template.clone()
+                })
+            };
+
+            durations.push(ProductionSchedule::get_phase_duration(next_spec, &template,
+                                                                  &path[..])?);
+            templates.push(template);
+            gaps.push(ProductionSchedule::get_phase_gaps(next_spec, &path[..])?);
+        }
+
+        // Pinned phases cannot move, so if other recipes are in their way, there's no point in
+        // scheduling the phases before them.
+        for (phase_index, next_spec) in recipe_spec.phase_specs.iter().enumerate() {
+            // Start dates of pinned phases have already been checked by
+            // verify_recipe_start_dates().
+            if let Some(pinned_start_date) = next_spec.start_date().unwrap() {
+                let pinned_start_date: NaiveDateTime
+                  = self.get_schedule_date(pinned_start_date, recipe_spec.site.as_deref());
+                self.verify_pinned_phase_is_available(recipe_spec, &templates, &durations,
+                                                      phase_index, pinned_start_date,
+                                                      batch_size.as_ref())?;
+            }
+        }
+
+        for phase_index in order {
+            let next_spec: &PhaseInstanceSpec = &recipe_spec.phase_specs[phase_index];
+            let id: usize = ids[phase_index];
+            let path = phase_path(&recipe_spec.name[..], phase_index);
+
+            // The production phase template we're going to use to construct this instance.
+            let template: ProductionPhaseTemplate = templates[phase_index].clone();
+
+            // If the description is specified in the spec, use that description.
+            // Otherwise, use the description by looking up from the template.
+            let mut description: String = next_spec.description.clone();
+
+            description = match description.is_empty()  {
+                true => {
+                    template.description.clone()
+                },
+                false => description
+            };
+
+            // Calendar months in the duration of the phase last as long as the months in which it
+            // runs, which is only known once it has a start date.
+            let mut duration: Duration = durations[phase_index].approximate();
+
+            // The phase starts as soon as all of its predecessors allow it to.
+            let mut next_start_date: NaiveDateTime = recipe_start_date;
+            for dependency in dependencies[phase_index].iter() {
+                let predecessor: &PhaseInstance = match dependency.recipe_index {
+                    Some(x) => &recipes[x].as_ref().unwrap().phases[dependency.phase_index],
+                    None => phases[dependency.phase_index].as_ref().unwrap()
+                };
+                let earliest_start_date = dependency.dependency_type.get_earliest_start_date(
+                    predecessor.start_date, predecessor.duration, duration, dependency.lag);
+
+                if earliest_start_date > next_start_date {
+                    next_start_date = earliest_start_date;
+                }
+            }
+
+            // The gap is measured from the time at which the predecessors allow the phase to
+            // start.
+            let (min_gap, max_gap) = gaps[phase_index];
+            let dependency_start_date: NaiveDateTime = next_start_date;
+            next_start_date += min_gap;
+
+            // A pinned phase starts at its pinned date, and nowhere else. If its predecessors don't
+            // allow it to start by then, the whole recipe has to start earlier instead. Start
+            // dates of pinned phases have already been checked by verify_recipe_start_dates().
+            let pinned_start_date: Option<NaiveDateTime>
+              = next_spec.start_date().unwrap()
+                         .map(|x| self.get_schedule_date(x, recipe_spec.site.as_deref()));
+            if let Some(pinned_start_date) = pinned_start_date {
+                if next_start_date > pinned_start_date {
+                    return Ok(PhaseScheduleAttempt::PinMissed {
+                        path,
+                        start: pinned_start_date,
+                        delay: next_start_date - pinned_start_date
+                    });
+                }
+
+                next_start_date = pinned_start_date;
+            }
+
+            // The resources used by the previous phase, which can be carried over. Carrying over
+            // resources implies that the previous phase has already been scheduled.
+            let previous_allocations: Vec<(Resource, ResourceType, NaiveDateTime)>
+              = match phase_index {
+                0 => vec![],
+                _ => phase_allocations[phase_index - 1].clone()
+            };
+
+            let mut allocations: Vec<(Resource, ResourceType, NaiveDateTime)> = vec![];
+
+            // Resources that are carried over from the previous phase are not allocated anew, but
+            // instead have their allocation for the previous phase extended over this phase.
+            let carry_over: Vec<ResourceType> = next_spec.carry_over(&template);
+            for next_resource_type in carry_over.iter() {
+                if !previous_allocations.iter().any(|(_, x, _)| x == next_resource_type) {
+                    return Err(Error::NothingToCarryOver {
+                        path,
+                        resource_type: next_resource_type.clone()
+                    });
+                }
+            }
+
+            let resources_needed: Vec<ResourceType> = next_spec.resources_needed(&template);
+
+            // Resources assigned to the phase by name or id take the place of the resources of
+            // their type that would otherwise be allocated.
+            let assigned_resources: Vec<(Resource, ResourceType)>
+              = ProductionSchedule::get_assigned_resources(&self.tracker, next_spec,
+                                                           &resources_needed, &carry_over,
+                                                           &path[..])?;
+            let mut preferred_ids: Vec<usize>
+              = ProductionSchedule::get_preferred_resource_ids(&self.tracker, next_spec,
+                                                               &path[..])?;
+            if let Some(x) = self.resource_choices.get(&id) {
+                preferred_ids.insert(0, *x);
+            }
+
+            let resources_to_allocate: Vec<&ResourceType> = resources_needed.iter()
+              .filter(|x| !carry_over.contains(x))
+              .filter(|x| !assigned_resources.iter().any(|(y, z)| z == *x || y.is_of_type(x)))
+              .collect();
+
+            for next_resource_type in resources_to_allocate.iter() {
+                if !self.tracker.has_resource_of_type(next_resource_type) {
+                    return Err(Error::MissingResourceType {
+                        path,
+                        resource_type: (*next_resource_type).clone()
+                    });
+                }
+            }
+
+            // We need to look through all the resources to determine if we have to push the start
+            // date back due to resource allocation constraints. Whenever one resource type pushes
+            // the start date back, the types checked before it may no longer be free, so we keep
+            // checking until all of them are free at the same time. As the start date only ever
+            // moves later, and every allocation ends eventually, this always settles.
+            //
+            // Staffed phases can only start during working time, and only progress during working
+            // time, so they take longer than their duration whenever they span non-working time.
+            let working_time: Option<WorkingTime> = match template.is_staffed() {
+                true => self.working_time.as_ref().map(|x| {
+                    x.in_time_zone(self.get_time_zone(recipe_spec.site.as_deref()))
+                }),
+                false => None
+            };
+            let working_time: Option<&WorkingTime> = working_time.as_ref();
+
+            ProductionSchedule::verify_staff_needed(&self.tracker, &template, &path[..])?;
+
+            // Once every allocation of staff is over, and a week has passed for their hours to
+            // start over, waiting any longer for staff doesn't help.
+            let staff_search_limit: NaiveDateTime = self.tracker.get_end_of_staff_allocations()
+                                                                .map_or(next_start_date,
+                                                                        |x| x.max(next_start_date))
+                                                      + Duration::weeks(1);
+
+            // How long the phase lasts from its start date, including any non-working time.
+            let mut elapsed_duration: Duration = duration;
+
+            // When resources can stand in for other types, a single resource may be the only one
+            // of several types the phase needs, but it can only fill one of them.
+            if self.tracker.has_substitutes() {
+                let assigned_ids: Vec<usize> = assigned_resources.iter()
+                                                                 .map(|(res, _)| res.id)
+                                                                 .collect();
+                if let Some(x) = self.tracker.find_unfillable_resource_type(&resources_to_allocate,
+                                                                            batch_size.as_ref(),
+                                                                            &assigned_ids) {
+                    return Err(Error::Allocation { path, resource_type: x });
+                }
+            }
+
+            let mut is_settled: bool = false;
+            while !is_settled {
+                is_settled = true;
+
+                let out_of_range = || Error::DateOutOfRange { path: path.clone() };
+                duration = durations[phase_index].get_duration_from(next_start_date)
+                                                 .ok_or_else(out_of_range)?;
+                elapsed_duration = duration;
+
+                if let Some(working_time) = working_time {
+                    let no_working_time = move || Error::InvalidCalendar {
+                        path: "calendar".to_string(),
+                        reason: format!("there is no working time within ten years of {}",
+                                        next_start_date)
+                    };
+
+                    next_start_date = working_time.get_next_working_date(next_start_date)
+                                                  .ok_or_else(no_working_time)?;
+                    duration = durations[phase_index].get_duration_from(next_start_date)
+                                                     .ok_or_else(out_of_range)?;
+                    elapsed_duration = working_time.get_end_of_work(next_start_date, duration)
+                                                   .ok_or_else(no_working_time)?
+                                         - next_start_date;
+                }
+
+                // Each type is checked against the resources the types before it would take, as
+                // a resource that can stand in for several types only fills one of them. Those
+                // resources are taken on a copy of the tracker, which is only needed if there are
+                // substitutes at all.
+                let mut phase_tracker: Option<ResourceTracker> = match self.tracker
+                                                                           .has_substitutes() {
+                    true => Some(self.tracker.clone()),
+                    false => None
+                };
+                let mut phase_resources: Vec<Resource> = vec![];
+                if let Some(tracker) = phase_tracker.as_mut() {
+                    for (next_resource, next_resource_type) in assigned_resources.iter() {
+                        let hold_duration = ProductionSchedule::get_hold_duration(
+                            recipe_spec, &templates, &durations, phase_index, next_start_date,
+                            elapsed_duration, next_resource_type);
+                        tracker.allocate_resource_for_period(
+                            next_resource.id,
+                            get_period(next_start_date, hold_duration, &path[..])?);
+                    }
+                }
+
+                let is_tracking_phase: bool = phase_tracker.is_some();
+                for next_resource_type in resources_to_allocate.iter() {
+                    let tracker: &mut ResourceTracker = match phase_tracker.as_mut() {
+                        Some(x) => x,
+                        None => &mut self.tracker
+                    };
+
+                    // A resource of several types that the phase needs fills all of them.
+                    if phase_resources.iter().any(|res| {
+                        res.is_of_type(next_resource_type)
+                          && res.can_hold_batch(batch_size.as_ref())
+                    }) {
+                        continue;
+                    }
+
+                    // Resources carried over into the following phases are needed for all of them.
+                    let hold_duration = ProductionSchedule::get_hold_duration(recipe_spec,
+                                                                              &templates,
+                                                                              &durations,
+                                                                              phase_index,
+                                                                              next_start_date,
+                                                                              elapsed_duration,
+                                                                              next_resource_type);
+                    let requested_period = get_period(next_start_date, hold_duration, &path[..])?;
+
+                    let result_start_date = match tracker
+                      .get_next_available_date_for_resources_of_type_over_period(
+                          next_resource_type, requested_period, batch_size.as_ref()) {
+                      Some(date) => date,
+                      None => return Err(Error::InsufficientCapacity {
+                          path,
+                          resource_type: (*next_resource_type).clone(),
+                          batch_size: batch_size.unwrap()
+                      })
+                    };
+
+                    if result_start_date == next_start_date && is_tracking_phase {
+                        let taken_resources = tracker.allocate_resources_of_type_for_period(
+                            next_resource_type, requested_period, batch_size.as_ref());
+                        phase_resources.extend(taken_resources.unwrap_or_default());
+                    }
+
+                    // If we can't allocate a resource in the given timeframe, we need to push back
+                    // the start date of the phase.
+                    if result_start_date > next_start_date {
+                        // Pinned phases have been checked against other recipes already, so
+                        // only the earlier phases of this one can be in the way.
+                        if pinned_start_date.is_some() {
+                            return Ok(PhaseScheduleAttempt::PinMissed {
+                                path,
+                                start: next_start_date,
+                                delay: result_start_date - next_start_date
+                            });
+                        }
+
+                        next_start_date = result_start_date;
+                        is_settled = false;
+
+                        // The resources taken on the copy of the tracker were taken for the old
+                        // start date.
+                        if is_tracking_phase {
+                            break;
+                        }
+                    }
+                }
+
+                // Staff are only needed for the working time of the phase, and only become free
+                // when one of their allocations ends, or when their working hours start over.
+                if is_settled && !template.staff_needed.is_empty() {
+                    let staff_periods: Vec<NaivePeriod>
+                      = ProductionSchedule::get_staff_periods(working_time, next_start_date,
+                                                              duration);
+                    if self.tracker.get_staff_ids_for_periods(&template.staff_needed,
+                                                              &staff_periods).is_none() {
+                        if pinned_start_date.is_some() {
+                            return Ok(PhaseScheduleAttempt::PinMissed {
+                                path,
+                                delay: self.tracker.get_next_staff_change_date(next_start_date)
+                                         - next_start_date,
+                                start: next_start_date
+                            });
+                        }
+
+                        if next_start_date > staff_search_limit {
+                            return Err(Error::InsufficientStaff { path });
+                        }
+
+                        next_start_date = self.tracker.get_next_staff_change_date(next_start_date);
+                        is_settled = false;
+                    }
+                }
+            }
+
+            let staff_periods: Vec<NaivePeriod>
+              = ProductionSchedule::get_staff_periods(working_time, next_start_date, duration);
+            let duration: Duration = elapsed_duration;
+
+            // If the phase has to wait too long for its resources, the whole recipe has to start
+            // later instead.
+            if let Some(max_gap) = max_gap {
+                let gap: Duration = next_start_date - dependency_start_date;
+                if gap > max_gap {
+                    return Ok(PhaseScheduleAttempt::GapExceeded {
+                        path,
+                        max_gap: next_spec.max_gap_string.clone(),
+                        delay: gap - max_gap
+                    });
+                }
+            }
+
+            // Assigned resources never hold up the phase. If one of them is taken when the phase
+            // is able to start, the phase fails to schedule, rather than being given another
+            // resource of the same type.
+            for (next_resource, next_resource_type) in assigned_resources.iter() {
+                let hold_duration = ProductionSchedule::get_hold_duration(recipe_spec, &templates,
+                                                                          &durations, phase_index,
+                                                                          next_start_date,
+                                                                          duration,
+                                                                          next_resource_type);
+                let allocation_period = get_period(next_start_date, hold_duration, &path[..])?;
+
+                match self.tracker.allocate_resource_for_period(next_resource.id,
+                                                                allocation_period) {
+                    Some(x) => allocations.push((x.clone(), next_resource_type.clone(),
+                                                 next_start_date)),
+                    None => return Err(Error::ResourceBusy {
+                        path,
+                        resource_name: next_resource.name.clone(),
+                        start: self.get_local_date(next_start_date)
+                    })
+                }
+            }
+
+            for next_resource_type in resources_needed {
+                if carry_over.contains(&next_resource_type) {
+                    let carried_allocations: Vec<(Resource, NaiveDateTime)> = previous_allocations
+                      .iter()
+                      .filter(|(_, x, _)| *x == next_resource_type)
+                      .map(|(res, _, allocation_start_date)| (res.clone(), *allocation_start_date))
+                      .collect();
+
+                    for (next_resource, allocation_start_date) in carried_allocations {
+                        let allocation_end_date: NaiveDateTime = next_start_date
+                          .checked_add_signed(duration)
+                          .ok_or_else(|| Error::DateOutOfRange { path: path.clone() })?;
+                        let extended_resource = self.tracker.extend_allocation_of_resource(
+                            next_resource.id, allocation_start_date, allocation_end_date);
+
+                        if let Some(x) = extended_resource {
+                            allocations.push((x.clone(), next_resource_type.clone(),
+                                              allocation_start_date));
+                            continue;
+                        }
+
+                        // The resource is booked by the time this phase is done with it, so the
+                        // earlier phases that use it have to move past that booking.
+                        let blocked_until: NaiveDateTime = self.tracker
+                          .get_tracked_resource(next_resource.id)
+                          .and_then(|res| {
+                              res.get_end_of_allocations_blocking_extension(allocation_start_date,
+                                                                            allocation_end_date)
+                          })
+                          .unwrap_or(allocation_end_date);
+
+                        // Moving the earlier phases doesn't help if the booking overlaps a pinned
+                        // phase itself.
+                        if pinned_start_date.is_some_and(|x| blocked_until >= x) {
+                            return Err(Error::PinnedPhaseUnavailable {
+                                path,
+                                start: self.get_local_date(next_start_date),
+                                reason: format!("\"{}\", which is carried over from the previous \
+                                                 phase, is taken then", next_resource.name)
+                            });
+                        }
+
+                        return Ok(PhaseScheduleAttempt::CarryOverBlocked {
+                            path,
+                            resource_name: next_resource.name,
+                            delay: blocked_until + Duration::seconds(1) - allocation_start_date
+                        });
+                    }
+
+                    continue;
+                }
+
+                if assigned_resources.iter().any(|(_, x)| *x == next_resource_type) {
+                    continue;
+                }
+
+                let hold_duration = ProductionSchedule::get_hold_duration(recipe_spec, &templates,
+                                                                          &durations, phase_index,
+                                                                          next_start_date,
+                                                                          duration,
+                                                                          &next_resource_type);
+                let allocation_period = get_period(next_start_date, hold_duration, &path[..])?;
+
+                // A resource already allocated to the phase that is also of this type fills this
+                // need as well, as long as it can be held for as long as this type is needed.
+                let shared_resource: Option<Resource> = allocations.iter()
+                  .filter(|(res, _, _)| {
+                      res.is_of_type(&next_resource_type) && res.can_hold_batch(batch_size.as_ref())
+                  })
+                  .find_map(|(res, _, _)| {
+                      self.tracker.extend_allocation_of_resource(res.id, next_start_date,
+                                                                 allocation_period.end)
+                                  .cloned()
+                  });
+                if let Some(x) = shared_resource {
+                    allocations.push((x, next_resource_type.clone(), next_start_date));
+                    continue;
+                }
+
+                // Allocate the resource
+                // Preferred resources are taken if they're free, and otherwise passed over.
+                let allocated_resources = match self.tracker
+                  .allocate_preferred_resource_of_type_for_period(&next_resource_type,
+                                                                  allocation_period,
+                                                                  batch_size.as_ref(),
+                                                                  &preferred_ids) {
+                    Some(x) => Some(vec![x.clone()]),
+                    None => self.tracker.allocate_resources_of_type_for_period(&next_resource_type,
+                                                                               allocation_period,
+                                                                               batch_size.as_ref())
+                };
+
+                match allocated_resources {
+                    // Put the allocated resources into the vector
+                    Some(x) => {
+                        allocations.extend(x.into_iter().map(|res| {
+                            (res, next_resource_type.clone(), next_start_date)
+                        }))
+                    },
+                    None => return Err(Error::Allocation {
+                        path,
+                        resource_type: next_resource_type
+                    })
+                }
+            }
+
+            // A resource that fills several needs of the phase is only listed once.
+            let mut resources_used: Vec<Resource> = vec![];
+            for (next_resource, _, _) in allocations.iter() {
+                if !resources_used.iter().any(|x| x.id == next_resource.id) {
+                    resources_used.push(next_resource.clone());
+                }
+            }
+
+            if !template.staff_needed.is_empty() {
+                match self.tracker.allocate_staff_for_periods(&template.staff_needed,
+                                                              &staff_periods) {
+                    Some(x) => resources_used.extend(x),
+                    None => return Err(Error::InsufficientStaff { path })
+                }
+            }
+            phase_allocations[phase_index] = allocations;
+
+            phases[phase_index] = Some(PhaseInstance::new(id, description,
+                                                          recipe_spec.color_hex.clone(), duration,
+                                                          next_start_date, resources_used));
+        }
+
+        let mut phases: Vec<PhaseInstance> = phases.into_iter().map(|x| x.unwrap()).collect();
+
+        // We have to add the id of each phase as a dependency to its predecessors. This is a weird
+        // nuance of pla that tasks X that are dependent on some task Y are actually defined in the
+        // definition of Y, not X. It basically means you have to specify that there will be
+        // defined a task with id X, but that task hasn't been defined yet.
+        for (phase_index, phase_dependencies) in dependencies.iter().enumerate() {
+            for dependency in phase_dependencies.iter().filter(|x| x.recipe_index.is_none()) {
+                phases[dependency.phase_index].add_dependency(ids[phase_index]);
+            }
+        }
+
+        Ok(PhaseScheduleAttempt::Scheduled(phases))
+    }
+
+    /// Verify that enough staff are defined to fill the `staffNeeded` of a phase template.
+    ///
+    /// # Arguments
+    /// - `tracker`: The `ResourceTracker` tracking the staff.
+    /// - `template`: The template of the phase.
+    /// - `path`: The path of the phase or template within the BPD file, used for error reporting.
+    ///
+    /// # Returns
+    /// - A `Result` containing either nothing, or an `Error` describing the first skill for which
+    ///   too few staff are defined. Staff are counted across all requirements, as no member of
+    ///   staff fills more than one of them.
+    pub(crate) fn verify_staff_needed(tracker: &ResourceTracker,
+                                      template: &ProductionPhaseTemplate, path: &str)
+      -> std::result::Result<(), Error> {
+        let mut skills: Vec<Option<&str>> = vec![];
+        for requirement in template.staff_needed.iter() {
+            if !skills.contains(&requirement.skill.as_deref()) {
+                skills.push(requirement.skill.as_deref());
+            }
+        }
+
+        if !template.staff_needed.is_empty() && !skills.contains(&None) {
+            skills.push(None);
+        }
+
+        for skill in skills {
+            let count: usize = template.staff_needed.iter()
+                                       .filter(|x| skill.is_none() || x.skill.as_deref() == skill)
+                                       .map(|x| x.count)
+                                       .sum();
+            let available: usize = tracker.count_staff_with_skill(skill);
+            if available < count {
+                return Err(Error::MissingStaff {
+                    path: path.to_string(),
+                    skill: skill.map(|x| x.to_string()),
+                    count,
+                    available
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Retrieve the periods during which staff work on a phase.
+    ///
+    /// # Arguments
+    /// - `working_time`: The `WorkingTime` of the phase, or `None` if it runs continuously.
+    /// - `start`: The date at which the phase starts.
+    /// - `work`: The duration of the phase, counting only working time.
+    fn get_staff_periods(working_time: Option<&WorkingTime>, start: NaiveDateTime,
+                         work: Duration) -> Vec<NaivePeriod> {
+        // The working time of the phase has already been checked while settling its start date.
+        match working_time {
+            Some(x) => x.get_periods_of_work(start, work).unwrap_or_default(),
+            None => vec![NaivePeriod::from_start_duration(start, work)]
+        }
+    }
+
+    /// Determine the minimum and maximum gap between the time at which the predecessors of a phase
+    /// allow it to start, and the time at which it actually starts.
+    ///
+    /// # Arguments
+    /// - `spec`: The `PhaseInstanceSpec` describing the phase.
+    /// - `path`: The path of `spec` within the BPD file, used for error reporting.
+    ///
+    /// # Returns
+    /// - A `Result` containing either the minimum gap (zero, if not specified) and the maximum gap
+    ///   (`None`, if not specified), or an `Error` if either gap could not be parsed, or the
+    ///   minimum gap is larger than the maximum gap.
+    fn get_phase_gaps(spec: &PhaseInstanceSpec, path: &str)
+      -> std::result::Result<(Duration, Option<Duration>), Error> {
+        let min_gap: Duration = match spec.min_gap_string.is_empty() {
+            true => Duration::zero(),
+            false => convert_string_to_duration(&spec.min_gap_string[..])
+                       .map_err(|reason| Error::DurationParse {
+                           path: format!("{}.minGap", path),
+                           value: spec.min_gap_string.clone(),
+                           reason
+                       })?
+        };
+
+        let max_gap: Option<Duration> = match spec.max_gap_string.is_empty() {
+            true => None,
+            false => Some(convert_string_to_duration(&spec.max_gap_string[..])
+                            .map_err(|reason| Error::DurationParse {
+                                path: format!("{}.maxGap", path),
+                                value: spec.max_gap_string.clone(),
+                                reason
+                            })?)
+        };
+
+        if max_gap.is_some_and(|x| x < min_gap) {
+            return Err(Error::InvalidGap {
+                path: format!("{}.maxGap", path),
+                min_gap: spec.min_gap_string.clone(),
+                max_gap: spec.max_gap_string.clone()
+            });
+        }
+
+        Ok((min_gap, max_gap))
+    }
+
+    /// Resolve the predecessors of each phase of a recipe.
+    ///
+    /// # Arguments
+    /// - `recipe_spec`: The `RecipeSpec` whose phases should be resolved.
+    ///
+    /// # Returns
+    /// - A `Result` containing either a `Vec` with the `PhaseDependency`s of each phase, in the
+    ///   order of the phases in `recipe_spec`, or an `Error` describing why a dependency could not
+    ///   be resolved.
+    ///
+    /// # Notes
+    /// A phase without an `after` depends on the previous phase. A phase that carries over
+    /// resources from the previous phase always depends on it. A dependency may refer to a phase
+    /// of another recipe by giving its name.
+    pub(crate) fn get_phase_dependencies(&self, recipe_spec: &RecipeSpec)
+      -> std::result::Result<Vec<Vec<PhaseDependency>>, Error> {
+        let mut dependencies: Vec<Vec<PhaseDependency>> = vec![];
+
+        for (phase_index, next_spec) in recipe_spec.phase_specs.iter().enumerate() {
+            let path = phase_path(&recipe_spec.name[..], phase_index);
+            let mut phase_dependencies: Vec<PhaseDependency> = vec![];
+
+            for (dependency_index, dependency_spec) in next_spec.after.iter().flatten().enumerate() {
+                let dependency_path = format!("{}.after[{}]", path, dependency_index);
+
+                // A dependency on the recipe itself is the same as one without a recipe.
+                let recipe_index: Option<usize> = match &dependency_spec.recipe {
+                    Some(x) if *x != recipe_spec.name => {
+                        match self.recipe_specs.iter().position(|other| other.name == *x) {
+                            Some(index) => Some(index),
+                            None => return Err(Error::UnknownRecipe {
+                                path: format!("{}.recipe", dependency_path),
+                                recipe: x.clone()
+                            })
+                        }
+                    },
+                    _ => None
+                };
+
+                let predecessor_recipe: &RecipeSpec = match recipe_index {
+                    Some(x) => &self.recipe_specs[x],
+                    None => recipe_spec
+                };
+
+                let predecessors: Vec<usize> = predecessor_recipe.phase_specs.iter().enumerate()
+                  .filter(|(_, x)| x.get_id() == dependency_spec.phase)
+                  .map(|(index, _)| index)
+                  .collect();
+
+                let predecessor_index: usize = match predecessors[..] {
+                    [x] => x,
+                    [] => return Err(Error::UnknownPhase {
+                        path: format!("{}.phase", dependency_path),
+                        phase: dependency_spec.phase.clone()
+                    }),
+                    _ => return Err(Error::AmbiguousPhase {
+                        path: format!("{}.phase", dependency_path),
+                        phase: dependency_spec.phase.clone()
+                    })
+                };
+
+                let lag: Duration = match dependency_spec.lag_string.is_empty() {
+                    true => Duration::zero(),
+                    false => convert_string_to_duration(&dependency_spec.lag_string[..])
+                               .map_err(|reason| Error::DurationParse {
+                                   path: format!("{}.lag", dependency_path),
+                                   value: dependency_spec.lag_string.clone(),
+                                   reason
+                               })?
+                };
+
+                phase_dependencies.push(PhaseDependency {
+                    recipe_index,
+                    phase_index: predecessor_index,
+                    dependency_type: dependency_spec.dependency_type,
+                    lag
+                });
+            }
+
+            let carries_over: bool = match self.get_phase_by_id(&next_spec.template[..]) {
+                Some(template) => !next_spec.carry_over(&template).is_empty(),
+                None => false
+            };
+
+            let is_after_previous_phase: bool = next_spec.after.is_none() || carries_over;
+            if phase_index > 0 && is_after_previous_phase
+              && !phase_dependencies.iter().any(|x| {
+                  x.recipe_index.is_none() && x.phase_index == phase_index - 1
+              }) {
+                phase_dependencies.push(PhaseDependency {
+                    recipe_index: None,
+                    phase_index: phase_index - 1,
+                    dependency_type: DependencyType::FinishToStart,
+                    lag: Duration::zero()
+                });
+            }
+
+            dependencies.push(phase_dependencies);
+        }
+
+        Ok(dependencies)
+    }
+
+    /// Determine the order in which the phases of a recipe should be scheduled, so that each
+    /// phase is scheduled after all of its predecessors.
+    ///
+    /// # Arguments
+    /// - `recipe_spec`: The `RecipeSpec` whose phases should be ordered.
+    /// - `dependencies`: The `PhaseDependency`s of each phase of `recipe_spec`.
+    ///
+    /// # Returns
+    /// - A `Result` containing either the indices of the phases in the order in which they should
+    ///   be scheduled, or an `Error::DependencyCycle` if the phases depend on each other in a
+    ///   cycle.
+    pub(crate) fn get_phase_order(recipe_spec: &RecipeSpec, dependencies: &[Vec<PhaseDependency>])
+      -> std::result::Result<Vec<usize>, Error> {
+        // Predecessors in other recipes are scheduled before this recipe, so they don't affect the
+        // order of its phases.
+        let preference: Vec<usize> = (0..dependencies.len()).collect();
+        get_topological_order(&preference, |index| {
+            dependencies[index].iter()
+                               .filter(|x| x.recipe_index.is_none())
+                               .map(|x| x.phase_index)
+                               .collect()
+        }).map_err(|cycle| Error::DependencyCycle {
+            path: recipe_path(&recipe_spec.name[..]),
+            phases: cycle.into_iter()
+                         .map(|index| recipe_spec.phase_specs[index].get_id().to_string())
+                         .collect()
+        })
+    }
+
+    /// Replace every alias of a resource type in the BPD data with the type it stands for, so that
+    /// resources and the phases that need them agree on the names of their types.
+    ///
+    /// # Notes
+    /// Resource types that are keys of a map, such as those of `turnarounds`, are resolved with
+    /// [resolve_resource_type](ProductionSchedule::resolve_resource_type) when they are used.
+    pub(crate) fn resolve_resource_type_aliases(&mut self) {
+        let aliases: BTreeMap<String, String> = self.resource_type_aliases.clone();
+        let resolve = |resource_type: &mut ResourceType| {
+            if let Some(x) = aliases.get(resource_type.name()) {
+                *resource_type = ResourceType::from(&x[..]);
+            }
+        };
+
+        for next_resource in self.resources.iter_mut() {
+            resolve(&mut next_resource.resource_type);
+            next_resource.additional_types.iter_mut().for_each(resolve);
+        }
+
+        for template in self.phase_templates.iter_mut() {
+            template.resources_needed.iter_mut().for_each(resolve);
+            template.carry_over.iter_mut().for_each(resolve);
+        }
+
+        for phase_spec in self.recipe_specs.iter_mut().flat_map(|x| x.phase_specs.iter_mut()) {
+            if let Some(carry_over) = phase_spec.carry_over.as_mut() {
+                carry_over.iter_mut().for_each(resolve);
+            }
+        }
+    }
+
+    /// Retrieve the `ResourceType` with a specific name, which may be an alias.
+    pub(crate) fn resolve_resource_type(&self, name: &str) -> ResourceType {
+        ResourceType::from(self.resource_type_aliases.get(name).map_or(name, |x| &x[..]))
+    }
+
+    /// Retrieve every pair of a `ResourceType` that can be used in place of another, and that
+    /// other `ResourceType`, as declared in `substitutes`.
+    pub(crate) fn get_substitutions(&self) -> Vec<(ResourceType, ResourceType)> {
+        self.substitutes.iter().flat_map(|(substitute, resource_types)| {
+            resource_types.iter().map(move |x| {
+                (self.resolve_resource_type(substitute), self.resolve_resource_type(x))
+            })
+        }).collect()
+    }
+
+    /// Retrieve a built-in allocation strategy named in the BPD file.
+    ///
+    /// # Arguments
+    /// - `name`: The name of the strategy, e.g. `best-fit`.
+    /// - `path`: The path of the name within the BPD file, used for error reporting.
+    pub(crate) fn get_named_allocation_strategy(name: &str, path: &str)
+      -> std::result::Result<Arc<dyn AllocationStrategy>, Error> {
+        get_allocation_strategy(name).ok_or_else(|| Error::UnknownAllocationStrategy {
+            path: path.to_string(),
+            strategy: name.to_string()
+        })
+    }
+
+    fn track_resources(&mut self) -> std::result::Result<(), Error> {
+        if let Some(name) = &self.allocation_strategy {
+            self.tracker.set_allocation_strategy(
+                ProductionSchedule::get_named_allocation_strategy(&name[..], "allocationStrategy")?);
+        }
+
+        for (resource_type, name) in self.allocation_strategies.iter() {
+            let path = format!("allocationStrategies[{:?}]", resource_type);
+            self.tracker.set_allocation_strategy_for_type(
+                self.resolve_resource_type(&resource_type[..]),
+                ProductionSchedule::get_named_allocation_strategy(&name[..], &path[..])?);
+        }
+
+        for (resource_type, strategy) in self.custom_allocation_strategies.iter() {
+            match resource_type {
+                Some(x) => self.tracker.set_allocation_strategy_for_type(x.clone(),
+                                                                         strategy.clone()),
+                None => self.tracker.set_allocation_strategy(strategy.clone())
+            }
+        }
+
+        for (substitute, resource_type) in self.get_substitutions() {
+            self.tracker.add_substitute(substitute, resource_type);
+        }
+
+        let mut turnarounds: Vec<(ResourceType, Duration)> = vec![];
+        for (resource_type, turnaround_string) in self.turnarounds.iter() {
+            match convert_string_to_duration(&turnaround_string[..]) {
+                Ok(x) => turnarounds.push((self.resolve_resource_type(&resource_type[..]), x)),
+                Err(reason) => return Err(Error::DurationParse {
+                    path: format!("turnarounds[{:?}]", resource_type),
+                    value: turnaround_string.clone(),
+                    reason
+                })
+            }
+        }
+
+        for (index, mut next_resource) in self.resources.clone().into_iter().enumerate() {
+            next_resource.turnaround = match &next_resource.turnaround_str {
+                Some(x) => convert_string_to_duration(&x[..]).map_err(|reason| {
+                    Error::DurationParse {
+                        path: format!("resources[{}].turnaround", index),
+                        value: x.clone(),
+                        reason
+                    }
+                })?,
+                None => turnarounds.iter()
+                  .find(|(resource_type, _)| *resource_type == next_resource.resource_type)
+                  .or_else(|| {
+                      turnarounds.iter()
+                                 .find(|(resource_type, _)| next_resource.is_of_type(resource_type))
+                  })
+                  .map_or_else(Duration::zero, |(_, duration)| *duration)
+            };
+
+            self.verify_site(&next_resource.site, &format!("resources[{}]", index)[..])?;
+
+            let site: Option<String> = next_resource.site.clone();
+            next_resource.time_zone = self.get_time_zone(site.as_deref()).cloned();
+            let downtime_specs: Vec<DowntimeSpec> = next_resource.downtime_specs.clone();
+            for (downtime_index, downtime_spec) in downtime_specs.iter().enumerate() {
+                let path = downtime_path(index, downtime_index);
+                for period in ProductionSchedule::get_downtime_periods(downtime_spec, &path[..])? {
+                    let period = NaivePeriod::new(
+                        self.get_schedule_date(period.start, site.as_deref()),
+                        self.get_schedule_date(period.end, site.as_deref()));
+                    next_resource.add_downtime(&downtime_spec.description[..], period);
+                }
+            }
+
+            self.tracker.track_resource(next_resource);
+        }
+
+        Ok(())
+    }
+}
+
+/// Retrieve the recipes named by the dependencies of the phases of a `RecipeSpec`.
+///
+/// # Returns
+/// - The index of the phase, the index of the dependency within its `after`, and the name of the
+///   recipe, for each dependency that names a recipe.
+fn get_dependency_recipes(spec: &RecipeSpec) -> Vec<(usize, usize, &String)> {
+    spec.phase_specs.iter().enumerate()
+        .flat_map(|(phase_index, phase_spec)| {
+            phase_spec.after.iter().flatten().enumerate()
+                      .filter_map(move |(dependency_index, dependency_spec)| {
+                          dependency_spec.recipe.as_ref()
+                                         .map(|x| (phase_index, dependency_index, x))
+                      })
+        })
+        .collect()
+}
+
+/// Retrieve the `NaivePeriod` for which a resource is requested.
+///
+/// # Arguments
+/// - `start`: The date at which the period starts.
+/// - `duration`: The `Duration` of the period, or `None` if it could not be determined because it
+///   would end beyond the latest date that can be represented.
+/// - `path`: The path of the phase the resource is requested for, used for error reporting.
+///
+/// # Returns
+/// - A `Result` containing either the `NaivePeriod`, or an `Error::DateOutOfRange` if it would end
+///   beyond the latest date that can be represented.
+fn get_period(start: NaiveDateTime, duration: Option<Duration>, path: &str)
+  -> std::result::Result<NaivePeriod, Error> {
+    duration.and_then(|x| start.checked_add_signed(x))
+            .map(|end| NaivePeriod::new(start, end))
+            .ok_or_else(|| Error::DateOutOfRange { path: path.to_string() })
+}
+
+/// Require a `Duration` parsed from a BPD file to be longer than zero.
+///
+/// # Returns
+/// - A `Result` containing either `duration`, or the reason it cannot be used, for an
+///   `Error::DurationParse`.
+fn get_positive_duration(duration: Duration) -> std::result::Result<Duration, String> {
+    match duration > Duration::zero() {
+        true => Ok(duration),
+        false => Err("it must be longer than zero".to_string())
+    }
+}
+
+/// Parse how often something recurs.
+///
+/// # Arguments
+/// - `every`: `daily`, `weekly`, `monthly`, or a duration such as `3w` or `2mo`.
+/// - `path`: The path of the element within the BPD file that recurs, used for error reporting.
+///
+/// # Returns
+/// - A `Result` containing either the time between two consecutive dates, or an
+///   `Error::DurationParse` if `every` cannot be parsed, or does not move forward in time.
+fn get_recurrence_step(every: &str, path: &str) -> std::result::Result<CalendarDuration, Error> {
+    match every {
+        "daily" => Ok(CalendarDuration::new(0, Duration::days(1))),
+        "weekly" => Ok(CalendarDuration::new(0, Duration::weeks(1))),
+        "monthly" => Ok(CalendarDuration::new(1, Duration::zero())),
+        _ => convert_string_to_calendar_duration(every)
+               .and_then(|x| match x.months >= 0 && x.duration >= Duration::zero() {
+                   true => get_positive_duration(x.approximate()).map(|_| x),
+                   false => Err("it must not go back in time".to_string())
+               })
+               .map_err(|reason| Error::DurationParse {
+                   path: format!("{}.every", path),
+                   value: every.to_string(),
+                   reason
+               })
+    }
+}
+
+/// Determine the dates on which something that recurs happens.
+///
+/// # Arguments
+/// - `start`: The date on which it happens first.
+/// - `step`: The time between two consecutive dates, as parsed by
+///   [get_recurrence_step](get_recurrence_step).
+/// - `count`: The number of times it happens, if limited.
+/// - `until`: The latest date on which it can happen, if limited. At least one of `count` and
+///   `until` must be given.
+///
+/// # Returns
+/// - A `Result` containing either the dates, in chronological order, or a `String` explaining why
+///   they cannot be determined: either there would be more than [MAX_RECURRENCES](MAX_RECURRENCES)
+///   of them, or a date would be out of range.
+///
+/// # Notes
+/// Dates that are calendar months apart are counted from the first one, so that they stay on the
+/// same day of the month wherever possible.
+fn get_recurring_dates(start: NaiveDateTime, step: &CalendarDuration, count: Option<usize>,
+                       until: Option<NaiveDateTime>)
+  -> std::result::Result<Vec<NaiveDateTime>, String> {
+    if count.is_some_and(|count| count > MAX_RECURRENCES) {
+        return Err(format!("it recurs more than {} times", MAX_RECURRENCES));
+    }
+
+    let mut dates: Vec<NaiveDateTime> = vec![];
+    while count.is_none_or(|count| dates.len() < count) {
+        if dates.len() == MAX_RECURRENCES {
+            return Err(format!("it recurs more than {} times before its \"until\" date",
+                               MAX_RECURRENCES));
+        }
+
+        // Months are never negative, as checked by get_recurrence_step().
+        let steps: u32 = dates.len() as u32;
+        let months: Option<u32> = (step.months as u32).checked_mul(steps);
+        let duration: Option<Duration> = step.duration.num_milliseconds()
+                                             .checked_mul(steps as i64)
+                                             .map(Duration::milliseconds);
+        let next_date: NaiveDateTime = months.zip(duration)
+          .and_then(|(months, duration)| {
+              start.checked_add_months(Months::new(months))?.checked_add_signed(duration)
+          })
+          .ok_or_else(|| "it recurs beyond the latest date that can be represented".to_string())?;
+
+        match until.is_none_or(|until| next_date <= until) {
+            true => dates.push(next_date),
+            false => break
+        }
+    }
+
+    Ok(dates)
+}
+
+/// The outcome of trying to schedule the phases of a recipe from a specific start date.
+enum PhaseScheduleAttempt {
+    /// All phases were scheduled.
+    Scheduled(Vec<PhaseInstance>),
+
+    /// The phase at `path` could not start within its maximum gap, `max_gap`. The recipe has to
+    /// start at least `delay` later for it to do so.
+    GapExceeded {
+        path: String,
+        max_gap: String,
+        delay: Duration
+    },
+
+    /// The phase at `path` could not keep the resource named `resource_name` from the previous
+    /// phase, as it is booked for something else in the meantime. The recipe has to start at
+    /// least `delay` later for the previous phase to use it after that booking.
+    CarryOverBlocked {
+        path: String,
+        resource_name: String,
+        delay: Duration
+    },
+
+    /// The phase at `path` is pinned to start at `start`, but the earlier phases of the recipe,
+    /// which it depends on or shares resources with, only allow it to start `delay` later. The
+    /// recipe has to start at least `delay` earlier for it to do so.
+    PinMissed {
+        path: String,
+        start: NaiveDateTime,
+        delay: Duration
+    }
+}
+
+/// A resolved dependency of a phase on another phase.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct PhaseDependency {
+    /// The index of the recipe containing the predecessor, or `None` if it is in the same recipe
+    /// as the phase.
+    recipe_index: Option<usize>,
+
+    /// The index of the predecessor within its recipe.
+    phase_index: usize,
+
+    dependency_type: DependencyType,
+    lag: Duration
+}
+
+/// Order a set of nodes so that each node comes after all of its predecessors.
+///
+/// # Arguments
+/// - `preference`: The indices of all nodes, in the order in which they should preferably come.
+/// - `get_predecessors`: A function retrieving the indices of the predecessors of a node.
+///
+/// # Returns
+/// - A `Result` containing either the indices of all nodes in order, or the indices of some nodes
+///   that form a cycle, in the order in which they depend on each other, starting at the lowest
+///   index.
+///
+/// # Notes
+/// Whenever several nodes could come next, the one that comes first in `preference` is chosen, so
+/// nodes that do not depend on each other keep their preferred relative order.
+fn get_topological_order<F>(preference: &[usize], get_predecessors: F)
+  -> std::result::Result<Vec<usize>, Vec<usize>>
+  where F: Fn(usize) -> Vec<usize> {
+    let count: usize = preference.len();
+    let mut order: Vec<usize> = vec![];
+    let mut is_ordered: Vec<bool> = vec![false; count];
+
+    while order.len() < count {
+        let next = preference.iter().cloned().find(|index| {
+            !is_ordered[*index] && get_predecessors(*index).iter().all(|x| is_ordered[*x])
+        });
+
+        match next {
+            Some(index) => {
+                is_ordered[index] = true;
+                order.push(index);
+            },
+            None => {
+                // Every remaining node has a remaining predecessor, so following them must
+                // eventually lead back to a node that has already been visited.
+                let mut visited: Vec<usize> = vec![];
+                let mut current: usize = (0..count).find(|index| !is_ordered[*index]).unwrap();
+                while !visited.contains(&current) {
+                    visited.push(current);
+                    current = get_predecessors(current).into_iter()
+                                                       .find(|x| !is_ordered[*x])
+                                                       .unwrap();
+                }
+
+                let cycle_start = visited.iter().position(|x| *x == current).unwrap();
+                let mut cycle: Vec<usize> = visited[cycle_start..].iter().rev().cloned().collect();
+
+                // Start the cycle at its lowest index, so that it's reported consistently.
+                let lowest = cycle.iter().enumerate().min_by_key(|(_, x)| **x).unwrap().0;
+                cycle.rotate_left(lowest);
+
+                return Err(cycle);
+            }
+        }
+    }
+
+    Ok(order)
 }
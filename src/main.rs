@@ -1,31 +1,27 @@
 use std::io::{BufReader, Read, Write};
 use std::fs::File;
+use std::process;
 
 extern crate chronogrog;
 use chronogrog::ProductionSchedule;
+use chronogrog::optimize::{optimize, Objective, OptimizationReport};
+use chronogrog::validation::{validate, Severity, ValidationReport};
 
 #[macro_use]
 extern crate clap;
 
-use clap::{App, Arg};
+use clap::{App, Arg, ArgMatches, SubCommand};
 
 fn main() {
-    let app_name = format!("{}", env!("CARGO_PKG_NAME"));
-    let app_description = format!("{}", env!("CARGO_PKG_DESCRIPTION"));
-    let authors = format!("{}", env!("CARGO_PKG_AUTHORS"));
+    let app_name = env!("CARGO_PKG_NAME").to_string();
+    let app_description = env!("CARGO_PKG_DESCRIPTION").to_string();
+    let authors = env!("CARGO_PKG_AUTHORS").to_string();
 
-    let matches = App::new(app_name)
+    let matches = App::new(&app_name[..])
       .version(crate_version!())
       .about(&app_description[..])
       .author(&authors[..])
-      .arg(
-            Arg::with_name("input")
-                .short("i")
-                .long("input")
-                .value_name("INPUT")
-                .help("Specify an input file to read from. Defaults to standard input.")
-                .takes_value(true),
-      )
+      .arg(input_arg())
       .arg(
             Arg::with_name("output")
                 .short("o")
@@ -34,34 +30,180 @@ fn main() {
                 .help("Specify an output file to write to. Defaults to standard output.")
                 .takes_value(true),
       )
+      .arg(
+            Arg::with_name("show-turnarounds")
+                .long("show-turnarounds")
+                .help("Include the turnaround time of each resource as tasks in the output.")
+      )
+      .arg(
+            Arg::with_name("optimize")
+                .long("optimize")
+                .value_name("OBJECTIVE")
+                .help("Search for an order of recipes and a choice of resources that improves the schedule by an objective.")
+                .possible_values(&["makespan", "tardiness", "utilization"])
+                .takes_value(true),
+      )
+      .arg(
+            Arg::with_name("seed")
+                .long("seed")
+                .value_name("SEED")
+                .help("Specify the seed of the optimization, which makes it reproducible.")
+                .default_value("0")
+                .takes_value(true),
+      )
+      .arg(
+            Arg::with_name("iterations")
+                .long("iterations")
+                .value_name("ITERATIONS")
+                .help("Specify the number of alternative schedules tried by the optimization.")
+                .default_value("100")
+                .takes_value(true),
+      )
+      .arg(
+            Arg::with_name("staff-load")
+                .long("staff-load")
+                .help("Report the number of hours each member of staff works per week to standard \
+                       error.")
+      )
+      .subcommand(
+            SubCommand::with_name("validate")
+                .about("Check a BPD file and report every problem found in it.")
+                .arg(input_arg())
+                .arg(
+                      Arg::with_name("format")
+                          .long("format")
+                          .value_name("FORMAT")
+                          .help("Specify the format of the validation report.")
+                          .possible_values(&["text", "json"])
+                          .default_value("text")
+                          .takes_value(true),
+                )
+      )
       .get_matches();
 
-    let input_file: Box<dyn Read> = match matches.value_of("input") {
+    let result = match matches.subcommand() {
+        ("validate", Some(sub_matches)) => run_validate(sub_matches),
+        _ => run(&matches).map(|_| true)
+    };
+
+    match result {
+        Ok(true) => {},
+        Ok(false) => process::exit(1),
+        Err(message) => {
+            eprintln!("{}: {}", app_name, message);
+            process::exit(1);
+        }
+    }
+}
+
+fn input_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("input")
+        .short("i")
+        .long("input")
+        .value_name("INPUT")
+        .help("Specify an input file to read from. Defaults to standard input.")
+        .takes_value(true)
+}
+
+fn open_input(matches: &ArgMatches) -> Result<Box<dyn Read>, String> {
+    match matches.value_of("input") {
         Some(in_file) => match File::open(in_file) {
-                Ok(f) => Box::new(f),
-                Err(e) => {
-                    panic!(format!("{}: {}", e, in_file));
-                }
+                Ok(f) => Ok(Box::new(f)),
+                Err(e) => Err(format!("{}: {}", e, in_file))
         },
-        None => Box::new(std::io::stdin())
+        None => Ok(Box::new(std::io::stdin()))
+    }
+}
+
+/// Convert the BPD input requested on the command line into PLA output.
+///
+/// # Returns
+/// - A `Result` that is `Ok` if the conversion succeeded, or contains a human-readable message
+///   explaining why it failed.
+fn run(matches: &ArgMatches) -> Result<(), String> {
+    let input_file: Box<dyn Read> = open_input(matches)?;
+
+    let mut production_schedule: ProductionSchedule = match ProductionSchedule::from_reader(input_file) {
+        Ok(x) => x,
+        Err(e) => return Err(e.to_string())
     };
 
-    let mut buf_reader = BufReader::new(input_file);
-    let mut json_data: String = String::new();
-    buf_reader.read_to_string(&mut json_data).unwrap();
+    production_schedule.show_turnarounds = matches.is_present("show-turnarounds");
 
-    let production_schedule: ProductionSchedule = ProductionSchedule::new(&json_data[..]);
+    if let Some(objective_string) = matches.value_of("optimize") {
+        let objective: Objective = objective_string.parse()?;
+        let seed: u64 = parse_number_arg(matches, "seed")?;
+        let iterations: usize = parse_number_arg(matches, "iterations")?;
+
+        let report: OptimizationReport = optimize(&mut production_schedule, objective, seed,
+                                                  iterations).map_err(|e| e.to_string())?;
+        eprintln!("{}: {:.2} -> {:.2}", report.objective, report.before, report.after);
+    }
+
+    for late_recipe in production_schedule.get_late_recipes() {
+        eprintln!("warning: recipe \"{}\" ends at {}, after its due date of {}", late_recipe.name,
+                  production_schedule.get_date_string(late_recipe.end_date().unwrap()),
+                  production_schedule.get_date_string(late_recipe.due_date.unwrap()));
+    }
+
+    if matches.is_present("staff-load") {
+        eprint!("{}", production_schedule.get_staff_load_report());
+    }
 
     let output_file: Box<dyn Write> = match matches.value_of("output") {
         Some(out_file) => match File::create(out_file) {
             Ok(f) => Box::new(f),
-            Err(e) => panic!(format!("{}: {}", e, out_file))
+            Err(e) => return Err(format!("{}: {}", e, out_file))
         },
         None => Box::new(std::io::stdout())
     };
 
-    match production_schedule.write_pla_file(output_file) {
-        Ok(_x) => _x,
-        Err(e) => panic!("{}", e)
+    production_schedule.write_pla_file(output_file).map_err(|e| e.to_string())
+}
+
+/// Parse the numeric value of a command line argument.
+///
+/// # Returns
+/// - A `Result` containing either the value, or a human-readable message explaining why it is not a
+///   valid number.
+fn parse_number_arg<T: std::str::FromStr>(matches: &ArgMatches, name: &str) -> Result<T, String> {
+    let value: &str = matches.value_of(name).unwrap_or_default();
+    value.parse::<T>().map_err(|_| format!("invalid value for --{}: \"{}\"", name, value))
+}
+
+/// Validate the BPD input requested on the command line, printing a report to standard output.
+///
+/// # Returns
+/// - A `Result` containing `true` if the BPD input has no errors, `false` if it has at least one
+///   error, or a human-readable message explaining why the input could not be read.
+fn run_validate(matches: &ArgMatches) -> Result<bool, String> {
+    let mut buf_reader = BufReader::new(open_input(matches)?);
+    let mut json_data: String = String::new();
+    buf_reader.read_to_string(&mut json_data).map_err(|e| e.to_string())?;
+
+    let report: ValidationReport = validate(&json_data[..]);
+
+    match matches.value_of("format") {
+        Some("json") => {
+            println!("{}", serde_json::to_string_pretty(&report).map_err(|e| e.to_string())?);
+        },
+        _ => {
+            for issue in report.issues.iter() {
+                let severity = match issue.severity {
+                    Severity::Error => "error",
+                    Severity::Warning => "warning"
+                };
+
+                if issue.path.is_empty() {
+                    println!("{}: {}", severity, issue.message);
+                } else {
+                    println!("{}: {}: {}", severity, issue.path, issue.message);
+                }
+            }
+
+            println!("{} error(s), {} warning(s)", report.error_count(), report.warning_count());
+        }
     }
+
+    Ok(!report.has_errors())
 }
@@ -0,0 +1,295 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{Duration, NaiveDateTime};
+
+use super::error::Error;
+use super::resources::{Resource, ResourceType};
+use super::ProductionSchedule;
+
+/// A measure of how good a schedule is, which [optimize](optimize) tries to improve.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Objective {
+    /// The number of hours between the start of the first phase and the end of the last phase of
+    /// the schedule. Lower is better.
+    Makespan,
+
+    /// The total number of hours by which recipes end later than their due date. Recipes without a
+    /// due date count the number of hours by which they start later than requested instead. Lower
+    /// is better.
+    Tardiness,
+
+    /// The fraction of the time between the start of the first phase and the end of the last phase
+    /// of the schedule during which resources are in use, across all resources. Higher is better.
+    Utilization
+}
+
+impl Objective {
+    /// Measure a scheduled `ProductionSchedule` by this `Objective`.
+    pub fn evaluate(self, schedule: &ProductionSchedule) -> f64 {
+        match self {
+            Objective::Makespan => get_hours(get_makespan(schedule)),
+            Objective::Tardiness => get_tardiness(schedule),
+            Objective::Utilization => get_utilization(schedule)
+        }
+    }
+
+    /// Determine if the value `candidate` of this `Objective` is strictly better than `current`.
+    pub fn is_improvement(self, candidate: f64, current: f64) -> bool {
+        match self {
+            Objective::Makespan | Objective::Tardiness => candidate < current,
+            Objective::Utilization => candidate > current
+        }
+    }
+}
+
+impl FromStr for Objective {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "makespan" => Ok(Objective::Makespan),
+            "tardiness" => Ok(Objective::Tardiness),
+            "utilization" => Ok(Objective::Utilization),
+            _ => Err(format!("unknown objective \"{}\"", s))
+        }
+    }
+}
+
+impl fmt::Display for Objective {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Objective::Makespan => write!(f, "makespan"),
+            Objective::Tardiness => write!(f, "tardiness"),
+            Objective::Utilization => write!(f, "utilization")
+        }
+    }
+}
+
+/// The outcome of [optimize](optimize).
+#[derive(Clone, Debug, PartialEq)]
+pub struct OptimizationReport {
+    pub objective: Objective,
+
+    /// The value of the objective for the schedule produced by scheduling recipes by priority and
+    /// due date.
+    pub before: f64,
+
+    /// The value of the objective for the optimized schedule.
+    pub after: f64,
+
+    /// The indices of the recipes in the order in which the optimized schedule schedules them.
+    pub order: Vec<usize>,
+
+    /// The id of the `Resource` the optimized schedule prefers to give a phase, keyed by the id of
+    /// the phase.
+    pub resource_choices: BTreeMap<usize, usize>
+}
+
+/// Improve a `ProductionSchedule` by searching for a better order in which to schedule its
+/// recipes, and better resources to give its phases.
+///
+/// # Arguments
+/// - `schedule`: The `ProductionSchedule` to improve. It is left scheduled in the best way found.
+/// - `objective`: The `Objective` to improve.
+/// - `seed`: The seed of the random choices made during the search. The same seed always produces
+///   the same schedule for the same BPD data.
+/// - `iterations`: The number of alternative schedules to try.
+///
+/// # Returns
+/// - A `Result` containing either an `OptimizationReport` comparing the greedy schedule with the
+///   optimized one, or an [Error](Error) if not even the greedy schedule could be produced.
+///
+/// # Notes
+/// The search is a hill climb: each iteration changes the best schedule found so far in one way,
+/// and keeps the result if it's strictly better. A change either moves or swaps recipes in the
+/// order, or has a phase prefer another resource of a type it uses; see
+/// [reschedule_with_resource_choices](ProductionSchedule::reschedule_with_resource_choices).
+/// Schedules that cannot be produced are skipped. Staff are never chosen, as they are allocated by
+/// their skills.
+pub fn optimize(schedule: &mut ProductionSchedule, objective: Objective, seed: u64,
+                iterations: usize) -> Result<OptimizationReport, Error> {
+    schedule.reschedule_with_resource_choices(&[], &BTreeMap::new())?;
+
+    let before: f64 = objective.evaluate(schedule);
+    let mut best_order: Vec<usize> = schedule.recipe_order();
+    let mut best_choices: BTreeMap<usize, usize> = BTreeMap::new();
+    let mut best_alternatives: Vec<(usize, usize)> = get_alternative_resources(schedule);
+    let mut best: f64 = before;
+
+    let mut random: SplitMix64 = SplitMix64::new(seed);
+    let count: usize = best_order.len();
+    for _ in 0..iterations {
+        let mut candidate_order: Vec<usize> = best_order.clone();
+        let mut candidate_choices: BTreeMap<usize, usize> = best_choices.clone();
+        let can_reorder: bool = count > 1;
+        let can_choose: bool = !best_alternatives.is_empty();
+        match (random.next_index(3), can_reorder, can_choose) {
+            (_, false, false) => break,
+            (0, true, _) | (_, true, false) => {
+                candidate_order.swap(random.next_index(count), random.next_index(count));
+            },
+            (1, true, _) => {
+                let moved: usize = candidate_order.remove(random.next_index(count));
+                candidate_order.insert(random.next_index(count), moved);
+            },
+            _ => {
+                let (phase_id, resource_id)
+                  = best_alternatives[random.next_index(best_alternatives.len())];
+                candidate_choices.insert(phase_id, resource_id);
+            }
+        }
+
+        if candidate_order == best_order && candidate_choices == best_choices {
+            continue;
+        }
+
+        if schedule.reschedule_with_resource_choices(&candidate_order, &candidate_choices)
+                   .is_err() {
+            continue;
+        }
+
+        let candidate: f64 = objective.evaluate(schedule);
+        if objective.is_improvement(candidate, best) {
+            best = candidate;
+            best_order = candidate_order;
+            best_choices = candidate_choices;
+            best_alternatives = get_alternative_resources(schedule);
+        }
+    }
+
+    schedule.reschedule_with_resource_choices(&best_order, &best_choices)?;
+
+    Ok(OptimizationReport {
+        objective,
+        before,
+        after: best,
+        order: best_order,
+        resource_choices: best_choices
+    })
+}
+
+/// Retrieve the resources that the phases of a scheduled `ProductionSchedule` could be given
+/// instead of those they use, i.e. the other resources of the type of each resource they use.
+///
+/// # Returns
+/// - A `Vec` containing the id of each phase along with the id of a `Resource` it could be given,
+///   in the order of the phases and resources.
+fn get_alternative_resources(schedule: &ProductionSchedule) -> Vec<(usize, usize)> {
+    let mut resources: Vec<Resource> = schedule.resources().into_iter()
+                                               .filter(|res| res.resource_type != ResourceType::Staff)
+                                               .collect();
+    resources.sort_by_key(|res| res.id);
+
+    let mut alternatives: Vec<(usize, usize)> = vec![];
+    for phase in schedule.get_recipe_iterator().flat_map(|recipe| recipe.get_phase_iterator()) {
+        for res in resources.iter() {
+            let is_alternative: bool = !phase.resources_used.iter().any(|x| x.id == res.id)
+              && phase.resources_used.iter().any(|x| {
+                     x.resource_type != ResourceType::Staff && res.is_of_type(&x.resource_type)
+                 });
+            if is_alternative {
+                alternatives.push((phase.id, res.id));
+            }
+        }
+    }
+
+    alternatives
+}
+
+/// Retrieve the start of the first phase and the end of the last phase of a schedule, or `None` if
+/// it has no phases.
+fn get_span(schedule: &ProductionSchedule) -> Option<(NaiveDateTime, NaiveDateTime)> {
+    schedule.get_recipe_iterator()
+            .flat_map(|recipe| recipe.get_phase_iterator())
+            .map(|phase| (phase.start_date, phase.start_date + phase.duration))
+            .fold(None, |span, (start, end)| match span {
+                Some((first, last)) => Some((start.min(first), end.max(last))),
+                None => Some((start, end))
+            })
+}
+
+fn get_makespan(schedule: &ProductionSchedule) -> Duration {
+    get_span(schedule).map_or_else(Duration::zero, |(first, last)| last - first)
+}
+
+fn get_tardiness(schedule: &ProductionSchedule) -> f64 {
+    schedule.recipe_specs.iter().zip(schedule.get_recipe_iterator()).map(|(spec, recipe)| {
+        let lateness: Duration = match (recipe.due_date, recipe.end_date()) {
+            (Some(due_date), Some(end_date)) => end_date - due_date,
+            _ => {
+                // Start dates have already been checked while scheduling.
+                let requested: NaiveDateTime
+                  = schedule.get_schedule_date(spec.start_date().unwrap(), spec.site.as_deref());
+                let actual: NaiveDateTime = recipe.get_phase_iterator()
+                                                  .map(|phase| phase.start_date)
+                                                  .min()
+                                                  .unwrap_or(requested);
+
+                actual - requested
+            }
+        };
+
+        get_hours(lateness.max(Duration::zero()))
+    }).sum()
+}
+
+fn get_utilization(schedule: &ProductionSchedule) -> f64 {
+    let (first, last): (NaiveDateTime, NaiveDateTime) = match get_span(schedule) {
+        Some(x) => x,
+        None => return 0.0
+    };
+
+    // Staff only work during working time, so they are never in use for the whole span.
+    let resources: Vec<Resource> = schedule.resources().into_iter()
+                                           .filter(|res| res.resource_type != ResourceType::Staff)
+                                           .collect();
+    let available: f64 = get_hours(last - first) * resources.len() as f64;
+    if available <= 0.0 {
+        return 0.0;
+    }
+
+    // Working periods include the time resources are held between phases, and every resource a
+    // batch is split across.
+    let used: f64 = resources.iter()
+                             .flat_map(|res| res.get_working_periods())
+                             .map(|period| {
+                                 let start: NaiveDateTime = period.start.max(first);
+                                 let end: NaiveDateTime = period.end.min(last);
+                                 get_hours((end - start).max(Duration::zero()))
+                             })
+                             .sum();
+
+    used / available
+}
+
+fn get_hours(duration: Duration) -> f64 {
+    duration.num_seconds() as f64 / 3600.0
+}
+
+/// A small pseudo-random number generator, so that the search is reproducible for a given seed
+/// without depending on the platform.
+struct SplitMix64 {
+    state: u64
+}
+
+impl SplitMix64 {
+    fn new(seed: u64) -> Self {
+        SplitMix64 { state: seed }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+
+        let mut z: u64 = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+
+        z ^ (z >> 31)
+    }
+
+    fn next_index(&mut self, count: usize) -> usize {
+        (self.next_u64() % count as u64) as usize
+    }
+}
@@ -1,11 +1,12 @@
-use chrono::{Duration, NaiveDateTime, NaiveTime};
+use chrono::{Duration, NaiveDateTime, ParseError};
 
 use string_builder::Builder;
 
 use serde::{Serialize, Deserialize};
 
-use super::resources::{Resource, ResourceType};
-use super::util::{get_space_indent, get_duration_in_hours, convert_string_to_duration};
+use super::resources::{Resource, ResourceSelector, ResourceType, StaffRequirement};
+use super::util::{get_space_indent, get_duration_in_hours, get_pla_date_string,
+                  convert_string_to_duration, get_naive_date_time_from_string};
 
 #[derive(Serialize, Deserialize, Default, Clone, PartialEq, Debug)]
 pub struct ProductionPhaseTemplate {
@@ -17,6 +18,26 @@ pub struct ProductionPhaseTemplate {
     #[serde(default="Vec::new")]
     pub resources_needed: Vec<ResourceType>,
 
+    /// Resource types whose `Resource`s are kept from the previous phase, rather than being
+    /// allocated anew. For example, beer that is carbonated in a keg stays in that same keg when
+    /// it is ready to drink.
+    #[serde(rename="carryOver")]
+    #[serde(default="Vec::new")]
+    pub carry_over: Vec<ResourceType>,
+
+    /// Whether phases using this template need staff, and so can only start and progress during
+    /// the working time of the working calendar, e.g. brewing. Phases that don't, e.g.
+    /// fermentation, run continuously.
+    #[serde(default)]
+    pub staffed: bool,
+
+    /// The members of staff that work on phases using this template, e.g.
+    /// `[{ "skill": "canning", "count": 1 }]`. They are allocated like equipment, for the working
+    /// time of the phase.
+    #[serde(rename="staffNeeded")]
+    #[serde(default="Vec::new")]
+    pub staff_needed: Vec<StaffRequirement>,
+
     #[serde(rename="color")]
     #[serde(default = "String::new")]
     color_hex: String,
@@ -27,13 +48,105 @@ pub struct ProductionPhaseTemplate {
 }
 
 impl ProductionPhaseTemplate {
+    /// Retrieve the default duration of this template, counting calendar months as 30 days, or
+    /// `None` if it is not specified or cannot be parsed.
     pub fn default_duration(&self) -> Option<Duration> {
-        convert_string_to_duration(&self.default_duration[..])
+        convert_string_to_duration(&self.default_duration[..]).ok()
+    }
+
+    /// Determine if phases using this template can only start and progress during working time,
+    /// either because the template is `staffed`, or because it needs specific staff.
+    pub fn is_staffed(&self) -> bool {
+        self.staffed || !self.staff_needed.is_empty()
+    }
+
+    /// Retrieve the default duration of this template, exactly as specified in the BPD file.
+    pub fn default_duration_string(&self) -> &str {
+        &self.default_duration[..]
+    }
+}
+
+/// The way in which a phase depends on one of its predecessors.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+pub enum DependencyType {
+    /// The phase cannot start until the predecessor has finished.
+    #[serde(rename="FS")]
+    #[default]
+    FinishToStart,
+
+    /// The phase cannot start until the predecessor has started.
+    #[serde(rename="SS")]
+    StartToStart,
+
+    /// The phase cannot finish until the predecessor has finished.
+    #[serde(rename="FF")]
+    FinishToFinish
+}
+
+impl DependencyType {
+    /// Retrieve the earliest date at which a phase can start, given one of its predecessors.
+    ///
+    /// # Arguments
+    /// - `predecessor_start`: The [NaiveDateTime](chrono::NaiveDateTime) at which the predecessor
+    ///   starts.
+    /// - `predecessor_duration`: The [Duration](chrono::Duration) of the predecessor.
+    /// - `duration`: The [Duration](chrono::Duration) of the phase itself.
+    /// - `lag`: The [Duration](chrono::Duration) that must additionally pass between the
+    ///   predecessor and the phase.
+    pub fn get_earliest_start_date(self, predecessor_start: NaiveDateTime,
+                                   predecessor_duration: Duration, duration: Duration,
+                                   lag: Duration) -> NaiveDateTime {
+        match self {
+            DependencyType::FinishToStart => predecessor_start + predecessor_duration + lag,
+            DependencyType::StartToStart => predecessor_start + lag,
+            DependencyType::FinishToFinish => {
+                predecessor_start + predecessor_duration + lag - duration
+            }
+        }
+    }
+}
+
+/// A specification of a predecessor of a phase, e.g.
+/// `{ "phase": "brewing", "type": "SS", "lag": "2h" }`, or
+/// `{ "recipe": "Barrel-Aged Stout", "phase": "aging" }` for a phase of another recipe.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct PhaseDependencySpec {
+    /// The name of the recipe containing the predecessor. If not specified, the predecessor is in
+    /// the same recipe as the phase.
+    #[serde(default)]
+    pub recipe: Option<String>,
+
+    /// The id of the predecessor within its recipe. See
+    /// [PhaseInstanceSpec::get_id](PhaseInstanceSpec::get_id).
+    pub phase: String,
+
+    #[serde(rename="type")]
+    #[serde(default)]
+    pub dependency_type: DependencyType,
+
+    #[serde(rename="lag")]
+    #[serde(default = "String::new")]
+    pub lag_string: String
+}
+
+impl PhaseDependencySpec {
+    /// Retrieve the lag of this dependency, or `None` if it cannot be parsed. If no lag is
+    /// specified, there is no lag.
+    pub fn lag(&self) -> Option<Duration> {
+        match self.lag_string.is_empty() {
+            true => Some(Duration::zero()),
+            false => convert_string_to_duration(&self.lag_string[..]).ok()
+        }
     }
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
 pub struct PhaseInstanceSpec {
+    /// An identifier for this phase, so that other phases can depend on it. If not specified, the
+    /// phase is identified by its template.
+    #[serde(default)]
+    pub id: Option<String>,
+
     #[serde(default = "String::new")]
     pub description: String,
 
@@ -41,16 +154,125 @@ pub struct PhaseInstanceSpec {
 
     #[serde(rename = "duration")]
     #[serde(default = "String::new")]
-    pub duration_string: String
+    pub duration_string: String,
+
+    /// Resource types whose `Resource`s are kept from the previous phase. If specified, this
+    /// overrides the `carryOver` of the `ProductionPhaseTemplate`.
+    #[serde(rename = "carryOver")]
+    #[serde(default)]
+    pub carry_over: Option<Vec<ResourceType>>,
+
+    /// The predecessors of this phase. If not specified, the phase starts once the previous phase
+    /// in the recipe has finished. If empty, the phase starts at the start of the recipe.
+    #[serde(default)]
+    pub after: Option<Vec<PhaseDependencySpec>>,
+
+    /// The minimum time to wait after the predecessors of this phase allow it to start.
+    #[serde(rename = "minGap")]
+    #[serde(default = "String::new")]
+    pub min_gap_string: String,
+
+    /// The maximum time to wait after the predecessors of this phase allow it to start, e.g.
+    /// because the beer spoils otherwise. If the resources of the phase are busy for longer, the
+    /// whole recipe starts later instead.
+    #[serde(rename = "maxGap")]
+    #[serde(default = "String::new")]
+    pub max_gap_string: String,
+
+    /// The date at which this phase has to start, e.g. a brew day booked with another brewery.
+    /// The phases before it are scheduled backwards from it, so that they end as late as
+    /// possible, and the phases after it forwards.
+    #[serde(rename = "start")]
+    #[serde(default)]
+    pub start_string: Option<String>,
+
+    /// Specific resources, by name or id, that this phase uses, e.g. `["Big Bertha"]`. Each of
+    /// them takes the place of the resources of its type that would otherwise be allocated, and
+    /// the phase fails to schedule if one of them is taken when the phase is able to start.
+    #[serde(default)]
+    pub resources: Vec<ResourceSelector>,
+
+    /// Resources, by name or id, that this phase would rather use, most preferred first. Unlike
+    /// `resources`, these are only allocated if they are free when the phase is able to start;
+    /// otherwise, resources of their type are allocated as usual.
+    #[serde(rename = "preferredResources")]
+    #[serde(default)]
+    pub preferred_resources: Vec<ResourceSelector>
 }
 
 impl PhaseInstanceSpec {
+    /// Retrieve the identifier of this phase within its recipe, i.e. its `id`, if specified, or
+    /// the id of its template, otherwise.
+    pub fn get_id(&self) -> &str {
+        match &self.id {
+            Some(x) => &x[..],
+            None => &self.template[..]
+        }
+    }
+
+    /// Retrieve the date at which this phase is pinned to start, if one was specified.
+    ///
+    /// # Returns
+    /// * A `Result` containing either an `Option` with the start date (or `None` if the phase is
+    ///   not pinned), or a `ParseError` that lets the client know why the parsing failed.
+    pub fn start_date(&self) -> Result<Option<NaiveDateTime>, ParseError> {
+        match &self.start_string {
+            Some(x) => get_naive_date_time_from_string(&x[..]).map(Some),
+            None => Ok(None)
+        }
+    }
+
     pub fn duration(&self) -> Option<Duration> {
         match self.duration_string.is_empty() {
             true => None,
-            false => convert_string_to_duration(&self.duration_string[..])
+            false => convert_string_to_duration(&self.duration_string[..]).ok()
+        }
+    }
+
+    pub fn min_gap(&self) -> Option<Duration> {
+        match self.min_gap_string.is_empty() {
+            true => None,
+            false => convert_string_to_duration(&self.min_gap_string[..]).ok()
         }
     }
+
+    pub fn max_gap(&self) -> Option<Duration> {
+        match self.max_gap_string.is_empty() {
+            true => None,
+            false => convert_string_to_duration(&self.max_gap_string[..]).ok()
+        }
+    }
+
+    /// Retrieve the resource types that are carried over from the previous phase.
+    ///
+    /// # Arguments
+    /// - `template`: The `ProductionPhaseTemplate` this phase is constructed from.
+    ///
+    /// # Returns
+    /// - The `carryOver` resource types of this spec, if specified, or those of `template`,
+    ///   otherwise.
+    pub fn carry_over(&self, template: &ProductionPhaseTemplate) -> Vec<ResourceType> {
+        match &self.carry_over {
+            Some(x) => x.clone(),
+            None => template.carry_over.clone()
+        }
+    }
+
+    /// Retrieve all resource types used by this phase, both those that are allocated anew and
+    /// those carried over from the previous phase.
+    ///
+    /// # Arguments
+    /// - `template`: The `ProductionPhaseTemplate` this phase is constructed from.
+    pub fn resources_needed(&self, template: &ProductionPhaseTemplate) -> Vec<ResourceType> {
+        let mut resources_needed: Vec<ResourceType> = template.resources_needed.clone();
+        for next_type in self.carry_over(template) {
+            if !resources_needed.contains(&next_type) {
+                resources_needed.push(next_type);
+            }
+        }
+
+        resources_needed
+    }
 }
 
 #[derive(Clone, PartialEq, Debug)]
@@ -87,12 +309,12 @@ impl PhaseInstance {
     pub fn new(id: usize, description: String, color_hex: String, duration: Duration,
                start_date: NaiveDateTime, resources: Vec<Resource>) -> Self {
         PhaseInstance{
-            description: description,
-            id: id,
-            color_hex: color_hex,
-            duration: duration,
+            description,
+            id,
+            color_hex,
+            duration,
             dependencies: vec![],
-            start_date: start_date,
+            start_date,
             resources_used: resources
         }
     }
@@ -106,7 +328,7 @@ impl PhaseInstance {
         }
     }
 
-    /// Retrieve a `String` representing this `PhaseInstance` in PLA format.
+    /// Retrieve a `String` representing this `PhaseInstance` in PLA format, using calendar dates.
     ///
     /// # Arguments
     /// - `self`: A borrowed reference to this `PhaseInstance`.
@@ -116,12 +338,24 @@ impl PhaseInstance {
     /// - A `String` containing the data from this `PhaseInstance` in PLA format.
     ///
     pub fn get_string_in_pla_format(&self, initial_indent: usize) -> String {
-        // If the time is set to start at midnight, then let's just output the date.
-        let mut start_date_as_string: String = self.start_date.format("%Y-%m-%d %H").to_string();
-        let midnight: NaiveTime = NaiveTime::from_hms(0, 0, 0);
-        if self.start_date.time() == midnight {
-            start_date_as_string = self.start_date.date().to_string();
-        }
+        self.get_string_in_pla_format_from_day_zero(initial_indent, None)
+    }
+
+    /// Retrieve a `String` representing this `PhaseInstance` in PLA format.
+    ///
+    /// # Arguments
+    /// - `self`: A borrowed reference to this `PhaseInstance`.
+    /// - `initial_indent`: A `usize` indicating the indentation to use for the output `String`.
+    /// - `day_zero`: The date that the start of this `PhaseInstance` is counted from, in days, or
+    ///   `None` to output its start as a calendar date.
+    ///
+    /// # Returns
+    /// - A `String` containing the data from this `PhaseInstance` in PLA format.
+    ///
+    pub fn get_string_in_pla_format_from_day_zero(&self, initial_indent: usize,
+                                                  day_zero: Option<NaiveDateTime>) -> String {
+        // If the time is set to start at midnight, then the hour is left out.
+        let start_date_as_string: String = get_pla_date_string(self.start_date, day_zero);
 
         let mut builder = Builder::default();
         builder.append(format!("{}[{}] {}\n", get_space_indent(initial_indent), self.id, self.description));
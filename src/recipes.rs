@@ -7,7 +7,8 @@ use chrono::{NaiveDate, NaiveDateTime, NaiveTime, ParseError};
 use super::phases::PhaseInstanceSpec;
 use super::phases::PhaseInstance;
 
-use super::util::{get_space_indent, get_naive_date_time_from_string};
+use super::util::{get_date_string, get_space_indent, get_naive_date_time_from_string};
+use super::volume::{convert_string_to_volume, Volume};
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
 
@@ -32,10 +33,81 @@ pub struct RecipeSpec {
     pub phase_specs: Vec<PhaseInstanceSpec>,
 
     #[serde(rename="start")]
-    pub start_string: Option<String>
+    pub start_string: Option<String>,
+
+    /// The volume of a single batch of this recipe, e.g. `10g`. Vessels that are too small to hold
+    /// the batch will not be allocated to it.
+    #[serde(rename="batchSize")]
+    #[serde(default)]
+    pub batch_size_string: Option<String>,
+
+    /// The date by which the final phase of this recipe should have ended, e.g. for an event or a
+    /// taproom release. Recipes that end later are reported as late.
+    #[serde(rename="dueDate")]
+    #[serde(default)]
+    pub due_date_string: Option<String>,
+
+    /// The date by which the final phase of this recipe has to have ended. If given, the recipe is
+    /// scheduled backwards from this date, starting as late as possible, but no earlier than its
+    /// `start`. It is also due at this date, unless a `dueDate` is given.
+    #[serde(rename="readyBy")]
+    #[serde(default)]
+    pub ready_by_string: Option<String>,
+
+    /// How often this recipe is brewed, if it is brewed on a cadence rather than once. Each brew
+    /// is scheduled as a `Recipe` of its own.
+    #[serde(default)]
+    pub repeat: Option<RepeatSpec>,
+
+    /// How important this recipe is compared to other recipes. When recipes contend for the same
+    /// resources, those with a higher priority are given the first choice. Defaults to `0`.
+    #[serde(default)]
+    pub priority: i32,
+
+    /// The name of the site at which this recipe is brewed. Its dates are given in the time zone
+    /// of that site, if it has one, and in the time zone of the timeline otherwise.
+    #[serde(default)]
+    pub site: Option<String>
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+/// A specification of how often a [RecipeSpec](RecipeSpec) is brewed, e.g. every three weeks.
+///
+/// # Notes
+/// Either `count` or `until` has to be specified. If both are, the recipe is brewed until either
+/// limit is reached.
+pub struct RepeatSpec {
+    /// How often the recipe is brewed: `daily`, `weekly`, `monthly`, or a duration such as `3w`.
+    #[serde(rename="every")]
+    pub every_string: String,
+
+    /// The number of times the recipe is brewed.
+    #[serde(default)]
+    pub count: Option<usize>,
+
+    /// The latest date at which a brew of the recipe can start.
+    #[serde(rename="until")]
+    #[serde(default)]
+    pub until_string: Option<String>
 }
 
 impl RecipeSpec {
+    /// Determine if a name refers to one of the instances of this recipe, e.g. `House IPA #3` for
+    /// a repeated recipe named `House IPA`.
+    ///
+    /// # Arguments
+    /// - `name`: The name to check.
+    /// - `instance_count`: The number of instances this recipe is expanded into.
+    pub fn is_instance_name(&self, name: &str, instance_count: usize) -> bool {
+        match &self.repeat {
+            Some(_) => name.strip_prefix(&self.name[..])
+                           .and_then(|x| x.strip_prefix(" #"))
+                           .and_then(|x| x.parse::<usize>().ok())
+                           .is_some_and(|x| x > 0 && x <= instance_count),
+            None => false
+        }
+    }
+
     /// Retrieve the start date of this `Recipe`, as a `NaiveDateTime`, if it can be parsed from
     /// the input string.
     ///
@@ -47,7 +119,49 @@ impl RecipeSpec {
     pub fn start_date(&self) -> Result<NaiveDateTime, ParseError> {
         match &self.start_string {
             Some(x) => get_naive_date_time_from_string(&x[..]),
-            None => Ok(NaiveDateTime::new(NaiveDate::from_ymd(1970, 1, 1), NaiveTime::from_hms(0, 0, 0)))
+            None => Ok(NaiveDateTime::new(NaiveDate::from_ymd_opt(1970, 1, 1).unwrap(),
+                                              NaiveTime::from_hms_opt(0, 0, 0).unwrap()))
+        }
+    }
+
+    /// Retrieve the due date of this `Recipe`, as a `NaiveDateTime`, if one was specified.
+    ///
+    /// # Returns
+    /// * A `Result` containing either an `Option` with the due date (or `None` if no due date was
+    ///   specified), or a `ParseError` that lets the client know why the parsing failed.
+    pub fn due_date(&self) -> Result<Option<NaiveDateTime>, ParseError> {
+        match &self.due_date_string {
+            Some(x) => get_naive_date_time_from_string(&x[..]).map(Some),
+            None => Ok(None)
+        }
+    }
+
+    /// Retrieve the date by which this `Recipe` has to be ready, as a `NaiveDateTime`, if one was
+    /// specified.
+    ///
+    /// # Returns
+    /// * A `Result` containing either an `Option` with the ready-by date (or `None` if no ready-by
+    ///   date was specified), or a `ParseError` that lets the client know why the parsing failed.
+    pub fn ready_by(&self) -> Result<Option<NaiveDateTime>, ParseError> {
+        match &self.ready_by_string {
+            Some(x) => get_naive_date_time_from_string(&x[..]).map(Some),
+            None => Ok(None)
+        }
+    }
+
+    /// Retrieve the batch size of this `Recipe`, as a [Volume](super::volume::Volume).
+    ///
+    /// # Returns
+    /// * A `Result` containing either an `Option` with the batch size (or `None` if no batch size
+    ///   was specified), or an `Err` containing the batch size string if it could not be parsed
+    ///   as a volume.
+    pub fn batch_size(&self) -> Result<Option<Volume>, String> {
+        match &self.batch_size_string {
+            Some(x) => match convert_string_to_volume(&x[..]) {
+                Some(volume) => Ok(Some(volume)),
+                None => Err(x.clone())
+            },
+            None => Ok(None)
         }
     }
 }
@@ -67,17 +181,51 @@ pub struct Recipe {
     pub name: String,
     pub color: String,
     pub phases: Vec<PhaseInstance>,
-    pub start_date: NaiveDateTime
+    pub start_date: NaiveDateTime,
+    pub due_date: Option<NaiveDateTime>
 }
 
 impl Recipe {
-    pub fn get_phase_iterator(&self) -> std::slice::Iter<PhaseInstance> {
+    pub fn get_phase_iterator(&self) -> std::slice::Iter<'_, PhaseInstance> {
         self.phases.iter()
     }
 
+    /// Retrieve the date at which the last phase of this `Recipe` ends, or `None` if it has no
+    /// phases.
+    pub fn end_date(&self) -> Option<NaiveDateTime> {
+        self.phases.iter().map(|phase| phase.start_date + phase.duration).max()
+    }
+
+    /// Determine if this `Recipe` ends after its due date. A `Recipe` without a due date is never
+    /// late.
+    pub fn is_late(&self) -> bool {
+        match (self.due_date, self.end_date()) {
+            (Some(due_date), Some(end_date)) => end_date > due_date,
+            _ => false
+        }
+    }
+
+    /// Retrieve a `String` representing this `Recipe` in PLA format, using calendar dates.
     pub fn get_string_in_pla_format(&self, initial_indent: usize) -> String {
+        self.get_string_in_pla_format_from_day_zero(initial_indent, None)
+    }
+
+    /// Retrieve a `String` representing this `Recipe` in PLA format.
+    ///
+    /// # Arguments
+    /// - `initial_indent`: A `usize` indicating the indentation to use for the output `String`.
+    /// - `day_zero`: The date that dates are counted from, in days, or `None` to output calendar
+    ///   dates.
+    pub fn get_string_in_pla_format_from_day_zero(&self, initial_indent: usize,
+                                                  day_zero: Option<NaiveDateTime>) -> String {
         let mut builder: Builder = Builder::default();
-        builder.append(format!("[{}] {}\n", self.id, self.name));
+        match (self.is_late(), self.due_date) {
+            (true, Some(due_date)) => {
+                builder.append(format!("[{}] {} (late: due {})\n", self.id, self.name,
+                                       get_date_string(due_date, day_zero)));
+            },
+            _ => builder.append(format!("[{}] {}\n", self.id, self.name))
+        }
 
         for next_phase in self.get_phase_iterator() {
             builder.append(format!("{}child {}\n", get_space_indent(initial_indent), next_phase.id));
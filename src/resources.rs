@@ -1,11 +1,16 @@
-use chrono::Duration;
-use chrono::NaiveDateTime;
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime};
 
 use chrono_period::NaivePeriod;
 
 use serde::{Serialize, Deserialize, Serializer, Deserializer};
 
 use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+use super::allocation::{AllocationStrategy, LowestId};
+use super::timezone::TimeZone;
+use super::volume::{convert_string_to_volume, Volume, VolumeUnit};
 
 #[derive(Clone, Debug, PartialEq)]
 
@@ -33,12 +38,43 @@ pub enum ResourceType {
     /// A tank for force-carbonating beer.
     GasTank,
 
+    /// A person, such as a brewer, who works on the phases that need staff. Unlike equipment,
+    /// staff have skills and a limited number of working hours.
+    Staff,
+
     /// A resource type that has not yet been added to the standard enum. The "real" type of the
     /// resource, for the purposes of serialization and deserialization, will be contained in the
     /// string variable present in the enum instance.
     Other(String)
 }
 
+impl ResourceType {
+    /// Determine if a `Resource` of this `ResourceType` holds the beer (or wort) itself, and thus
+    /// must be large enough to hold an entire batch.
+    ///
+    /// # Notes
+    /// `Other` resource types are assumed to be vessels. A `Resource` that is not a vessel (e.g.
+    /// a `GasTank`) is never checked against the batch size of a `Recipe`.
+    pub fn is_vessel(&self) -> bool {
+        !matches!(self, ResourceType::Kegerator | ResourceType::GasTank | ResourceType::Staff)
+    }
+
+    /// Retrieve the name of this `ResourceType`, as used in BPD files, e.g. `fermentor`.
+    pub fn name(&self) -> &str {
+        match self {
+            ResourceType::Fermentor => "fermentor",
+            ResourceType::Kettle => "kettle",
+            ResourceType::MashTun => "mashtun",
+            ResourceType::LauterTun => "lautertun",
+            ResourceType::Keg => "keg",
+            ResourceType::Kegerator => "kegerator",
+            ResourceType::GasTank => "gastank",
+            ResourceType::Staff => "staff",
+            ResourceType::Other(other) => other
+        }
+    }
+}
+
 impl From<&str> for ResourceType {
     /// Convert from a string slice (`&str`) to a `ResourceType`.
     ///
@@ -66,6 +102,7 @@ impl From<&str> for ResourceType {
             "keg" => ResourceType::Keg,
             "kegerator" => ResourceType::Kegerator,
             "gastank" => ResourceType::GasTank,
+            "staff" => ResourceType::Staff,
             _ => ResourceType::Other(res.to_string())
         }
     }
@@ -136,20 +173,11 @@ impl Serialize for ResourceType {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where S: Serializer
     {
-        serializer.serialize_str(match *self {
-            ResourceType::Fermentor => "fermentor",
-            ResourceType::Kettle => "kettle",
-            ResourceType::MashTun => "mashtun",
-            ResourceType::LauterTun => "lautertun",
-            ResourceType::Keg => "keg",
-            ResourceType::Kegerator => "kegerator",
-            ResourceType::GasTank => "gastank",
-            ResourceType::Other(ref other) => other
-        })
+        serializer.serialize_str(self.name())
     }
 }
 
-/// A piece of equipment that must be used in order to produce a `Recipe`.
+/// A piece of equipment, or a member of staff, that must be used in order to produce a `Recipe`.
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct Resource {
     pub id: usize,
@@ -158,17 +186,172 @@ pub struct Resource {
     #[serde(rename="type")]
     pub resource_type: ResourceType,
 
+    /// The other `ResourceType`s this `Resource` serves as, e.g. `["mashtun", "lautertun"]` for an
+    /// all-in-one brewing system whose `type` is `kettle`.
+    ///
+    /// # Notes
+    /// A `Resource` is allocated once to a phase that needs several of its types, and fills all of
+    /// them. Its capacity, and whether it is a vessel at all, are still those of its `type`.
+    #[serde(rename="additionalTypes")]
+    #[serde(default="Vec::new")]
+    pub additional_types: Vec<ResourceType>,
+
     /// A `String` denoting the capacity for this `Resource`.
     ///
     /// # Notes
-    /// This is currently unused, but will likely be used in the future in the form of an `enum` so
-    /// that we can check to see if a particular `Recipe` requires more than one `Resource` of a
-    /// particular type.
+    /// If this is a volume (see [capacity](Resource::capacity)) and the `Resource` is a vessel,
+    /// it will only be allocated for batches that fit within it. Other capacities, such as `2k`
+    /// for a kegerator holding two kegs, are not currently checked. Staff need no capacity.
     #[serde(rename="capacity")]
+    #[serde(default = "String::new")]
     pub capacity_str: String,
 
+    /// A `String` denoting the time this `Resource` needs after each allocation (e.g. for
+    /// cleaning and sanitizing) before it can be allocated again, e.g. `1d`.
+    ///
+    /// # Notes
+    /// This is only the value specified in the BPD file. The parsed value, which also takes the
+    /// `turnarounds` of the [ProductionSchedule](super::ProductionSchedule) into account, is
+    /// stored in [turnaround](Resource::turnaround).
+    #[serde(rename="turnaround")]
+    #[serde(default)]
+    pub turnaround_str: Option<String>,
+
+    /// The time this `Resource` needs after each allocation before it can be allocated again.
+    #[serde(skip_serializing, skip_deserializing, default="Duration::zero")]
+    pub turnaround: Duration,
+
+    /// Windows during which this `Resource` is out of service, e.g. for maintenance, as specified
+    /// in the BPD file.
+    #[serde(rename="downtime")]
+    #[serde(default="Vec::new")]
+    pub downtime_specs: Vec<DowntimeSpec>,
+
+    /// The periods during which this `Resource` is out of service, along with a description of
+    /// each. These are also part of `allocated_periods`.
     #[serde(skip_serializing, skip_deserializing, default="Vec::new")]
-    pub allocated_periods: Vec<NaivePeriod>
+    pub downtime_periods: Vec<(String, NaivePeriod)>,
+
+    /// The skills of a member of staff, e.g. `canning`, which phase templates can require in
+    /// their `staffNeeded`.
+    #[serde(default="Vec::new")]
+    pub skills: Vec<String>,
+
+    /// The maximum number of hours a member of staff works on a single calendar day, if limited.
+    /// Days start at midnight in [time_zone](Resource::time_zone).
+    #[serde(rename="maxHoursPerDay")]
+    #[serde(default)]
+    pub max_hours_per_day: Option<f64>,
+
+    /// The maximum number of hours a member of staff works in a single week, from Monday to
+    /// Sunday, if limited. Weeks start at midnight on Monday in [time_zone](Resource::time_zone).
+    #[serde(rename="maxHoursPerWeek")]
+    #[serde(default)]
+    pub max_hours_per_week: Option<f64>,
+
+    /// The name of the site at which this `Resource` is located. Its downtime windows are given in
+    /// the time zone of that site, if it has one, and in the time zone of the timeline otherwise.
+    #[serde(default)]
+    pub site: Option<String>,
+
+    /// The time zone of the site of this `Resource`, or of the timeline, if either has one. The
+    /// working hours of a member of staff are limited per day and per week of its local time.
+    #[serde(skip_serializing, skip_deserializing)]
+    pub time_zone: Option<TimeZone>,
+
+    #[serde(skip_serializing, skip_deserializing, default="Vec::new")]
+    pub allocated_periods: Vec<NaivePeriod>,
+
+    /// The allocations of this `Resource` to phases, i.e. `allocated_periods` without the downtime
+    /// windows, in chronological order. This is kept up to date as this `Resource` is allocated,
+    /// so that allocation strategies don't have to go through `allocated_periods` every time.
+    #[serde(skip_serializing, skip_deserializing, default="Vec::new")]
+    working_periods: Vec<NaivePeriod>,
+
+    /// The total time this `Resource` is allocated to phases, i.e. the sum of `working_periods`.
+    #[serde(skip_serializing, skip_deserializing, default="Duration::zero")]
+    working_duration: Duration
+}
+
+/// A specification of a window during which a [Resource](Resource) is out of service, e.g.
+/// `{ "description": "Gasket replacement", "start": "2020-03-01", "end": "2020-03-10" }`.
+///
+/// # Notes
+/// A window may repeat `every` so often (either a duration such as `2w`, or one of `daily`,
+/// `weekly` or `monthly`), in which case either a `count` of windows, or an `until` date after
+/// which no more windows begin, must also be specified.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct DowntimeSpec {
+    #[serde(default = "String::new")]
+    pub description: String,
+
+    #[serde(rename="start")]
+    pub start_string: String,
+
+    /// The date at which the `Resource` is back in service. If neither this nor `duration` is
+    /// specified, the window lasts a single day.
+    #[serde(rename="end")]
+    #[serde(default)]
+    pub end_string: Option<String>,
+
+    #[serde(rename="duration")]
+    #[serde(default)]
+    pub duration_string: Option<String>,
+
+    #[serde(rename="every")]
+    #[serde(default)]
+    pub every_string: Option<String>,
+
+    #[serde(default)]
+    pub count: Option<usize>,
+
+    #[serde(rename="until")]
+    #[serde(default)]
+    pub until_string: Option<String>
+}
+
+/// A specification of the staff a phase needs, e.g. `{ "skill": "canning", "count": 1 }`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct StaffRequirement {
+    /// The skill each of the staff must have, or `None` if anyone will do.
+    #[serde(default)]
+    pub skill: Option<String>,
+
+    /// The number of staff needed. Defaults to one.
+    #[serde(default = "get_default_staff_count")]
+    pub count: usize
+}
+
+fn get_default_staff_count() -> usize {
+    1
+}
+
+/// A reference to a specific [Resource](Resource), either by its `name`, e.g. `"Big Bertha"`, or
+/// by its `id`, e.g. `3`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(untagged)]
+pub enum ResourceSelector {
+    Id(usize),
+    Name(String)
+}
+
+impl ResourceSelector {
+    /// Determine if a `Resource` is the one this `ResourceSelector` refers to.
+    pub fn matches(&self, res: &Resource) -> bool {
+        match self {
+            ResourceSelector::Id(x) => res.id == *x,
+            ResourceSelector::Name(x) => res.name == *x
+        }
+    }
+}
+
+impl fmt::Display for ResourceSelector {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ResourceSelector::Id(x) => write!(f, "with id {}", x),
+            ResourceSelector::Name(x) => write!(f, "\"{}\"", x)
+        }
+    }
 }
 
 impl Resource {
@@ -188,11 +371,55 @@ impl Resource {
     pub fn new(id: usize, name: &str, resource_type: ResourceType,
                capacity_str: &str) -> Resource {
         Resource {
-            id: id,
+            id,
             name: name.to_string(),
-            resource_type: resource_type,
+            resource_type,
+            additional_types: vec![],
             capacity_str: capacity_str.to_string(),
-            allocated_periods: vec![]
+            turnaround_str: None,
+            turnaround: Duration::zero(),
+            downtime_specs: vec![],
+            downtime_periods: vec![],
+            skills: vec![],
+            max_hours_per_day: None,
+            max_hours_per_week: None,
+            site: None,
+            time_zone: None,
+            allocated_periods: vec![],
+            working_periods: vec![],
+            working_duration: Duration::zero()
+        }
+    }
+
+    /// Determine if this `Resource` is of a specific `ResourceType`, either as its `type` or as one
+    /// of its `additional_types`.
+    pub fn is_of_type(&self, resource_type: &ResourceType) -> bool {
+        self.resource_type == *resource_type || self.additional_types.contains(resource_type)
+    }
+
+    /// Retrieve the capacity of this `Resource` as a [Volume](super::volume::Volume), if it is
+    /// specified as one.
+    pub fn capacity(&self) -> Option<Volume> {
+        convert_string_to_volume(&self.capacity_str[..])
+    }
+
+    /// Determine if this `Resource` is able to hold a batch of a given size.
+    ///
+    /// # Arguments
+    /// - `batch_size`: An `Option` containing the size of the batch, or `None` if the batch size
+    ///   is not known.
+    ///
+    /// # Returns
+    /// - `false`, if this `Resource` is a vessel with a capacity smaller than `batch_size`;
+    ///   `true`, otherwise.
+    pub fn can_hold_batch(&self, batch_size: Option<&Volume>) -> bool {
+        if !self.resource_type.is_vessel() {
+            return true;
+        }
+
+        match (self.capacity(), batch_size) {
+            (Some(capacity), Some(batch)) => capacity.can_hold(batch),
+            _ => true
         }
     }
 
@@ -207,15 +434,42 @@ impl Resource {
     /// # Returns
     /// - `true`, if this `Resource` is already allocated _at any point_ during the requested
     ///   period comprising `start` - `end`, including `end`; `false`, otherwise.
+    ///
+    /// # Notes
+    /// The [turnaround](Resource::turnaround) of this `Resource` is considered part of each
+    /// allocation, including the requested one.
     pub fn is_allocated_over_start_duration(&self, start: NaiveDateTime,
                                             duration: Duration) -> bool {
-        let intersection_period = NaivePeriod::from_start_duration(start, duration);
+        let intersection_period = NaivePeriod::from_start_duration(start,
+                                                                   duration + self.turnaround);
 
         self.allocated_periods.iter().any(|period| {
-            period.intersects_with(intersection_period)
+            self.get_blocked_period(period).intersects_with(intersection_period)
         })
     }
 
+    /// Retrieve the `NaivePeriod` during which this `Resource` cannot be used because of one of
+    /// its allocations, i.e. the allocated `NaivePeriod` followed by the turnaround time.
+    pub fn get_blocked_period(&self, period: &NaivePeriod) -> NaivePeriod {
+        NaivePeriod::new(period.start, period.end + self.turnaround)
+    }
+
+    /// Retrieve the `NaivePeriod`s during which this `Resource` is being turned around after each
+    /// of its allocations, in chronological order.
+    ///
+    /// # Returns
+    /// - A `Vec` containing one `NaivePeriod` per allocation, beginning when the allocation ends.
+    ///   If this `Resource` has no turnaround time, the `Vec` is empty.
+    pub fn get_turnaround_periods(&self) -> Vec<NaivePeriod> {
+        if self.turnaround <= Duration::zero() {
+            return vec![];
+        }
+
+        self.allocated_periods.iter()
+          .map(|period| NaivePeriod::from_start_duration(period.end, self.turnaround))
+          .collect()
+    }
+
     /// Determine if this `Resource` is allocated at any time during a specific `NaivePeriod`.
     ///
     /// # Arguments
@@ -240,6 +494,10 @@ impl Resource {
         self.allocated_periods.push(allocation_period);
         self.allocated_periods.sort_by(|a, b| a.start.partial_cmp(&b.start).unwrap());
 
+        let index: usize = self.working_periods.partition_point(|period| period.start <= start);
+        self.working_periods.insert(index, allocation_period);
+        self.working_duration = self.working_duration + duration;
+
         Some(self)
     }
 
@@ -247,24 +505,219 @@ impl Resource {
         self.allocate_over_start_duration(period.start, period.duration())
     }
 
+    /// Take this `Resource` out of service for a `NaivePeriod`, so that it is not allocated
+    /// during it.
+    ///
+    /// # Arguments
+    /// - `description`: A string slice describing why this `Resource` is out of service.
+    /// - `period`: The [NaivePeriod](chrono_period::NaivePeriod) during which this `Resource` is
+    ///   out of service.
+    ///
+    /// # Notes
+    /// Unlike an allocation, a downtime window may overlap other downtime windows. Downtime should
+    /// be added before any allocations are made, as it is not checked against them. Like any
+    /// allocation, a downtime window is followed by the turnaround time of this `Resource`.
+    pub fn add_downtime(&mut self, description: &str, period: NaivePeriod) {
+        self.downtime_periods.push((description.to_string(), period));
+        self.allocated_periods.push(period);
+        self.allocated_periods.sort_by(|a, b| a.start.partial_cmp(&b.start).unwrap());
+    }
+
+    /// Extend an existing allocation of this `Resource` so that it ends at a later date.
+    ///
+    /// # Arguments
+    /// - `start`: The [NaiveDateTime](chrono::NaiveDateTime) at which the existing allocation
+    ///   begins.
+    /// - `end`: The [NaiveDateTime](chrono::NaiveDateTime) at which the extended allocation should
+    ///   end.
+    ///
+    /// # Returns
+    /// - `Some`, containing this `Resource`, if the allocation now lasts until at least `end`;
+    ///   `None`, if there is no allocation beginning at `start`, or if this `Resource` is
+    ///   allocated to something else between the end of that allocation and `end`. In the latter
+    ///   cases, nothing is changed.
+    pub fn extend_allocation(&mut self, start: NaiveDateTime,
+                             end: NaiveDateTime) -> Option<&Resource> {
+        let index = self.allocated_periods.iter().position(|period| period.start == start)?;
+        if end <= self.allocated_periods[index].end {
+            return Some(self);
+        }
+
+        let extended_period = NaivePeriod::new(start, end);
+        let blocked_period = self.get_blocked_period(&extended_period);
+        let is_blocked = self.allocated_periods.iter().enumerate().any(|(other_index, period)| {
+            other_index != index && self.get_blocked_period(period).intersects_with(blocked_period)
+        });
+
+        if is_blocked {
+            return None;
+        }
+
+        self.allocated_periods[index] = extended_period;
+
+        // Downtime windows can be extended too, but they are not working periods.
+        if let Some(working_period) = self.working_periods.iter_mut()
+                                          .find(|period| period.start == start) {
+            self.working_duration = self.working_duration + (end - working_period.end);
+            *working_period = extended_period;
+        }
+
+        Some(self)
+    }
+
+    /// Retrieve the date at which the allocations preventing an existing allocation of this
+    /// `Resource` from being extended are over.
+    ///
+    /// # Arguments
+    /// - `start`: The [NaiveDateTime](chrono::NaiveDateTime) at which the existing allocation
+    ///   begins.
+    /// - `end`: The [NaiveDateTime](chrono::NaiveDateTime) at which the extended allocation should
+    ///   end.
+    ///
+    /// # Returns
+    /// - An `Option` containing the latest end, including turnaround time, of the other
+    ///   allocations that overlap the extended allocation, or `None` if there are none.
+    pub fn get_end_of_allocations_blocking_extension(&self, start: NaiveDateTime,
+                                                     end: NaiveDateTime) -> Option<NaiveDateTime> {
+        let blocked_period = self.get_blocked_period(&NaivePeriod::new(start, end));
+
+        self.allocated_periods.iter()
+          .filter(|period| period.start != start)
+          .map(|period| self.get_blocked_period(period))
+          .filter(|period| period.intersects_with(blocked_period))
+          .map(|period| period.end)
+          .max()
+    }
+
     pub fn get_earliest_free_date_for_period(&self, period: NaivePeriod) -> NaiveDateTime {
         if !self.is_allocated_over_start_duration(period.start, period.duration()) {
             return period.start;
         }
 
-        self.allocated_periods.iter().filter(|needle| {
+        self.allocated_periods.iter().map(|needle| self.get_blocked_period(needle)).filter(|needle| {
             // Discard any where the end date is before the desired start date.
             if needle.end.timestamp() < period.start.timestamp() {
                 return false;
             }
 
             let end_date_plus_one_second = needle.end + Duration::seconds(1);
-            let is_alloc = !self.is_allocated_over_start_duration(end_date_plus_one_second, period.duration());
-            is_alloc
+            !self.is_allocated_over_start_duration(end_date_plus_one_second, period.duration())
         }).map(|needle| {
             needle.end + Duration::seconds(1)
         }).take(1).next().unwrap()
     }
+
+    /// Determine if this `Resource` is a member of staff with a specific skill.
+    ///
+    /// # Arguments
+    /// - `skill`: The skill to check for, or `None` if any member of staff will do.
+    pub fn has_skill(&self, skill: Option<&str>) -> bool {
+        self.resource_type == ResourceType::Staff
+          && skill.is_none_or(|x| self.skills.iter().any(|y| y == x))
+    }
+
+    /// Retrieve the `NaivePeriod`s during which this `Resource` is allocated to phases, i.e. its
+    /// allocations other than its downtime windows, in chronological order.
+    pub fn get_working_periods(&self) -> Vec<NaivePeriod> {
+        self.working_periods.clone()
+    }
+
+    /// Retrieve the total time this `Resource` is allocated to phases, i.e. the sum of its
+    /// [working periods](Resource::get_working_periods).
+    pub fn get_working_duration(&self) -> Duration {
+        self.working_duration
+    }
+
+    /// Retrieve the last allocation of this `Resource` to a phase that started at or before a
+    /// specific date.
+    ///
+    /// # Returns
+    /// - An `Option` containing the `NaivePeriod` of the allocation, or `None` if this `Resource`
+    ///   was not allocated to any phase that started by `date`.
+    pub fn get_last_working_period_before(&self, date: NaiveDateTime) -> Option<NaivePeriod> {
+        let count: usize = self.working_periods.partition_point(|period| period.start <= date);
+
+        count.checked_sub(1).map(|index| self.working_periods[index])
+    }
+
+    /// Determine if this `Resource` can be allocated over several `NaivePeriod`s without working
+    /// more than its maximum hours on any day or in any week.
+    ///
+    /// # Arguments
+    /// - `periods`: The [NaivePeriod](chrono_period::NaivePeriod)s of the requested allocation.
+    ///
+    /// # Returns
+    /// - `true`, if the hours of the requested allocation, added to the hours this `Resource` is
+    ///   already allocated for, stay within [max_hours_per_day](Resource::max_hours_per_day) and
+    ///   [max_hours_per_week](Resource::max_hours_per_week) on every day and in every week it
+    ///   touches; `false`, otherwise.
+    pub fn is_within_working_hour_limits(&self, periods: &[NaivePeriod]) -> bool {
+        let working_periods: Vec<NaivePeriod> = self.get_working_periods();
+        let time_zone: Option<&TimeZone> = self.time_zone.as_ref();
+        let limits: [(Option<f64>, GetWindow); 2] = [
+            (self.max_hours_per_day, get_day_containing),
+            (self.max_hours_per_week, get_week_containing)
+        ];
+
+        limits.iter().all(|(max_hours, get_window)| {
+            let max_hours: f64 = match max_hours {
+                Some(x) => *x,
+                None => return true
+            };
+
+            periods.iter().all(|period| {
+                let mut window: NaivePeriod = get_window(period.start, time_zone);
+                loop {
+                    let hours: f64 = get_hours_within(&working_periods, window)
+                                       + get_hours_within(periods, window);
+                    if hours > max_hours + HOUR_LIMIT_TOLERANCE {
+                        return false;
+                    }
+
+                    if window.end >= period.end {
+                        return true;
+                    }
+
+                    window = get_window(window.end, time_zone);
+                }
+            })
+        })
+    }
+
+    /// Determine if this `Resource` is free during several `NaivePeriod`s, and can work all of them
+    /// within its maximum working hours.
+    pub fn is_free_for_periods(&self, periods: &[NaivePeriod]) -> bool {
+        periods.iter().all(|period| !self.is_allocated_over_period(*period))
+          && self.is_within_working_hour_limits(periods)
+    }
+
+    /// Retrieve the number of hours this `Resource` is allocated to phases in each week, from
+    /// Monday to Sunday.
+    ///
+    /// # Returns
+    /// - A `Vec` containing the start of each week in which this `Resource` is allocated, in
+    ///   chronological order, along with the number of hours it is allocated for in that week.
+    pub fn get_hours_worked_per_week(&self) -> Vec<(NaiveDateTime, f64)> {
+        let working_periods: Vec<NaivePeriod> = self.get_working_periods();
+
+        let mut weeks: Vec<NaivePeriod> = vec![];
+        for period in working_periods.iter() {
+            let mut window: NaivePeriod = get_week_containing(period.start,
+                                                              self.time_zone.as_ref());
+            while window.start < period.end {
+                if !weeks.contains(&window) {
+                    weeks.push(window);
+                }
+
+                window = get_week_containing(window.end, self.time_zone.as_ref());
+            }
+        }
+
+        weeks.sort_by_key(|window| window.start);
+        weeks.into_iter()
+             .map(|window| (window.start, get_hours_within(&working_periods, window)))
+             .collect()
+    }
 }
 
 #[derive(Clone)]
@@ -295,9 +748,26 @@ pub struct PossiblyAllocatedResource {
 /// Thus, it is assumed that `id` fields will be unique within this instance of `ResourceTracker`.
 /// If you have an `id` that is duplicated, the behavior is undefined, but likely will result in
 /// unwanted behavior.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct ResourceTracker {
-    resources: HashMap<usize, Resource>
+    resources: HashMap<usize, Resource>,
+
+    /// The strategy used to choose between free `Resource`s of types without a strategy of their
+    /// own.
+    default_strategy: Arc<dyn AllocationStrategy>,
+
+    /// The strategies used to choose between free `Resource`s of specific types.
+    strategies: Vec<(ResourceType, Arc<dyn AllocationStrategy>)>,
+
+    /// Pairs of a `ResourceType` whose `Resource`s can be used in place of those of another
+    /// `ResourceType`, and that other `ResourceType`, e.g. a unitank and a fermentor.
+    substitutes: Vec<(ResourceType, ResourceType)>
+}
+
+impl Default for ResourceTracker {
+    fn default() -> Self {
+        ResourceTracker::new()
+    }
 }
 
 impl ResourceTracker {
@@ -319,10 +789,123 @@ impl ResourceTracker {
     ///
     pub fn new() -> Self {
         ResourceTracker {
-            resources: HashMap::new()
+            resources: HashMap::new(),
+            default_strategy: Arc::new(LowestId),
+            strategies: vec![],
+            substitutes: vec![]
+        }
+    }
+
+    /// Allow `Resource`s of one type to be used in place of those of another type.
+    ///
+    /// # Arguments
+    /// - `substitute`: The `ResourceType` of the `Resource`s that can be used instead, e.g. a
+    ///   unitank.
+    /// - `resource_type`: The `ResourceType` they can be used for, e.g. a fermentor.
+    ///
+    /// # Notes
+    /// Queries for `Resource`s of `resource_type` consider `Resource`s of type `substitute` as
+    /// well, but a `Resource` whose type is exactly `resource_type` is always allocated in
+    /// preference to a substitute, if one is free. Substitution is not transitive.
+    pub fn add_substitute(&mut self, substitute: ResourceType, resource_type: ResourceType) {
+        if !self.substitutes.contains(&(substitute.clone(), resource_type.clone())) {
+            self.substitutes.push((substitute, resource_type));
         }
     }
 
+    /// Determine if `Resource`s of some type can be used in place of those of another type.
+    pub fn has_substitutes(&self) -> bool {
+        !self.substitutes.is_empty()
+    }
+
+    /// Find a resource type that cannot be filled when several types are needed at the same time,
+    /// as each `Resource`, even one that can be used in place of several types, only fills one
+    /// of them, unless it is of all of those types itself.
+    ///
+    /// # Arguments
+    /// - `resource_types`: The `ResourceType`s needed at the same time.
+    /// - `batch_size`: An `Option` containing the size of the batch the `Resource`s must be able
+    ///   to hold, or `None` if any `Resource` will do.
+    /// - `excluded_ids`: The `id`s of `Resource`s that are already in use for other needs.
+    ///
+    /// # Returns
+    /// - An `Option` containing the first of `resource_types` for which no `Resource` is left once
+    ///   the types before it are filled, or `None` if all of them can be filled.
+    ///
+    /// # Notes
+    /// Allocations are ignored, so this only tells whether enough `Resource`s are tracked at all.
+    /// Types are filled in order, each by the `Resource`s the tracker would allocate for it.
+    pub fn find_unfillable_resource_type(&self, resource_types: &[&ResourceType],
+                                         batch_size: Option<&Volume>, excluded_ids: &[usize])
+      -> Option<ResourceType> {
+        let mut unallocated_tracker: ResourceTracker = ResourceTracker {
+            resources: HashMap::new(),
+            ..self.clone()
+        };
+        for res in self.resources.values().filter(|res| !excluded_ids.contains(&res.id)) {
+            unallocated_tracker.track_resource(Resource {
+                allocated_periods: vec![],
+                downtime_periods: vec![],
+                working_periods: vec![],
+                working_duration: Duration::zero(),
+                ..res.clone()
+            });
+        }
+
+        let start: NaiveDateTime = NaiveDate::from_ymd_opt(1970, 1, 1).unwrap()
+                                             .and_hms_opt(0, 0, 0).unwrap();
+        let period: NaivePeriod = NaivePeriod::from_start_duration(start, Duration::seconds(1));
+        let mut filling_resources: Vec<Resource> = vec![];
+        for resource_type in resource_types.iter() {
+            if filling_resources.iter().any(|res| {
+                res.is_of_type(resource_type) && res.can_hold_batch(batch_size)
+            }) {
+                continue;
+            }
+
+            match unallocated_tracker.allocate_resources_of_type_for_period(resource_type, period,
+                                                                            batch_size) {
+                Some(x) => filling_resources.extend(x),
+                None => return Some((*resource_type).clone())
+            }
+        }
+
+        None
+    }
+
+    /// Determine if a `Resource` can be used where a `Resource` of a specific type is needed,
+    /// either because it is of that type, or because one of its types is a substitute for that
+    /// type.
+    pub fn is_resource_usable_as(&self, res: &Resource, resource_type: &ResourceType) -> bool {
+        res.is_of_type(resource_type)
+          || self.substitutes.iter().any(|(substitute, substituted)| {
+                 res.is_of_type(substitute) && substituted == resource_type
+             })
+    }
+
+    /// Set the [AllocationStrategy](super::allocation::AllocationStrategy) used to choose between
+    /// free `Resource`s of types without a strategy of their own.
+    pub fn set_allocation_strategy(&mut self, strategy: Arc<dyn AllocationStrategy>) {
+        self.default_strategy = strategy;
+    }
+
+    /// Set the [AllocationStrategy](super::allocation::AllocationStrategy) used to choose between
+    /// free `Resource`s of a specific type, replacing any strategy set for that type before.
+    pub fn set_allocation_strategy_for_type(&mut self, resource_type: ResourceType,
+                                            strategy: Arc<dyn AllocationStrategy>) {
+        self.strategies.retain(|(x, _)| *x != resource_type);
+        self.strategies.push((resource_type, strategy));
+    }
+
+    /// Retrieve the [AllocationStrategy](super::allocation::AllocationStrategy) used to choose
+    /// between free `Resource`s of a specific type.
+    pub fn get_allocation_strategy(&self, resource_type: &ResourceType)
+      -> &Arc<dyn AllocationStrategy> {
+        self.strategies.iter()
+          .find(|(x, _)| x == resource_type)
+          .map_or(&self.default_strategy, |(_, strategy)| strategy)
+    }
+
     /// Track a `Resource` using this `ResourceTracker`.
     ///
     /// Calling this method moves the `Resource` in question to be owned by this `ResourceTracker`.
@@ -337,6 +920,36 @@ impl ResourceTracker {
         self.resources.insert(res.id, res);
     }
 
+    /// Determine if at least one `Resource` of a specific `ResourceType` is tracked.
+    ///
+    /// # Arguments
+    /// - `resource_type`: A borrowed reference to a `ResourceType` to check for.
+    ///
+    /// # Returns
+    /// - `true`, if a `Resource` of type `resource_type` is tracked by this `ResourceTracker`;
+    ///   `false`, otherwise.
+    pub fn has_resource_of_type(&self, resource_type: &ResourceType) -> bool {
+        self.has_resource_of_type_with_capacity(resource_type, None)
+    }
+
+    /// Determine if at least one `Resource` of a specific `ResourceType` that can hold a batch of
+    /// a given size is tracked.
+    ///
+    /// # Arguments
+    /// - `resource_type`: A borrowed reference to a `ResourceType` to check for.
+    /// - `batch_size`: An `Option` containing the size of the batch the `Resource` must be able
+    ///   to hold, or `None` if any `Resource` of type `resource_type` will do.
+    ///
+    /// # Returns
+    /// - `true`, if a `Resource` of type `resource_type` able to hold `batch_size` is tracked by
+    ///   this `ResourceTracker`; `false`, otherwise.
+    pub fn has_resource_of_type_with_capacity(&self, resource_type: &ResourceType,
+                                              batch_size: Option<&Volume>) -> bool {
+        self.resources.values()
+          .filter(|res| self.is_resource_usable_as(res, resource_type))
+          .any(|res| res.can_hold_batch(batch_size))
+    }
+
     /// Determine if a `Resource` of a specific `ResourceType` is free during a `NaivePeriod`.
     ///
     /// # Arguments
@@ -349,8 +962,29 @@ impl ResourceTracker {
     ///   otherwise.
     pub fn is_resource_of_type_free_for_period(&self, resource_type: &ResourceType,
                                                period: NaivePeriod) -> bool {
+      self.is_resource_of_type_free_for_period_with_capacity(resource_type, period, None)
+    }
+
+    /// Determine if a `Resource` of a specific `ResourceType` that can hold a batch of a given size
+    /// is free during a `NaivePeriod`.
+    ///
+    /// # Arguments
+    /// - `resource_type`: A borrowed reference to a `ResourceType` to check for.
+    /// - `period`: An instance of [NaivePeriod](chrono_period::NaivePeriod) for which to check
+    ///   against.
+    /// - `batch_size`: An `Option` containing the size of the batch the `Resource` must be able
+    ///   to hold, or `None` if any `Resource` of type `resource_type` will do.
+    ///
+    /// # Returns
+    /// - `true`, if a `Resource` of type `resource_type` able to hold `batch_size` is free for the
+    ///   period `period`; `false`, otherwise.
+    pub fn is_resource_of_type_free_for_period_with_capacity(&self, resource_type: &ResourceType,
+                                                             period: NaivePeriod,
+                                                             batch_size: Option<&Volume>)
+      -> bool {
       self.resources.iter()
-        .filter(|res| res.1.resource_type == *resource_type)
+        .filter(|res| self.is_resource_usable_as(res.1, resource_type))
+        .filter(|res| res.1.can_hold_batch(batch_size))
         .any(|res| !res.1.is_allocated_over_period(period))
     }
 
@@ -373,9 +1007,35 @@ impl ResourceTracker {
                                                                  resource_type: &ResourceType,
                                                                  period: NaivePeriod)
       -> Option<NaiveDateTime> {
+      self.get_next_available_resource_date_for_type_over_period_with_capacity(resource_type,
+                                                                               period, None)
+    }
+
+    /// Retrieve the next [NaiveDateTime](chrono::NaiveDateTime) at which a `Resource` of a
+    /// specific `ResourceType`, that can hold a batch of a given size, will be free.
+    ///
+    /// # Arguments
+    ///
+    /// * `resource_type`: The [ResourceType](ResourceType) to query for.
+    /// * `period`: The [NaivePeriod](chrono_period::NaivePeriod) that is desired.
+    /// * `batch_size`: An `Option` containing the size of the batch the `Resource` must be able
+    ///   to hold, or `None` if any `Resource` of type `resource_type` will do.
+    ///
+    /// # Returns
+    ///
+    /// * An `Option` containing one of the following values:
+    ///   * `Some`: Contains an instance of type [NaiveDateTime](chrono::NaiveDateTime) that
+    ///     represents the closest date at which a `Resource` of type `resource_type` able to hold
+    ///     `batch_size` will be free.
+    ///   * `None`: If there are no `Resource`s of type `resource_type` able to hold `batch_size`.
+    ///
+    pub fn get_next_available_resource_date_for_type_over_period_with_capacity(
+        &mut self, resource_type: &ResourceType, period: NaivePeriod,
+        batch_size: Option<&Volume>) -> Option<NaiveDateTime> {
 
       let mut free_dates: Vec<NaiveDateTime> = self.resources.iter()
-        .filter(|res| res.1.resource_type == *resource_type)
+        .filter(|res| self.is_resource_usable_as(res.1, resource_type))
+        .filter(|res| res.1.can_hold_batch(batch_size))
         .map(|res| {
             res.1.get_earliest_free_date_for_period(period)
         }).collect();
@@ -397,9 +1057,10 @@ impl ResourceTracker {
     ///   should happen.
     ///
     /// # Notes
-    /// `Resource` objects are checked in `id` order, so if multiple `Resource`s with the requested
-    /// `ResourceType` are free for the requested `NaivePeriod`, then the one with the minimum `id`
-    /// will be allocated and returned.
+    /// If multiple `Resource`s with the requested `ResourceType` are free for the requested
+    /// `NaivePeriod`, the [AllocationStrategy](super::allocation::AllocationStrategy) for
+    /// `resource_type` chooses which of them is allocated and returned. By default, this is the
+    /// one with the minimum `id`.
     ///
     /// # Returns
     /// - An `Option` containing either:
@@ -408,29 +1069,330 @@ impl ResourceTracker {
     ///   - None, if no `Resource` with type `resource_type` is free during the given `NaivePeriod`
     pub fn allocate_resource_of_type_for_period(&mut self, resource_type: &ResourceType,
                                                 period: NaivePeriod) -> Option<&Resource> {
-      // println!("Requesting resource of type {:?} for period starting {:?}", resource_type,
-      //          period.start);
+      self.allocate_resource_of_type_for_period_with_capacity(resource_type, period, None)
+    }
 
-      if !self.is_resource_of_type_free_for_period(resource_type, period) {
+    /// Allocate a `Resource` of a specific type, that can hold a batch of a given size, for a
+    /// given `NaivePeriod`.
+    ///
+    /// # Arguments
+    /// - `resource_type`: The `ResourceType` to allocate.
+    /// - `period`: The [NaivePeriod](chrono_period::NaivePeriod) during which the allocation
+    ///   should happen.
+    /// - `batch_size`: An `Option` containing the size of the batch the `Resource` must be able
+    ///   to hold, or `None` if any `Resource` of type `resource_type` will do.
+    ///
+    /// # Notes
+    /// If multiple suitable `Resource`s are free for the requested `NaivePeriod`, the
+    /// [AllocationStrategy](super::allocation::AllocationStrategy) for `resource_type` chooses
+    /// which of them is allocated and returned. By default, this is the one with the minimum
+    /// `id`.
+    ///
+    /// # Returns
+    /// - An `Option` containing either:
+    ///   - `Some(x)`, where `x` is a `Resource` whose type corresponds to `resource_type`, which
+    ///     can hold `batch_size`, and which is free during the given `NaivePeriod`
+    ///   - None, if no such `Resource` is free during the given `NaivePeriod`
+    pub fn allocate_resource_of_type_for_period_with_capacity(&mut self,
+                                                              resource_type: &ResourceType,
+                                                              period: NaivePeriod,
+                                                              batch_size: Option<&Volume>)
+      -> Option<&Resource> {
+      if !self.is_resource_of_type_free_for_period_with_capacity(resource_type, period,
+                                                                 batch_size) {
           return None
       }
 
-      // Because of the above check, we know this will return an element, so the unwrap() should
-      // never panic here.
-      let mut resources: Vec<(&usize, &mut Resource)> = self.resources.iter_mut().collect::<Vec<(&usize, &mut Resource)> >();
-      resources.sort_by(|a, b| a.0.cmp(b.0));
+      let mut resources: Vec<&Resource> = self.resources.values()
+        .filter(|res| self.is_resource_usable_as(res, resource_type))
+        .collect();
+      resources.sort_by_key(|res| res.id);
 
-      let resource_tuple: (&usize, &mut Resource) = resources.into_iter()
-          .filter(|hash_entry| {
-            hash_entry.1.resource_type == *resource_type
-              && !hash_entry.1.is_allocated_over_period(period)
-          }).next().unwrap();
+      // Because of the above check, we know there is at least one candidate. Substitutes are only
+      // candidates if no free `Resource` is of the requested type itself.
+      let mut candidates: Vec<&Resource> = resources.iter()
+        .filter(|res| res.can_hold_batch(batch_size) && !res.is_allocated_over_period(period))
+        .cloned()
+        .collect();
+      if candidates.iter().any(|res| res.is_of_type(resource_type)) {
+          candidates.retain(|res| res.is_of_type(resource_type));
+      }
 
-      let ret_val = resource_tuple.1.allocate_over_period(period);
+      let index: usize = self.get_allocation_strategy(resource_type)
+                             .choose(&candidates, &resources, period, batch_size);
+      let id: usize = candidates.get(index)?.id;
 
-      // println!("Allocated resource of type: {:?}", ret_val.unwrap().resource_type);
+      self.allocate_resource_for_period(id, period)
+    }
 
-      ret_val
+    /// Determine if the `Resource`s of a specific `ResourceType` are able to hold a batch of a
+    /// given size, either within a single `Resource` or split across several of them.
+    ///
+    /// # Arguments
+    /// - `resource_type`: A borrowed reference to a `ResourceType` to check for.
+    /// - `batch_size`: An `Option` containing the size of the batch, or `None` if any `Resource`
+    ///   of type `resource_type` will do.
+    ///
+    /// # Returns
+    /// - `true`, if a single `Resource` of type `resource_type` can hold `batch_size`, or if the
+    ///   combined capacity of all `Resource`s of type `resource_type` can hold it; `false`,
+    ///   otherwise.
+    pub fn can_hold_batch_with_resources_of_type(&self, resource_type: &ResourceType,
+                                                 batch_size: Option<&Volume>) -> bool {
+        if self.has_resource_of_type_with_capacity(resource_type, batch_size) {
+            return true;
+        }
+
+        match batch_size {
+            Some(batch) => {
+                let total_litres: f64 = self.resources.values()
+                  .filter(|res| self.is_resource_usable_as(res, resource_type))
+                  .filter_map(|res| res.capacity())
+                  .map(|capacity| capacity.litres())
+                  .sum();
+
+                Volume::new(total_litres, VolumeUnit::Litres).can_hold(batch)
+            },
+            None => false
+        }
+    }
+
+    /// Retrieve the next [NaiveDateTime](chrono::NaiveDateTime) at which enough `Resource`s of a
+    /// specific `ResourceType` to hold a batch of a given size will be free.
+    ///
+    /// # Arguments
+    /// * `resource_type`: The [ResourceType](ResourceType) to query for.
+    /// * `period`: The [NaivePeriod](chrono_period::NaivePeriod) that is desired.
+    /// * `batch_size`: An `Option` containing the size of the batch, or `None` if any `Resource`
+    ///   of type `resource_type` will do.
+    ///
+    /// # Notes
+    /// The batch is only split across several `Resource`s if no single `Resource` of type
+    /// `resource_type` is large enough to hold it. Otherwise, this is equivalent to
+    /// [get_next_available_resource_date_for_type_over_period_with_capacity](
+    /// ResourceTracker::get_next_available_resource_date_for_type_over_period_with_capacity).
+    ///
+    /// # Returns
+    /// * An `Option` containing one of the following values:
+    ///   * `Some`: The closest date at which enough `Resource`s of type `resource_type` to hold
+    ///     `batch_size` will be free.
+    ///   * `None`: If the `Resource`s of type `resource_type` cannot hold `batch_size`, even when
+    ///     combined.
+    pub fn get_next_available_date_for_resources_of_type_over_period(
+        &mut self, resource_type: &ResourceType, period: NaivePeriod,
+        batch_size: Option<&Volume>) -> Option<NaiveDateTime> {
+        let batch = match self.get_batch_to_split(resource_type, batch_size) {
+            Some(x) => x,
+            None => {
+                return self.get_next_available_resource_date_for_type_over_period_with_capacity(
+                    resource_type, period, batch_size);
+            }
+        };
+
+        if !self.can_hold_batch_with_resources_of_type(resource_type, Some(&batch)) {
+            return None;
+        }
+
+        // The set of free resources only changes when an allocation ends, so it's sufficient to
+        // check the requested start date and the end of each allocation after it.
+        let mut candidate_dates: Vec<NaiveDateTime> = vec![period.start];
+        for res in self.resources.values()
+                       .filter(|res| self.is_resource_usable_as(res, resource_type)) {
+            for allocated_period in res.allocated_periods.iter() {
+                let blocked_period = res.get_blocked_period(allocated_period);
+                if blocked_period.end >= period.start {
+                    candidate_dates.push(blocked_period.end + Duration::seconds(1));
+                }
+            }
+        }
+
+        candidate_dates.sort();
+        candidate_dates.dedup();
+
+        candidate_dates.into_iter().find(|date| {
+            let candidate_period = NaivePeriod::from_start_duration(*date, period.duration());
+            self.get_resource_ids_to_split_batch(resource_type, candidate_period, &batch).is_some()
+        })
+    }
+
+    /// Allocate enough `Resource`s of a specific type to hold a batch of a given size for a given
+    /// `NaivePeriod`.
+    ///
+    /// # Arguments
+    /// - `resource_type`: The `ResourceType` to allocate.
+    /// - `period`: The [NaivePeriod](chrono_period::NaivePeriod) during which the allocation
+    ///   should happen.
+    /// - `batch_size`: An `Option` containing the size of the batch, or `None` if any `Resource`
+    ///   of type `resource_type` will do.
+    ///
+    /// # Notes
+    /// If a single `Resource` of type `resource_type` is large enough to hold the batch, only that
+    /// `Resource` is allocated, as chosen by the allocation strategy for `resource_type`.
+    /// Otherwise, free `Resource`s are taken in `id` order, those of type `resource_type` before
+    /// any substitutes, until their combined capacity is able to hold the batch.
+    ///
+    /// # Returns
+    /// - An `Option` containing either:
+    ///   - `Some(x)`, where `x` is a `Vec` of copies of the allocated `Resource`s, in the order in
+    ///     which they were taken
+    ///   - None, if not enough `Resource`s of type `resource_type` are free during the given
+    ///     `NaivePeriod`. In this case, nothing is allocated.
+    pub fn allocate_resources_of_type_for_period(&mut self, resource_type: &ResourceType,
+                                                 period: NaivePeriod,
+                                                 batch_size: Option<&Volume>)
+      -> Option<Vec<Resource>> {
+        let batch = match self.get_batch_to_split(resource_type, batch_size) {
+            Some(x) => x,
+            None => {
+                return self.allocate_resource_of_type_for_period_with_capacity(resource_type,
+                                                                               period,
+                                                                               batch_size)
+                           .map(|res| vec![res.clone()]);
+            }
+        };
+
+        let ids: Vec<usize> = self.get_resource_ids_to_split_batch(resource_type, period, &batch)?;
+
+        let mut allocated: Vec<Resource> = vec![];
+        for id in ids {
+            let res = self.resources.get_mut(&id).unwrap();
+            allocated.push(res.allocate_over_period(period).unwrap().clone());
+        }
+
+        Some(allocated)
+    }
+
+    /// Extend an existing allocation of a tracked `Resource` so that it ends at a later date.
+    ///
+    /// # Arguments
+    /// - `id`: The `id` of the `Resource` whose allocation should be extended.
+    /// - `start`: The [NaiveDateTime](chrono::NaiveDateTime) at which the existing allocation
+    ///   begins.
+    /// - `end`: The [NaiveDateTime](chrono::NaiveDateTime) at which the extended allocation should
+    ///   end.
+    ///
+    /// # Returns
+    /// - An `Option` containing the `Resource` whose allocation was extended, or `None` if no
+    ///   `Resource` with `id` is tracked, or its allocation could not be extended. See
+    ///   [Resource::extend_allocation](Resource::extend_allocation).
+    pub fn extend_allocation_of_resource(&mut self, id: usize, start: NaiveDateTime,
+                                         end: NaiveDateTime) -> Option<&Resource> {
+        self.resources.get_mut(&id)?.extend_allocation(start, end)
+    }
+
+    /// Retrieve a `Resource` tracked by this `ResourceTracker`.
+    ///
+    /// # Arguments
+    /// - `id`: The `id` of the `Resource` to retrieve.
+    ///
+    /// # Returns
+    /// - An `Option` containing a borrowed reference to the `Resource` with `id`, or `None` if no
+    ///   such `Resource` is tracked.
+    pub fn get_tracked_resource(&self, id: usize) -> Option<&Resource> {
+        self.resources.get(&id)
+    }
+
+    /// Find a `Resource` tracked by this `ResourceTracker` by name or id.
+    ///
+    /// # Arguments
+    /// - `selector`: The `ResourceSelector` naming the `Resource`.
+    ///
+    /// # Returns
+    /// - An `Option` containing a borrowed reference to the `Resource` `selector` refers to, or
+    ///   `None` if no such `Resource` is tracked. If several `Resource`s share the same name, the
+    ///   one with the minimum `id` is returned.
+    pub fn find_resource(&self, selector: &ResourceSelector) -> Option<&Resource> {
+        self.resources.values()
+          .filter(|res| selector.matches(res))
+          .min_by_key(|res| res.id)
+    }
+
+    /// Allocate a specific `Resource` for a given `NaivePeriod`.
+    ///
+    /// # Arguments
+    /// - `id`: The `id` of the `Resource` to allocate.
+    /// - `period`: The [NaivePeriod](chrono_period::NaivePeriod) during which the allocation
+    ///   should happen.
+    ///
+    /// # Returns
+    /// - An `Option` containing the allocated `Resource`, or `None` if no `Resource` with `id` is
+    ///   tracked, or it is not free during the given `NaivePeriod`.
+    pub fn allocate_resource_for_period(&mut self, id: usize,
+                                        period: NaivePeriod) -> Option<&Resource> {
+        self.resources.get_mut(&id)?.allocate_over_period(period)
+    }
+
+    /// Allocate one of several preferred `Resource`s of a specific type, that can hold a batch of
+    /// a given size, for a given `NaivePeriod`.
+    ///
+    /// # Arguments
+    /// - `resource_type`: The `ResourceType` to allocate.
+    /// - `period`: The [NaivePeriod](chrono_period::NaivePeriod) during which the allocation
+    ///   should happen.
+    /// - `batch_size`: An `Option` containing the size of the batch the `Resource` must be able
+    ///   to hold, or `None` if any `Resource` of type `resource_type` will do.
+    /// - `preferred_ids`: The `id`s of the preferred `Resource`s, most preferred first.
+    ///
+    /// # Returns
+    /// - An `Option` containing the first `Resource` of `preferred_ids` whose type corresponds to
+    ///   `resource_type`, which can hold `batch_size`, and which is free during the given
+    ///   `NaivePeriod`, or `None` if there is no such `Resource`. In this case, nothing is
+    ///   allocated.
+    pub fn allocate_preferred_resource_of_type_for_period(&mut self,
+                                                          resource_type: &ResourceType,
+                                                          period: NaivePeriod,
+                                                          batch_size: Option<&Volume>,
+                                                          preferred_ids: &[usize])
+      -> Option<&Resource> {
+        let id: usize = *preferred_ids.iter().find(|id| {
+            self.resources.get(id).is_some_and(|res| {
+                self.is_resource_usable_as(res, resource_type)
+                  && res.can_hold_batch(batch_size)
+                  && !res.is_allocated_over_period(period)
+            })
+        })?;
+
+        self.allocate_resource_for_period(id, period)
+    }
+
+    /// Retrieve the batch size that must be split across several `Resource`s of a specific
+    /// `ResourceType`, or `None` if the batch does not need to be split.
+    fn get_batch_to_split(&self, resource_type: &ResourceType,
+                          batch_size: Option<&Volume>) -> Option<Volume> {
+        match batch_size {
+            Some(batch) if resource_type.is_vessel()
+              && !self.has_resource_of_type_with_capacity(resource_type, batch_size) => {
+                Some(*batch)
+            },
+            _ => None
+        }
+    }
+
+    /// Retrieve the ids of the `Resource`s that are free during a `NaivePeriod` and that together
+    /// are able to hold a batch, or `None` if no such set of `Resource`s exists. `Resource`s of
+    /// type `resource_type` are taken before substitutes, and each in `id` order.
+    fn get_resource_ids_to_split_batch(&self, resource_type: &ResourceType, period: NaivePeriod,
+                                       batch: &Volume) -> Option<Vec<usize>> {
+        let mut free_resources: Vec<&Resource> = self.resources.values()
+          .filter(|res| {
+              self.is_resource_usable_as(res, resource_type)
+                && res.capacity().is_some()
+                && !res.is_allocated_over_period(period)
+          }).collect();
+        free_resources.sort_by_key(|res| (!res.is_of_type(resource_type), res.id));
+
+        let mut ids: Vec<usize> = vec![];
+        let mut total_litres: f64 = 0.0;
+        for res in free_resources {
+            ids.push(res.id);
+            total_litres += res.capacity().unwrap().litres();
+
+            if Volume::new(total_litres, VolumeUnit::Litres).can_hold(batch) {
+                return Some(ids);
+            }
+        }
+
+        None
     }
 
     /// Retrieve all `Resource` objects tracked by this `ResourceTracker`.
@@ -441,4 +1403,176 @@ impl ResourceTracker {
     pub fn get_all_tracked_resources(&self) -> Vec<Resource> {
         self.resources.clone().into_iter().map(|tuple| tuple.1).collect()
     }
+
+    /// Count the members of staff with a specific skill.
+    ///
+    /// # Arguments
+    /// - `skill`: The skill to count staff for, or `None` to count all staff.
+    pub fn count_staff_with_skill(&self, skill: Option<&str>) -> usize {
+        self.resources.values().filter(|res| res.has_skill(skill)).count()
+    }
+
+    /// Retrieve the ids of the members of staff that would be allocated to a phase over several
+    /// `NaivePeriod`s.
+    ///
+    /// # Arguments
+    /// - `requirements`: The staff the phase needs.
+    /// - `periods`: The [NaivePeriod](chrono_period::NaivePeriod)s during which the staff work on
+    ///   the phase.
+    ///
+    /// # Returns
+    /// - An `Option` containing the ids of the staff, or `None` if not enough staff are free
+    ///   during all of `periods` within their maximum working hours.
+    ///
+    /// # Notes
+    /// The requirements that the fewest staff have the skill for are filled first, so that staff
+    /// with rare skills are kept for them. Each requirement takes free staff in `id` order, and no
+    /// member of staff fills more than one requirement.
+    pub fn get_staff_ids_for_periods(&self, requirements: &[StaffRequirement],
+                                     periods: &[NaivePeriod]) -> Option<Vec<usize>> {
+        let mut ordered_requirements: Vec<&StaffRequirement> = requirements.iter().collect();
+        ordered_requirements.sort_by_key(|requirement| {
+            self.count_staff_with_skill(requirement.skill.as_deref())
+        });
+
+        let mut staff: Vec<&Resource> = self.resources.values()
+          .filter(|res| res.resource_type == ResourceType::Staff)
+          .collect();
+        staff.sort_by_key(|res| res.id);
+
+        let mut ids: Vec<usize> = vec![];
+        for requirement in ordered_requirements {
+            let chosen: Vec<usize> = staff.iter()
+              .filter(|res| !ids.contains(&res.id))
+              .filter(|res| res.has_skill(requirement.skill.as_deref()))
+              .filter(|res| res.is_free_for_periods(periods))
+              .map(|res| res.id)
+              .take(requirement.count)
+              .collect();
+
+            if chosen.len() < requirement.count {
+                return None;
+            }
+
+            ids.extend(chosen);
+        }
+
+        Some(ids)
+    }
+
+    /// Allocate the members of staff a phase needs over several `NaivePeriod`s.
+    ///
+    /// # Returns
+    /// - An `Option` containing either:
+    ///   - `Some(x)`, where `x` is a `Vec` of copies of the allocated staff, in `id` order
+    ///   - None, if not enough staff are free. In this case, nothing is allocated.
+    ///
+    /// See [get_staff_ids_for_periods](ResourceTracker::get_staff_ids_for_periods) for how staff
+    /// are chosen.
+    pub fn allocate_staff_for_periods(&mut self, requirements: &[StaffRequirement],
+                                      periods: &[NaivePeriod]) -> Option<Vec<Resource>> {
+        let mut ids: Vec<usize> = self.get_staff_ids_for_periods(requirements, periods)?;
+        ids.sort();
+
+        let mut allocated: Vec<Resource> = vec![];
+        for id in ids {
+            let res = self.resources.get_mut(&id).unwrap();
+            for period in periods.iter() {
+                res.allocate_over_period(*period).unwrap();
+            }
+
+            allocated.push(res.clone());
+        }
+
+        Some(allocated)
+    }
+
+    /// Retrieve the next date after a specific date at which the members of staff that are free
+    /// may change.
+    ///
+    /// # Notes
+    /// This is either the end of an allocation of a member of staff, or the start of the next day
+    /// in the local time of a member of staff, on which the hours they work per day (and possibly
+    /// per week) start over.
+    pub fn get_next_staff_change_date(&self, date: NaiveDateTime) -> NaiveDateTime {
+        let staff: Vec<&Resource> = self.resources.values()
+                                        .filter(|res| res.resource_type == ResourceType::Staff)
+                                        .collect();
+        let next_day: NaiveDateTime = staff.iter()
+          .map(|res| get_day_containing(date, res.time_zone.as_ref()).end)
+          .min()
+          .unwrap_or_else(|| get_day_containing(date, None).end);
+
+        staff.iter()
+          .flat_map(|res| {
+              res.allocated_periods.iter().map(move |period| res.get_blocked_period(period).end)
+          })
+          .map(|end| end + Duration::seconds(1))
+          .filter(|end| *end > date)
+          .fold(next_day, |earliest, end| earliest.min(end))
+    }
+
+    /// Retrieve the date at which the last allocation of any member of staff ends, or `None` if no
+    /// staff are allocated.
+    pub fn get_end_of_staff_allocations(&self) -> Option<NaiveDateTime> {
+        self.resources.values()
+          .filter(|res| res.resource_type == ResourceType::Staff)
+          .flat_map(|res| {
+              res.allocated_periods.iter().map(move |period| res.get_blocked_period(period).end)
+          })
+          .max()
+    }
+}
+
+/// The number of hours by which the hours worked by a member of staff may exceed their maximum,
+/// to allow for the single seconds that separate consecutive allocations.
+const HOUR_LIMIT_TOLERANCE: f64 = 0.001;
+
+/// A function retrieving the window, e.g. the day or the week, over which the working hours of a
+/// member of staff are limited, that contains a specific date, in the local time of a time zone.
+type GetWindow = fn(NaiveDateTime, Option<&TimeZone>) -> NaivePeriod;
+
+/// Retrieve the calendar day that contains a specific date.
+///
+/// # Arguments
+/// - `date`: A date of the schedule.
+/// - `time_zone`: The time zone whose local midnights separate days, or `None` if the dates of the
+///   schedule are local times already.
+///
+/// # Notes
+/// Days on which the clock is set forward or back are an hour shorter or longer.
+fn get_day_containing(date: NaiveDateTime, time_zone: Option<&TimeZone>) -> NaivePeriod {
+    let day: NaiveDate = get_local_date(date, time_zone).date();
+    get_local_period(day, day + Duration::days(1), time_zone)
+}
+
+/// Retrieve the week, from Monday to Sunday, that contains a specific date. See
+/// [get_day_containing](get_day_containing).
+fn get_week_containing(date: NaiveDateTime, time_zone: Option<&TimeZone>) -> NaivePeriod {
+    let day: NaiveDate = get_local_date(date, time_zone).date();
+    let monday: NaiveDate = day - Duration::days(day.weekday().num_days_from_monday() as i64);
+    get_local_period(monday, monday + Duration::weeks(1), time_zone)
+}
+
+fn get_local_date(date: NaiveDateTime, time_zone: Option<&TimeZone>) -> NaiveDateTime {
+    time_zone.map_or(date, |x| x.to_local(date))
+}
+
+/// Retrieve the period from local midnight on one day to local midnight on another.
+fn get_local_period(start: NaiveDate, end: NaiveDate, time_zone: Option<&TimeZone>)
+  -> NaivePeriod {
+    let to_schedule_date = |day: NaiveDate| {
+        let midnight: NaiveDateTime = day.and_hms_opt(0, 0, 0).unwrap();
+        time_zone.map_or(midnight, |x| x.to_utc(midnight))
+    };
+
+    NaivePeriod::new(to_schedule_date(start), to_schedule_date(end))
+}
+
+/// Retrieve the number of hours of several `NaivePeriod`s that fall within another one.
+fn get_hours_within(periods: &[NaivePeriod], window: NaivePeriod) -> f64 {
+    periods.iter()
+           .filter_map(|period| period.get_intersection_with(window))
+           .map(|intersection| intersection.duration().num_seconds() as f64 / 3600.0)
+           .sum()
 }
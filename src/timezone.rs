@@ -0,0 +1,392 @@
+use chrono::{Datelike, Duration, FixedOffset, NaiveDate, NaiveDateTime, Offset, Weekday};
+use chrono::TimeZone as _;
+
+use chrono_tz::Tz;
+
+/// The days of the week, in the order in which POSIX `TZ` rules number them.
+const POSIX_WEEKDAYS: [Weekday; 7] = [Weekday::Sun, Weekday::Mon, Weekday::Tue, Weekday::Wed,
+                                      Weekday::Thu, Weekday::Fri, Weekday::Sat];
+
+/// The number of seconds in a day. Offsets from UTC must be shorter than this.
+const SECONDS_PER_DAY: i32 = 24 * 3600;
+
+/// A time zone, in which the wall clock may be moved forward and back for daylight saving time.
+///
+/// A `TimeZone` is created from one of:
+/// - `UTC`, or a fixed offset from UTC, e.g. `+02:00` or `-05:30`.
+/// - A POSIX `TZ` rule, e.g. `CET-1CEST,M3.5.0,M10.5.0/3`. Its rules apply to every year.
+/// - The name of a zone in the IANA time zone database, e.g. `America/Denver`. The database is
+///   built in, and includes the rules the zone followed in the past as well as its current ones.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TimeZone {
+    name: String,
+    rules: ZoneRules
+}
+
+/// The rules by which the offset of a `TimeZone` from UTC changes over time.
+#[derive(Clone, Debug, PartialEq)]
+enum ZoneRules {
+    /// A fixed offset, or a POSIX `TZ` rule.
+    Posix {
+        /// The offset from UTC outside of daylight saving time, in seconds east of UTC.
+        standard_offset: i32,
+
+        daylight_saving: Option<DaylightSaving>
+    },
+
+    /// A zone of the IANA time zone database.
+    Database(Tz)
+}
+
+/// The rules by which a `TimeZone` observes daylight saving time.
+#[derive(Clone, Debug, PartialEq)]
+struct DaylightSaving {
+    /// The offset from UTC during daylight saving time, in seconds east of UTC.
+    offset: i32,
+
+    /// When daylight saving time starts, in standard time.
+    start: TransitionRule,
+
+    /// When daylight saving time ends, in daylight saving time.
+    end: TransitionRule
+}
+
+/// A rule in the `Mm.w.d/time` form of POSIX, e.g. `M3.5.0/3` for the last Sunday of March at
+/// 03:00.
+#[derive(Clone, Debug, PartialEq)]
+struct TransitionRule {
+    month: u32,
+
+    /// The week of the month, from `1` to `5`, where `5` is the last one.
+    week: u8,
+
+    weekday: Weekday,
+
+    /// The local time of the transition, in seconds since midnight. It may be negative, or more
+    /// than a day.
+    time: i64
+}
+
+impl TransitionRule {
+    /// Retrieve the local date and time at which this rule applies in a specific year.
+    fn get_date_in_year(&self, year: i32) -> Option<NaiveDateTime> {
+        let day: NaiveDate = (1..=self.week).rev().find_map(|week| {
+            NaiveDate::from_weekday_of_month_opt(year, self.month, self.weekday, week)
+        })?;
+
+        Some(day.and_hms_opt(0, 0, 0)? + Duration::seconds(self.time))
+    }
+}
+
+impl TimeZone {
+    /// Look up a `TimeZone` by name.
+    ///
+    /// # Arguments
+    /// - `name`: `UTC`, a fixed offset, a POSIX `TZ` rule, or the name of a zone in the IANA time
+    ///   zone database. See [TimeZone](TimeZone).
+    ///
+    /// # Returns
+    /// - A `Result` containing either the `TimeZone`, or a `String` explaining why `name` does not
+    ///   describe one.
+    pub fn from_name(name: &str) -> Result<TimeZone, String> {
+        let name: &str = name.trim();
+        if name.is_empty() {
+            return Err("the time zone is empty".to_string());
+        }
+
+        if name == "UTC" || name == "Z" {
+            return Ok(TimeZone::fixed(name, 0));
+        }
+
+        if let Some(offset) = get_fixed_offset(name) {
+            return Ok(TimeZone::fixed(name, offset));
+        }
+
+        if let Some(x) = get_posix_time_zone(name)? {
+            return Ok(x);
+        }
+
+        if name.contains(',') {
+            return Err(format!("unable to parse the time zone rule \"{}\"", name));
+        }
+
+        name.parse::<Tz>()
+            .map(|x| TimeZone { name: name.to_string(), rules: ZoneRules::Database(x) })
+            .map_err(|_| format!("unknown time zone \"{}\"", name))
+    }
+
+    fn fixed(name: &str, offset: i32) -> TimeZone {
+        TimeZone {
+            name: name.to_string(),
+            rules: ZoneRules::Posix { standard_offset: offset, daylight_saving: None }
+        }
+    }
+
+    /// Retrieve the name this `TimeZone` was looked up by.
+    pub fn name(&self) -> &str {
+        &self.name[..]
+    }
+
+    /// Retrieve the offset from UTC of this `TimeZone` at a specific moment.
+    ///
+    /// # Arguments
+    /// - `utc`: The moment, in UTC.
+    pub fn get_offset_at(&self, utc: NaiveDateTime) -> FixedOffset {
+        match &self.rules {
+            ZoneRules::Posix { .. } => {
+                // POSIX offsets are rejected by from_name() unless they are shorter than a day.
+                FixedOffset::east_opt(self.get_offset_seconds_at(utc)).unwrap()
+            },
+            ZoneRules::Database(x) => x.offset_from_utc_datetime(&utc).fix()
+        }
+    }
+
+    fn get_offset_seconds_at(&self, utc: NaiveDateTime) -> i32 {
+        let (standard_offset, daylight_saving): (i32, &DaylightSaving) = match &self.rules {
+            ZoneRules::Posix { standard_offset, daylight_saving: Some(x) } => (*standard_offset, x),
+            ZoneRules::Posix { standard_offset, daylight_saving: None } => return *standard_offset,
+            ZoneRules::Database(x) => return x.offset_from_utc_datetime(&utc).fix()
+                                              .local_minus_utc()
+        };
+
+        let year: i32 = utc.year();
+        let transitions = daylight_saving.start.get_date_in_year(year)
+                                         .zip(daylight_saving.end.get_date_in_year(year));
+        let (start, end): (NaiveDateTime, NaiveDateTime) = match transitions {
+            Some((start, end)) => {
+                (start - Duration::seconds(standard_offset as i64),
+                 end - Duration::seconds(daylight_saving.offset as i64))
+            },
+            None => return standard_offset
+        };
+
+        // In the southern hemisphere, daylight saving time spans the turn of the year.
+        let is_daylight_saving: bool = match start <= end {
+            true => start <= utc && utc < end,
+            false => !(end <= utc && utc < start)
+        };
+
+        match is_daylight_saving {
+            true => daylight_saving.offset,
+            false => standard_offset
+        }
+    }
+
+    /// Convert a moment in UTC into the local time of this `TimeZone`.
+    pub fn to_local(&self, utc: NaiveDateTime) -> NaiveDateTime {
+        utc + Duration::seconds(self.get_offset_seconds_at(utc) as i64)
+    }
+
+    /// Convert a local time of this `TimeZone` into UTC.
+    ///
+    /// # Notes
+    /// A local time that occurs twice, when the clock is set back, is taken to be the first one. A
+    /// local time that does not occur, when the clock is set forward, is taken to be as long after
+    /// the transition as it is after the time at which the clock is set forward, e.g. 02:30 becomes
+    /// 03:30 when the clock jumps from 02:00 to 03:00.
+    pub fn to_utc(&self, local: NaiveDateTime) -> NaiveDateTime {
+        // The offset changes at most once within a day, so the offsets a day before and a day
+        // after are the only ones that can be in effect at the local time, and the one a day
+        // before is still the one in effect before the transition.
+        let offset_before: i32 = self.get_offset_seconds_at(local - Duration::days(1));
+        let offset_after: i32 = self.get_offset_seconds_at(local + Duration::days(1));
+
+        let candidates: Vec<NaiveDateTime> = [offset_before, offset_after].iter()
+          .map(|offset| local - Duration::seconds(*offset as i64))
+          .filter(|utc| self.to_local(*utc) == local)
+          .collect();
+
+        match candidates.into_iter().min() {
+            Some(x) => x,
+            None => local - Duration::seconds(offset_before as i64)
+        }
+    }
+}
+
+/// Parse an offset such as `+02:00`, `-0530` or `+2` into seconds east of UTC.
+fn get_fixed_offset(value: &str) -> Option<i32> {
+    let sign: i32 = match value.chars().next()? {
+        '+' => 1,
+        '-' => -1,
+        _ => return None
+    };
+
+    let digits: String = value[1..].chars().filter(|x| *x != ':').collect();
+    if digits.is_empty() || digits.len() > 4 || !digits.chars().all(|x| x.is_ascii_digit()) {
+        return None;
+    }
+
+    let (hours, minutes): (i32, i32) = match digits.len() {
+        1 | 2 => (digits.parse().ok()?, 0),
+        _ => {
+            let split: usize = digits.len() - 2;
+            (digits[..split].parse().ok()?, digits[split..].parse().ok()?)
+        }
+    };
+
+    if hours > 14 || minutes > 59 {
+        return None;
+    }
+
+    Some(sign * (hours * 3600 + minutes * 60))
+}
+
+/// Parse a POSIX `TZ` rule such as `MST7MDT,M3.2.0,M11.1.0` into a `TimeZone`.
+///
+/// # Returns
+/// - A `Result` containing either the `TimeZone`, or `None` if `rule` is not a POSIX `TZ` rule, or
+///   a `String` explaining why the offsets of the rule cannot be used.
+fn get_posix_time_zone(rule: &str) -> Result<Option<TimeZone>, String> {
+    let mut parser: RuleParser = RuleParser { rest: rule };
+
+    if parser.parse_abbreviation().is_none() {
+        return Ok(None);
+    }
+
+    // POSIX offsets are west of UTC, the opposite of the usual convention.
+    let standard_offset: i32 = match parser.parse_time() {
+        Some(x) => get_offset_within_a_day(-x, rule)?,
+        None => return Ok(None)
+    };
+    if parser.rest.is_empty() {
+        return Ok(Some(TimeZone::fixed(rule, standard_offset)));
+    }
+
+    if parser.parse_abbreviation().is_none() {
+        return Ok(None);
+    }
+
+    let offset: i32 = match parser.rest.starts_with(',') {
+        true => get_offset_within_a_day(standard_offset as i64 + 3600, rule)?,
+        false => match parser.parse_time() {
+            Some(x) => get_offset_within_a_day(-x, rule)?,
+            None => return Ok(None)
+        }
+    };
+
+    let (start, end): (TransitionRule, TransitionRule) = match parser.parse_transition_rules() {
+        Some(x) if parser.rest.is_empty() => x,
+        _ => return Ok(None)
+    };
+
+    Ok(Some(TimeZone {
+        name: rule.to_string(),
+        rules: ZoneRules::Posix {
+            standard_offset,
+            daylight_saving: Some(DaylightSaving { offset, start, end })
+        }
+    }))
+}
+
+/// Require an offset from UTC of a POSIX `TZ` rule, in seconds east of UTC, to be shorter than a
+/// day, as no time zone is further than that from UTC.
+fn get_offset_within_a_day(offset: i64, rule: &str) -> Result<i32, String> {
+    match offset.abs() < SECONDS_PER_DAY as i64 {
+        true => Ok(offset as i32),
+        false => Err(format!("the offsets from UTC of the time zone rule \"{}\" must be shorter \
+                              than 24 hours", rule))
+    }
+}
+
+/// A cursor over a POSIX `TZ` rule.
+struct RuleParser<'a> {
+    rest: &'a str
+}
+
+impl<'a> RuleParser<'a> {
+    fn expect(&mut self, c: char) -> Option<()> {
+        self.rest = self.rest.strip_prefix(c)?;
+
+        Some(())
+    }
+
+    fn take_while<F: Fn(char) -> bool>(&mut self, predicate: F) -> &'a str {
+        let end: usize = self.rest.find(|x: char| !predicate(x)).unwrap_or(self.rest.len());
+        let (taken, rest) = self.rest.split_at(end);
+        self.rest = rest;
+
+        taken
+    }
+
+    /// Parse an abbreviation such as `CEST`, or `<+03>`.
+    fn parse_abbreviation(&mut self) -> Option<()> {
+        let abbreviation: &str = match self.rest.starts_with('<') {
+            true => {
+                let end: usize = self.rest.find('>')?;
+                let abbreviation: &str = &self.rest[1..end];
+                self.rest = &self.rest[end + 1..];
+                abbreviation
+            },
+            false => self.take_while(|x| x.is_ascii_alphabetic())
+        };
+
+        match abbreviation.len() >= 3 {
+            true => Some(()),
+            false => None
+        }
+    }
+
+    /// Parse a time such as `5`, `-1`, `2:30` or `167`, in seconds.
+    fn parse_time(&mut self) -> Option<i64> {
+        let sign: i64 = match self.rest.chars().next()? {
+            '-' => { self.rest = &self.rest[1..]; -1 },
+            '+' => { self.rest = &self.rest[1..]; 1 },
+            _ => 1
+        };
+
+        let mut seconds: i64 = 0;
+        for (index, factor) in [3600, 60, 1].iter().enumerate() {
+            if index > 0 && self.expect(':').is_none() {
+                break;
+            }
+
+            let digits: &str = self.take_while(|x| x.is_ascii_digit());
+            let value: i64 = digits.parse().ok()?;
+            if (index == 0 && value > 167) || (index > 0 && value > 59) {
+                return None;
+            }
+
+            seconds += value * factor;
+        }
+
+        Some(sign * seconds)
+    }
+
+    /// Parse the rules for the start and the end of daylight saving time, e.g.
+    /// `,M3.5.0,M10.5.0/3`.
+    fn parse_transition_rules(&mut self) -> Option<(TransitionRule, TransitionRule)> {
+        self.expect(',')?;
+        let start: TransitionRule = self.parse_transition_rule()?;
+        self.expect(',')?;
+        let end: TransitionRule = self.parse_transition_rule()?;
+
+        Some((start, end))
+    }
+
+    /// Parse a rule such as `M3.5.0/3`. Rules for days of the year (`Jn` or `n`) are not
+    /// supported, as no current zone uses them.
+    fn parse_transition_rule(&mut self) -> Option<TransitionRule> {
+        self.expect('M')?;
+        let month: u32 = self.take_while(|x| x.is_ascii_digit()).parse().ok()?;
+        self.expect('.')?;
+        let week: u8 = self.take_while(|x| x.is_ascii_digit()).parse().ok()?;
+        self.expect('.')?;
+        let day: u32 = self.take_while(|x| x.is_ascii_digit()).parse().ok()?;
+
+        if !(1..=12).contains(&month) || !(1..=5).contains(&week) || day > 6 {
+            return None;
+        }
+
+        let time: i64 = match self.expect('/') {
+            Some(_) => self.parse_time()?,
+            None => 2 * 3600
+        };
+
+        Some(TransitionRule {
+            month,
+            week,
+            weekday: POSIX_WEEKDAYS[day as usize],
+            time
+        })
+    }
+}
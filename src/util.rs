@@ -1,35 +1,285 @@
-use std::error::Error;
 use std::fs::File;
 use std::io::{BufReader, Read};
 
-use chrono::{Duration, NaiveDate, NaiveDateTime, ParseError};
-
-pub fn convert_string_to_duration(duration_string: &str) -> Option<Duration> {
-    let mut characters: Vec<_> = duration_string.chars().collect();
-    let mut identifier = None;
-    if characters.len() > 0 {
-        let last_character = characters[characters.len()- 1];
-        let is_last_character_digit = last_character.to_string().parse::<usize>().is_ok();
-        identifier = Some('d');
-        if !is_last_character_digit {
-            identifier = characters.pop();
+use chrono::{Duration, Months, NaiveDate, NaiveDateTime, NaiveTime, ParseError};
+
+/// A length of time that may include calendar months, whose length depends on the date from which
+/// they are counted.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CalendarDuration {
+    /// The number of calendar months, e.g. `2` for `2mo`.
+    pub months: i32,
+
+    /// The part of the length of time that doesn't depend on the calendar, e.g. `3d 4h`.
+    pub duration: Duration
+}
+
+impl CalendarDuration {
+    pub fn new(months: i32, duration: Duration) -> Self {
+        CalendarDuration { months, duration }
+    }
+
+    /// Determine how long this `CalendarDuration` lasts, if it is counted from a specific date.
+    ///
+    /// # Returns
+    /// - An `Option` containing the `Duration`, or `None` if it would end beyond the range of
+    ///   dates that can be represented.
+    ///
+    /// # Notes
+    /// Calendar months are counted first, and stay on the same day of the month wherever
+    /// possible, so that a month from January 31st ends on the last day of February.
+    pub fn get_duration_from(&self, start: NaiveDateTime) -> Option<Duration> {
+        let months = Months::new(self.months.unsigned_abs());
+        let end: NaiveDateTime = match self.months >= 0 {
+            true => start.checked_add_months(months)?,
+            false => start.checked_sub_months(months)?
+        };
+
+        end.checked_add_signed(self.duration).map(|x| x - start)
+    }
+
+    /// Determine roughly how long this `CalendarDuration` lasts, counting every calendar month as
+    /// 30 days, for when there is no date to count it from.
+    pub fn approximate(&self) -> Duration {
+        Duration::days(30 * self.months as i64) + self.duration
+    }
+}
+
+/// A unit in which a part of a duration string can be expressed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum DurationUnit {
+    /// A unit that lasts a number of calendar months, e.g. a year of 12 months.
+    CalendarMonths(i32),
+
+    /// A unit that always lasts the same number of seconds.
+    Seconds(i64)
+}
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+/// The longest duration, in seconds, that can be parsed: roughly 10,000 years.
+const MAX_DURATION_SECONDS: f64 = 10_000.0 * 366.0 * SECONDS_PER_DAY as f64;
+
+/// The most calendar months that can be parsed: 10,000 years.
+const MAX_DURATION_MONTHS: f64 = 10_000.0 * 12.0;
+
+fn get_duration_unit(unit_string: &str) -> Option<DurationUnit> {
+    match &unit_string.to_lowercase()[..] {
+        "mo" | "month" | "months" => Some(DurationUnit::CalendarMonths(1)),
+        // Existing BPD files use `m` for months of exactly 30 days.
+        "m" => Some(DurationUnit::Seconds(30 * SECONDS_PER_DAY)),
+        "w" | "wk" | "wks" | "week" | "weeks" => Some(DurationUnit::Seconds(7 * SECONDS_PER_DAY)),
+        "d" | "day" | "days" => Some(DurationUnit::Seconds(SECONDS_PER_DAY)),
+        "h" | "hr" | "hrs" | "hour" | "hours" => Some(DurationUnit::Seconds(60 * 60)),
+        "min" | "mins" | "minute" | "minutes" => Some(DurationUnit::Seconds(60)),
+        "s" | "sec" | "secs" | "second" | "seconds" => Some(DurationUnit::Seconds(1)),
+        _ => None
+    }
+}
+
+/// The parts of a duration string added up so far.
+#[derive(Default)]
+struct DurationTotal {
+    months: f64,
+    seconds: f64
+}
+
+impl DurationTotal {
+    fn add(&mut self, amount_string: &str, unit: DurationUnit) -> Result<(), String> {
+        let amount: f64 = match amount_string.replace(',', ".").parse::<f64>() {
+            Ok(x) if x.is_finite() => x,
+            _ => return Err(format!("\"{}\" is not a number", amount_string))
+        };
+
+        match unit {
+            DurationUnit::CalendarMonths(_) if amount.fract() != 0.0 => {
+                return Err(format!("calendar months and years must be whole numbers, but got {}",
+                                   amount_string));
+            },
+            DurationUnit::CalendarMonths(x) => self.months += amount * x as f64,
+            DurationUnit::Seconds(x) => self.seconds += amount * x as f64
         }
+
+        Ok(())
     }
 
-    match identifier {
-        Some(x) => {
-            let digit_string: String = characters.into_iter().collect();
-            let digits: i64 = digit_string.parse::<i64>().unwrap();
-            match x {
-                'm' => Some(Duration::days(digits*30)),
-                'w' => Some(Duration::weeks(digits)),
-                'd' => Some(Duration::days(digits)),
-                'h' => Some(Duration::hours(digits)),
-                _ => None
+    fn into_calendar_duration(self, is_negative: bool) -> Result<CalendarDuration, String> {
+        if self.months > MAX_DURATION_MONTHS || self.seconds > MAX_DURATION_SECONDS {
+            return Err("it is too long".to_string());
+        }
+
+        let sign: i64 = match is_negative {
+            true => -1,
+            false => 1
+        };
+
+        Ok(CalendarDuration::new(sign as i32 * self.months as i32,
+                                 Duration::seconds(sign * self.seconds.round() as i64)))
+    }
+}
+
+/// Try to convert a `&str` into a `CalendarDuration`.
+///
+/// # Arguments
+/// * `duration_string`: A string slice containing either:
+///   - One or more numbers, each followed by a unit, e.g. `3d`, `1w 3d 4h`, `1.5d` or `30min`.
+///     The units are `mo` (calendar months), `m` (months of 30 days), `w`, `d`, `h`, `min` and
+///     `s`, or their full names, e.g. `2 weeks`.
+///   - A single number without a unit, which is a number of days, e.g. `10`.
+///   - An ISO 8601 duration, e.g. `P1W2DT3H` or `PT90M`, whose months are calendar months.
+///
+///   A leading `-` makes the whole duration negative, e.g. `-2h`.
+///
+/// # Returns
+/// * A `Result` containing either the `CalendarDuration`, or a `String` describing why it could
+///   not be parsed.
+///
+/// # Examples
+///
+/// ```
+/// # use chrono::Duration;
+/// # use chronogrog::util::{convert_string_to_calendar_duration, CalendarDuration};
+/// assert_eq!(Ok(CalendarDuration::new(2, Duration::hours(12))),
+///            convert_string_to_calendar_duration("2mo 0.5d"));
+/// assert_eq!(Ok(CalendarDuration::new(0, Duration::hours(75))),
+///            convert_string_to_calendar_duration("P3DT3H"));
+/// ```
+pub fn convert_string_to_calendar_duration(duration_string: &str)
+  -> Result<CalendarDuration, String> {
+    let trimmed: &str = duration_string.trim();
+    let (is_negative, unsigned) = match trimmed.strip_prefix('-') {
+        Some(x) => (true, x.trim_start()),
+        None => (false, trimmed)
+    };
+
+    if unsigned.is_empty() {
+        return Err("it is empty".to_string());
+    }
+
+    let total: DurationTotal = match unsigned.strip_prefix(['P', 'p']) {
+        Some(x) => get_iso_8601_duration_total(x)?,
+        None => get_compound_duration_total(unsigned)?
+    };
+
+    total.into_calendar_duration(is_negative)
+}
+
+/// Try to convert a `&str` into a `Duration`, counting calendar months as 30 days.
+///
+/// See [convert_string_to_calendar_duration](convert_string_to_calendar_duration) for the
+/// supported formats. Use that instead wherever there is a date to count calendar months from.
+pub fn convert_string_to_duration(duration_string: &str) -> Result<Duration, String> {
+    convert_string_to_calendar_duration(duration_string).map(|x| x.approximate())
+}
+
+/// Split a number from the start of a string, returning the number and the rest of the string.
+fn split_number(text: &str) -> (&str, &str) {
+    let end: usize = text.find(|c: char| !(c.is_ascii_digit() || c == '.' || c == ','))
+                         .unwrap_or(text.len());
+
+    text.split_at(end)
+}
+
+/// Add up a duration string such as `1w 3d 4h`.
+fn get_compound_duration_total(text: &str) -> Result<DurationTotal, String> {
+    let mut total: DurationTotal = DurationTotal::default();
+    let mut rest: &str = text;
+    while !rest.is_empty() {
+        let (amount_string, after_amount) = split_number(rest);
+        let after_amount: &str = after_amount.trim_start();
+        let unit_end: usize = after_amount.find(|c: char| !c.is_alphabetic())
+                                          .unwrap_or(after_amount.len());
+        let (unit_string, after_unit) = after_amount.split_at(unit_end);
+
+        if amount_string.is_empty() {
+            return Err(match after_amount.chars().next() {
+                Some(x) if x.is_alphabetic() => {
+                    format!("expected a number before \"{}\"", unit_string)
+                },
+                _ => format!("unexpected \"{}\"", after_amount)
+            });
+        }
+
+        let unit: DurationUnit = match unit_string.is_empty() {
+            // A single number without a unit is a number of days.
+            true if rest.len() == text.len() && after_unit.is_empty() => {
+                DurationUnit::Seconds(SECONDS_PER_DAY)
+            },
+            true => return Err(format!("{} has no unit; expected e.g. \"mo\", \"w\", \"d\", \
+                                        \"h\" or \"min\"", amount_string)),
+            false => get_duration_unit(unit_string).ok_or_else(|| {
+                format!("unknown unit \"{}\"; expected e.g. \"mo\", \"w\", \"d\", \"h\" or \
+                         \"min\"", unit_string)
+            })?
+        };
+
+        total.add(amount_string, unit)?;
+        rest = after_unit.trim_start();
+    }
+
+    Ok(total)
+}
+
+/// Add up an ISO 8601 duration such as `P1W2DT3H`, without its leading `P`.
+fn get_iso_8601_duration_total(text: &str) -> Result<DurationTotal, String> {
+    let (date_part, time_part): (&str, Option<&str>) = match text.find(['T', 't']) {
+        Some(x) => (&text[..x], Some(&text[x + 1..])),
+        None => (text, None)
+    };
+
+    if time_part == Some("") {
+        return Err("nothing follows \"T\" in the ISO 8601 duration".to_string());
+    }
+
+    if date_part.is_empty() && time_part.is_none() {
+        return Err("the ISO 8601 duration has no parts".to_string());
+    }
+
+    let date_units: [(char, DurationUnit); 4] = [
+        ('Y', DurationUnit::CalendarMonths(12)),
+        ('M', DurationUnit::CalendarMonths(1)),
+        ('W', DurationUnit::Seconds(7 * SECONDS_PER_DAY)),
+        ('D', DurationUnit::Seconds(SECONDS_PER_DAY))
+    ];
+    let time_units: [(char, DurationUnit); 3] = [
+        ('H', DurationUnit::Seconds(60 * 60)),
+        ('M', DurationUnit::Seconds(60)),
+        ('S', DurationUnit::Seconds(1))
+    ];
+
+    let mut total: DurationTotal = DurationTotal::default();
+    for (part, units) in [(date_part, &date_units[..]), (time_part.unwrap_or(""), &time_units[..])]
+                           .iter() {
+        // Each unit can appear once, in order, e.g. `P1D2W` is not allowed.
+        let mut next_unit_index: usize = 0;
+        let mut rest: &str = part;
+        while !rest.is_empty() {
+            let (amount_string, after_amount) = split_number(rest);
+            let designator: char = match after_amount.chars().next() {
+                Some(x) => x.to_ascii_uppercase(),
+                None => return Err(format!("{} has no unit in the ISO 8601 duration",
+                                           amount_string))
+            };
+
+            let remaining_units = &units[next_unit_index..];
+            let unit_index: usize = match remaining_units.iter().position(|(x, _)| *x == designator) {
+                Some(x) => next_unit_index + x,
+                None => return Err(format!("unexpected \"{}\" in the ISO 8601 duration",
+                                           &after_amount[..designator.len_utf8()]))
+            };
+
+            if amount_string.is_empty() {
+                return Err(format!("expected a number before \"{}\"", designator));
             }
-        },
-        None => None
+
+            total.add(amount_string, units[unit_index].1)?;
+
+            next_unit_index = unit_index + 1;
+            rest = &after_amount[designator.len_utf8()..];
+        }
     }
+
+    Ok(total)
 }
 
 /// Try to convert a `String` to a `NativeDateTime`.
@@ -52,9 +302,9 @@ pub fn get_naive_date_time_from_string(date_string: &str) -> Result<NaiveDateTim
     match NaiveDateTime::parse_from_str(date_string, "%Y-%m-%d %H:%M:%S") {
         Ok(x) => Ok(x),
         Err(e) => {
-            if e.description() == "premature end of input" {
+            if e.to_string() == "premature end of input" {
                 match NaiveDate::parse_from_str(date_string, "%Y-%m-%d") {
-                    Ok(x) => Ok(x.and_hms(0, 0, 0)),
+                    Ok(x) => Ok(x.and_hms_opt(0, 0, 0).unwrap()),
                     Err(e) => Err(e)
                 }
             } else {
@@ -64,6 +314,44 @@ pub fn get_naive_date_time_from_string(date_string: &str) -> Result<NaiveDateTim
     }
 }
 
+/// Retrieve the representation of a date used by the `start` of tasks in PLA format.
+///
+/// # Arguments
+/// * `date`: The date to represent.
+/// * `day_zero`: The date that days are counted from, or `None` to use calendar dates.
+///
+/// # Returns
+/// * A `String` containing either the calendar date, e.g. `2020-01-15`, or the number of days
+///   since the date of `day_zero`, e.g. `14`. Unless `date` is at midnight, the hour is appended,
+///   e.g. `2020-01-15 06` or `14 06`.
+pub fn get_pla_date_string(date: NaiveDateTime, day_zero: Option<NaiveDateTime>) -> String {
+    let day: String = match day_zero {
+        Some(x) => (date.date() - x.date()).num_days().to_string(),
+        None => date.date().to_string()
+    };
+
+    match date.time() == NaiveTime::from_hms_opt(0, 0, 0).unwrap() {
+        true => day,
+        false => format!("{} {}", day, date.format("%H"))
+    }
+}
+
+/// Retrieve a human-readable representation of a date, for use in messages and annotations.
+///
+/// # Arguments
+/// * `date`: The date to represent.
+/// * `day_zero`: The date that days are counted from, or `None` to use calendar dates.
+///
+/// # Returns
+/// * A `String` containing either the calendar date and time, e.g. `2020-01-15 06:00`, or the
+///   number of days since the date of `day_zero` and the time, e.g. `day 14 06:00`.
+pub fn get_date_string(date: NaiveDateTime, day_zero: Option<NaiveDateTime>) -> String {
+    match day_zero {
+        Some(x) => format!("day {} {}", (date.date() - x.date()).num_days(), date.format("%H:%M")),
+        None => date.format("%Y-%m-%d %H:%M").to_string()
+    }
+}
+
 pub fn get_duration_in_hours(duration: Duration) -> i64 {
     duration.num_hours()
 }
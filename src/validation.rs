@@ -0,0 +1,615 @@
+use std::collections::{BTreeMap, HashSet};
+
+use serde::Serialize;
+
+use chrono::NaiveDateTime;
+
+use chrono_period::NaivePeriod;
+
+use super::{Error, ProductionSchedule};
+use super::error::{downtime_path, phase_path, recipe_path, template_path};
+use super::recipes::RecipeSpec;
+use super::resources::{Resource, ResourceTracker, ResourceType};
+use super::util::{CalendarDuration, convert_string_to_duration, get_naive_date_time_from_string};
+use super::volume::Volume;
+
+/// The severity of a [ValidationIssue](ValidationIssue).
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+pub enum Severity {
+    /// The BPD file cannot be scheduled, or would be scheduled incorrectly.
+    #[serde(rename="error")]
+    Error,
+
+    /// The BPD file can be scheduled, but likely not in the way that was intended.
+    #[serde(rename="warning")]
+    Warning
+}
+
+/// A single problem found while validating a BPD file.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct ValidationIssue {
+    pub severity: Severity,
+
+    /// The path of the offending element within the BPD file, e.g.
+    /// `recipes["Damned Squirrel Mk. II"].phases[2].duration`.
+    pub path: String,
+
+    pub message: String
+}
+
+/// The result of validating a BPD file, containing every problem that was found.
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+pub struct ValidationReport {
+    pub issues: Vec<ValidationIssue>
+}
+
+impl ValidationReport {
+    /// Determine if this `ValidationReport` contains any issues of severity `Severity::Error`.
+    pub fn has_errors(&self) -> bool {
+        self.error_count() > 0
+    }
+
+    /// Retrieve the number of issues of severity `Severity::Error`.
+    pub fn error_count(&self) -> usize {
+        self.issues.iter().filter(|x| x.severity == Severity::Error).count()
+    }
+
+    /// Retrieve the number of issues of severity `Severity::Warning`.
+    pub fn warning_count(&self) -> usize {
+        self.issues.iter().filter(|x| x.severity == Severity::Warning).count()
+    }
+
+    fn error(&mut self, path: String, message: String) {
+        self.issues.push(ValidationIssue { severity: Severity::Error, path, message });
+    }
+
+    fn warning(&mut self, path: String, message: String) {
+        self.issues.push(ValidationIssue { severity: Severity::Warning, path, message });
+    }
+
+    fn error_from(&mut self, e: &Error) {
+        self.error(e.path().unwrap_or("").to_string(), e.message());
+    }
+}
+
+/// Validate BPD data, collecting every problem found rather than stopping at the first one.
+///
+/// # Arguments
+/// - `json_data`: A string slice containing the BPD data to validate.
+///
+/// # Returns
+/// - A `ValidationReport` containing every issue found. If the data is not valid JSON, or does
+///   not match the expected structure, the report contains only that single issue.
+///
+/// # Notes
+/// Validation does not schedule the production, so problems that only arise from resource
+/// contention are not reported. Use
+/// [ProductionSchedule::try_new](super::ProductionSchedule::try_new) to detect these.
+pub fn validate(json_data: &str) -> ValidationReport {
+    let mut report = ValidationReport::default();
+
+    let mut schedule: ProductionSchedule = match serde_json::from_str(json_data) {
+        Ok(x) => x,
+        Err(e) => {
+            report.error(String::new(), format!("unable to parse production schedule: {}", e));
+            return report;
+        }
+    };
+    schedule.resolve_resource_type_aliases();
+
+    check_timeline(&schedule, &mut report);
+
+    // Time zones that cannot be found have already been reported, and dates at their sites are
+    // compared as they are given.
+    let _ = schedule.resolve_time_zones();
+    check_resources(&schedule, &mut report);
+    check_phase_templates(&schedule, &mut report);
+    check_recipes(&schedule, &mut report);
+    check_pinned_phases(&schedule, &mut report);
+
+    report
+}
+
+fn check_timeline(schedule: &ProductionSchedule, report: &mut ValidationReport) {
+    if let Err(e) = schedule.timeline.start_date() {
+        report.error("timeline.start".to_string(),
+                     format!("unable to parse date \"{}\": {}", schedule.timeline.start, e));
+    }
+
+    if let Some(calendar) = &schedule.calendar {
+        if let Err(e) = calendar.working_time("calendar") {
+            report.error_from(&e);
+        }
+    }
+
+    if let Err(e) = schedule.timeline.time_zone() {
+        report.error_from(&e);
+    }
+
+    for index in 0..schedule.sites.len() {
+        if let Err(e) = schedule.get_site_time_zone(index) {
+            report.error_from(&e);
+        }
+    }
+
+    if schedule.timeline.timeline_configuration().is_none() {
+        report.error_from(&Error::UnknownTimelineConfiguration {
+            path: "timeline.configuration".to_string(),
+            configuration: schedule.timeline.configuration.clone()
+        });
+    }
+}
+
+fn check_resources(schedule: &ProductionSchedule, report: &mut ValidationReport) {
+    let mut seen_ids: HashSet<usize> = HashSet::new();
+
+    for (index, resource) in schedule.resources.iter().enumerate() {
+        if !seen_ids.insert(resource.id) {
+            report.error(format!("resources[{}].id", index),
+                         format!("duplicate resource id {} (\"{}\")", resource.id, resource.name));
+        }
+
+        if let Err(e) = schedule.verify_site(&resource.site, &format!("resources[{}]", index)[..]) {
+            report.error_from(&e);
+        }
+
+        if let Some(turnaround) = &resource.turnaround_str {
+            check_duration(report, format!("resources[{}].turnaround", index), turnaround);
+        }
+
+        let limits = [("maxHoursPerDay", resource.max_hours_per_day),
+                      ("maxHoursPerWeek", resource.max_hours_per_week)];
+        for (field, limit) in limits.iter() {
+            if limit.is_some_and(|x| x <= 0.0) {
+                report.error(format!("resources[{}].{}", index, field),
+                             "the maximum number of working hours must be positive".to_string());
+            }
+        }
+
+        for (downtime_index, downtime_spec) in resource.downtime_specs.iter().enumerate() {
+            let path = downtime_path(index, downtime_index);
+            if let Err(e) = ProductionSchedule::get_downtime_periods(downtime_spec, &path[..]) {
+                report.error_from(&e);
+            }
+        }
+    }
+
+    for (resource_type, turnaround) in schedule.turnarounds.iter() {
+        let path = format!("turnarounds[{:?}]", resource_type);
+        check_duration(report, path.clone(), turnaround);
+
+        if !has_resource_of_type(schedule, &schedule.resolve_resource_type(&resource_type[..])) {
+            report.warning(path, format!("no resources of type \"{}\" are defined",
+                                         resource_type));
+        }
+    }
+
+    if let Some(name) = &schedule.allocation_strategy {
+        if let Err(e) = ProductionSchedule::get_named_allocation_strategy(&name[..],
+                                                                          "allocationStrategy") {
+            report.error_from(&e);
+        }
+    }
+
+    for (resource_type, name) in schedule.allocation_strategies.iter() {
+        let path = format!("allocationStrategies[{:?}]", resource_type);
+        if let Err(e) = ProductionSchedule::get_named_allocation_strategy(&name[..], &path[..]) {
+            report.error_from(&e);
+        }
+
+        if !has_resource_of_type(schedule, &schedule.resolve_resource_type(&resource_type[..])) {
+            report.warning(path, format!("no resources of type \"{}\" are defined",
+                                         resource_type));
+        }
+    }
+
+    for substitute in schedule.substitutes.keys() {
+        let substitute_type: ResourceType = schedule.resolve_resource_type(&substitute[..]);
+        if !schedule.resources.iter().any(|x| x.is_of_type(&substitute_type)) {
+            report.warning(format!("substitutes[{:?}]", substitute),
+                           format!("no resources of type \"{}\" are defined", substitute));
+        }
+    }
+}
+
+fn check_phase_templates(schedule: &ProductionSchedule, report: &mut ValidationReport) {
+    let tracker: ResourceTracker = get_tracker(schedule);
+
+    let mut seen_ids: HashSet<&str> = HashSet::new();
+
+    for (index, template) in schedule.phase_templates.iter().enumerate() {
+        if !seen_ids.insert(&template.id[..]) {
+            report.error(format!("phaseTemplates[{}].id", index),
+                         format!("duplicate phase template id \"{}\"", template.id));
+        }
+
+        let path = template_path(&template.id[..]);
+        let default_duration = template.default_duration_string();
+        if !default_duration.is_empty() {
+            let duration_path = format!("{}.defaultDuration", path);
+            if let Err(e) = ProductionSchedule::parse_phase_duration(default_duration,
+                                                                     &duration_path[..]) {
+                report.error_from(&e);
+            }
+        }
+
+        for (res_index, resource_type) in template.resources_needed.iter().enumerate() {
+            if !has_resource_of_type(schedule, resource_type) {
+                report.error(format!("{}.resourcesNeeded[{}]", path, res_index),
+                             format!("no resources of type {:?} are defined", resource_type));
+            }
+        }
+
+        let staff_path = format!("{}.staffNeeded", path);
+        if let Err(e) = ProductionSchedule::verify_staff_needed(&tracker, template,
+                                                                &staff_path[..]) {
+            report.error_from(&e);
+        }
+    }
+}
+
+fn check_recipes(schedule: &ProductionSchedule, report: &mut ValidationReport) {
+    let tracker: ResourceTracker = get_tracker(schedule);
+
+    // Repeated recipes whose instances cannot be determined are reported below. Until they are
+    // fixed, any of their instances can be depended on.
+    let dated_recipe_specs: Vec<RecipeSpec> = schedule.recipe_specs.iter()
+      .map(|x| match (&x.repeat, &x.start_string) {
+          (Some(_), None) => RecipeSpec {
+              start_string: Some(schedule.timeline.start.clone()),
+              ..x.clone()
+          },
+          _ => x.clone()
+      })
+      .collect();
+    let instance_counts: BTreeMap<String, usize> = dated_recipe_specs.iter()
+      .filter(|x| x.repeat.is_some())
+      .map(|x| {
+          let count: usize = ProductionSchedule::get_recipe_instance_dates(x)
+                               .map_or(usize::MAX, |dates| dates.len());
+          (x.name.clone(), count)
+      })
+      .collect();
+
+    for (recipe_spec, dated_recipe_spec) in schedule.recipe_specs.iter()
+                                                    .zip(dated_recipe_specs.iter()) {
+        let path = recipe_path(&recipe_spec.name[..]);
+        if let Err(e) = schedule.verify_site(&recipe_spec.site, &path[..]) {
+            report.error_from(&e);
+        }
+
+        if let Some(start) = &recipe_spec.start_string {
+            if let Err(e) = get_naive_date_time_from_string(&start[..]) {
+                report.error(format!("{}.start", recipe_path(&recipe_spec.name[..])),
+                             format!("unable to parse date \"{}\": {}", start, e));
+            }
+        }
+
+        let deadlines = [("dueDate", &recipe_spec.due_date_string),
+                         ("readyBy", &recipe_spec.ready_by_string)];
+        for (field, deadline) in deadlines.iter() {
+            let deadline: &str = match deadline {
+                Some(x) => &x[..],
+                None => continue
+            };
+
+            let path = format!("{}.{}", recipe_path(&recipe_spec.name[..]), field);
+            match get_naive_date_time_from_string(deadline) {
+                Ok(x) => {
+                    let start: Option<NaiveDateTime> = match &recipe_spec.start_string {
+                        Some(_) => recipe_spec.start_date().ok(),
+                        None => schedule.timeline.start_date().ok()
+                    };
+
+                    if start.is_some_and(|start| x <= start) {
+                        report.warning(path, format!("the {} is not after the start of the recipe, \
+                                                      so it cannot be met", field));
+                    }
+                },
+                Err(e) => {
+                    report.error(path, format!("unable to parse date \"{}\": {}", deadline, e));
+                }
+            }
+        }
+
+        // Unparseable start dates have already been reported.
+        if recipe_spec.repeat.is_none() || dated_recipe_spec.start_date().is_ok() {
+            if let Err(e) = ProductionSchedule::get_recipe_instance_specs(dated_recipe_spec,
+                                                                          &instance_counts) {
+                report.error_from(&e);
+            }
+        }
+
+        let batch_size: Option<Volume> = match recipe_spec.batch_size() {
+            Ok(x) => x,
+            Err(value) => {
+                report.error(format!("{}.batchSize", recipe_path(&recipe_spec.name[..])),
+                             format!("unable to parse volume \"{}\"", value));
+                None
+            }
+        };
+
+        // The resource types used by the previous phase, which can be carried over.
+        let mut previous_resources: Vec<ResourceType> = vec![];
+
+        for (index, phase_spec) in recipe_spec.phase_specs.iter().enumerate() {
+            let path = phase_path(&recipe_spec.name[..], index);
+
+            if let Some(start) = &phase_spec.start_string {
+                if let Err(e) = get_naive_date_time_from_string(&start[..]) {
+                    report.error(format!("{}.start", path),
+                                 format!("unable to parse date \"{}\": {}", start, e));
+                }
+            }
+
+            for (res_index, resource_type) in phase_spec.carry_over.iter().flatten().enumerate() {
+                if !has_resource_of_type(schedule, resource_type) {
+                    report.error(format!("{}.carryOver[{}]", path, res_index),
+                                 format!("no resources of type {:?} are defined", resource_type));
+                }
+            }
+
+            match schedule.get_phase_by_id(&phase_spec.template[..]) {
+                Some(template) => {
+                    for resource_type in phase_spec.carry_over(&template).iter() {
+                        if !previous_resources.contains(resource_type) {
+                            report.error(path.clone(),
+                                         format!("the previous phase does not use a resource of \
+                                                  type {:?} to carry over", resource_type));
+                        }
+                    }
+
+                    previous_resources = phase_spec.resources_needed(&template);
+
+                    let carry_over: Vec<ResourceType> = phase_spec.carry_over(&template);
+                    let selectors = [("resources", &phase_spec.resources),
+                                     ("preferredResources", &phase_spec.preferred_resources)];
+                    for (field, selectors) in selectors.iter() {
+                        for (selector_index, selector) in selectors.iter().enumerate() {
+                            let selector_path = format!("{}.{}[{}]", path, field, selector_index);
+                            let res: &Resource = match tracker.find_resource(selector) {
+                                Some(x) => x,
+                                None => {
+                                    report.error_from(&Error::UnknownResource {
+                                        path: selector_path,
+                                        resource: selector.clone()
+                                    });
+                                    continue;
+                                }
+                            };
+
+                            let carried_over_type: Option<&ResourceType>
+                              = carry_over.iter().find(|x| res.is_of_type(x));
+                            if let Some(resource_type) = carried_over_type {
+                                report.warning(selector_path,
+                                               format!("resources of type {:?} are carried over \
+                                                        from the previous phase, so \"{}\" is \
+                                                        ignored", resource_type, res.name));
+                            } else if !res.can_hold_batch(batch_size.as_ref()) {
+                                report.warning(selector_path,
+                                               format!("\"{}\" cannot hold a batch of {}",
+                                                       res.name, batch_size.unwrap()));
+                            }
+                        }
+                    }
+
+                    for resource_type in phase_spec.resources_needed(&template).iter() {
+                        if tracker.has_resource_of_type(resource_type)
+                          && !tracker.can_hold_batch_with_resources_of_type(resource_type,
+                                                                            batch_size.as_ref()) {
+                            report.error(path.clone(),
+                                         format!("resources of type {:?} cannot hold a batch of \
+                                                  {}, even when combined", resource_type,
+                                                 batch_size.unwrap()));
+                        }
+                    }
+                },
+                None => {
+                    previous_resources = vec![];
+                    report.error(format!("{}.template", path),
+                                 format!("unknown phase template \"{}\"", phase_spec.template));
+                }
+            }
+
+            if !phase_spec.duration_string.is_empty() {
+                let duration_path = format!("{}.duration", path);
+                if let Err(e) = ProductionSchedule::parse_phase_duration(
+                                  &phase_spec.duration_string[..], &duration_path[..]) {
+                    report.error_from(&e);
+                }
+            }
+
+            let durations = [("minGap", &phase_spec.min_gap_string),
+                             ("maxGap", &phase_spec.max_gap_string)];
+            for (field, value) in durations.iter() {
+                if !value.is_empty() {
+                    check_duration(report, format!("{}.{}", path, field), value);
+                }
+            }
+
+            if let (Some(min_gap), Some(max_gap)) = (phase_spec.min_gap(), phase_spec.max_gap()) {
+                if min_gap > max_gap {
+                    report.error_from(&Error::InvalidGap {
+                        path: format!("{}.maxGap", path),
+                        min_gap: phase_spec.min_gap_string.clone(),
+                        max_gap: phase_spec.max_gap_string.clone()
+                    });
+                }
+            }
+
+            for (dependency_index, dependency_spec) in phase_spec.after.iter().flatten().enumerate() {
+                let dependency_path = format!("{}.after[{}]", path, dependency_index);
+                let predecessor_recipe = match &dependency_spec.recipe {
+                    Some(x) => schedule.recipe_specs.iter().find(|other| {
+                        other.name == *x
+                          || instance_counts.get(&other.name)
+                                            .is_some_and(|count| other.is_instance_name(x, *count))
+                    }),
+                    None => Some(recipe_spec)
+                };
+
+                let predecessor_count = match predecessor_recipe {
+                    Some(x) => x.phase_specs.iter()
+                                .filter(|other| other.get_id() == dependency_spec.phase)
+                                .count(),
+                    None => {
+                        report.error(format!("{}.recipe", dependency_path),
+                                     format!("unknown recipe \"{}\"",
+                                             dependency_spec.recipe.as_ref().unwrap()));
+                        1
+                    }
+                };
+
+                match predecessor_count {
+                    0 => report.error(format!("{}.phase", dependency_path),
+                                      format!("unknown phase \"{}\"", dependency_spec.phase)),
+                    1 => {},
+                    _ => report.error(format!("{}.phase", dependency_path),
+                                      format!("several phases are identified by \"{}\"; give \
+                                               them distinct ids", dependency_spec.phase))
+                }
+
+                if !dependency_spec.lag_string.is_empty() {
+                    check_duration(report, format!("{}.lag", dependency_path),
+                                   &dependency_spec.lag_string[..]);
+                }
+            }
+        }
+
+        // Cycles can only be detected once every dependency has been resolved.
+        if let Ok(dependencies) = schedule.get_phase_dependencies(recipe_spec) {
+            if let Err(e) = ProductionSchedule::get_phase_order(recipe_spec, &dependencies) {
+                report.error_from(&e);
+            }
+        }
+    }
+
+    // Recipes whose dependencies cannot be resolved have already been reported, and are left out.
+    let dependencies: Vec<_> = schedule.recipe_specs.iter()
+                                       .map(|x| schedule.get_phase_dependencies(x).unwrap_or_default())
+                                       .collect();
+    if let Err(e) = schedule.get_recipe_order(&dependencies) {
+        report.error_from(&e);
+    }
+}
+
+/// Warn about phases that are pinned to start at a specific date, but that need a `ResourceType`
+/// whose `Resource`s are all out of service at some point during the phase.
+///
+/// # Notes
+/// A phase is pinned if it has a `start` of its own. The `start` of a recipe is only the earliest
+/// date at which its phases can start, so it doesn't pin any of them. As when scheduling, pinned
+/// dates and downtime are given in the local time of their sites, and compared in UTC.
+fn check_pinned_phases(schedule: &ProductionSchedule, report: &mut ValidationReport) {
+    let tracker: ResourceTracker = get_tracker(schedule);
+    let mut downtime: Vec<(&Resource, Vec<NaivePeriod>)> = vec![];
+    for (index, resource) in schedule.resources.iter().enumerate() {
+        let site: Option<&str> = resource.site.as_deref();
+        let mut periods: Vec<NaivePeriod> = vec![];
+        for (downtime_index, downtime_spec) in resource.downtime_specs.iter().enumerate() {
+            let path = downtime_path(index, downtime_index);
+            if let Ok(x) = ProductionSchedule::get_downtime_periods(downtime_spec, &path[..]) {
+                periods.extend(x.iter().map(|period| {
+                    NaivePeriod::new(schedule.get_schedule_date(period.start, site),
+                                     schedule.get_schedule_date(period.end, site))
+                }));
+            }
+        }
+
+        downtime.push((resource, periods));
+    }
+
+    for recipe_spec in schedule.recipe_specs.iter() {
+        for (index, phase_spec) in recipe_spec.phase_specs.iter().enumerate() {
+            if let Some(start) = phase_spec.start_date().ok().flatten() {
+                check_pinned_phase(schedule, report, &tracker, &downtime, recipe_spec, index,
+                                   start);
+            }
+        }
+    }
+}
+
+/// Warn about a pinned phase if every `Resource` usable as a `ResourceType` it needs is out of
+/// service at some point during the phase.
+///
+/// # Arguments
+/// - `tracker`: A `ResourceTracker` tracking the resources of `schedule`, used to determine which
+///   `Resource`s can be used as a `ResourceType`.
+/// - `downtime`: Each `Resource`, along with its periods of downtime in UTC.
+/// - `recipe_spec`: The `RecipeSpec` containing the phase.
+/// - `index`: The index of the phase within `recipe_spec`.
+/// - `start`: The date at which the phase is pinned to start, in the local time of the site of
+///   `recipe_spec`.
+#[allow(clippy::too_many_arguments)]
+fn check_pinned_phase(schedule: &ProductionSchedule, report: &mut ValidationReport,
+                      tracker: &ResourceTracker, downtime: &[(&Resource, Vec<NaivePeriod>)],
+                      recipe_spec: &RecipeSpec, index: usize, start: NaiveDateTime) {
+    let phase_spec = &recipe_spec.phase_specs[index];
+    let template = match schedule.get_phase_by_id(&phase_spec.template[..]) {
+        Some(x) => x,
+        None => return
+    };
+
+    // Durations that cannot be parsed have already been reported.
+    let path: String = phase_path(&recipe_spec.name[..], index);
+    let duration: CalendarDuration
+      = match ProductionSchedule::get_phase_duration(phase_spec, &template, &path[..]) {
+          Ok(x) => x,
+          Err(_) => return
+      };
+
+    let schedule_start: NaiveDateTime = schedule.get_schedule_date(start,
+                                                                   recipe_spec.site.as_deref());
+    let phase_period = match duration.get_duration_from(schedule_start) {
+        Some(x) => NaivePeriod::from_start_duration(schedule_start, x),
+        None => return
+    };
+
+    for resource_type in phase_spec.resources_needed(&template).iter() {
+        let mut resources = downtime.iter()
+                                    .filter(|(x, _)| {
+                                        tracker.is_resource_usable_as(x, resource_type)
+                                    })
+                                    .peekable();
+        let is_down = resources.peek().is_some() && resources.all(|(_, periods)| {
+            periods.iter().any(|period| period.intersects_with(phase_period))
+        });
+
+        if is_down {
+            report.warning(path.clone(),
+                           format!("every resource of type {:?} is out of service during \
+                                    this phase, so it cannot start at {}", resource_type,
+                                   start));
+        }
+    }
+}
+
+/// Retrieve a `ResourceTracker` tracking the resources of a schedule, and the substitutes between
+/// their types.
+fn get_tracker(schedule: &ProductionSchedule) -> ResourceTracker {
+    let mut tracker = ResourceTracker::new();
+    schedule.resources.iter().for_each(|res| tracker.track_resource(res.clone()));
+    for (substitute, resource_type) in schedule.get_substitutions() {
+        tracker.add_substitute(substitute, resource_type);
+    }
+
+    tracker
+}
+
+/// Determine if a schedule defines a resource of a specific type, or of a substitute for it.
+fn has_resource_of_type(schedule: &ProductionSchedule, resource_type: &ResourceType) -> bool {
+    let substitutions: Vec<(ResourceType, ResourceType)> = schedule.get_substitutions();
+    schedule.resources.iter().any(|x| {
+        x.is_of_type(resource_type)
+          || substitutions.iter().any(|(substitute, substituted)| {
+                 x.is_of_type(substitute) && substituted == resource_type
+             })
+    })
+}
+
+/// Report a duration string at `path` that cannot be parsed, along with the reason why.
+fn check_duration(report: &mut ValidationReport, path: String, value: &str) {
+    if let Err(reason) = convert_string_to_duration(value) {
+        report.error_from(&Error::DurationParse { path, value: value.to_string(), reason });
+    }
+}
@@ -0,0 +1,107 @@
+use std::fmt;
+
+/// A unit in which a [Volume](Volume) can be expressed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum VolumeUnit {
+    /// US gallons, specified as `g` or `gal`.
+    Gallons,
+
+    /// Litres, specified as `l`.
+    Litres,
+
+    /// US beer barrels (31 US gallons), specified as `bbl`.
+    Barrels,
+
+    /// Hectolitres, specified as `hl`.
+    Hectolitres
+}
+
+impl VolumeUnit {
+    /// Retrieve the number of litres in a single one of this unit.
+    pub fn litres_per_unit(self) -> f64 {
+        match self {
+            VolumeUnit::Gallons => 3.785_411_784,
+            VolumeUnit::Litres => 1.0,
+            VolumeUnit::Barrels => 31.0 * 3.785_411_784,
+            VolumeUnit::Hectolitres => 100.0
+        }
+    }
+
+    fn suffix(self) -> &'static str {
+        match self {
+            VolumeUnit::Gallons => "g",
+            VolumeUnit::Litres => "l",
+            VolumeUnit::Barrels => "bbl",
+            VolumeUnit::Hectolitres => "hl"
+        }
+    }
+}
+
+/// A volume of liquid, such as the capacity of a [Resource](super::resources::Resource) or the
+/// batch size of a [RecipeSpec](super::recipes::RecipeSpec).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Volume {
+    pub amount: f64,
+    pub unit: VolumeUnit
+}
+
+impl Volume {
+    /// Create a new `Volume` of `amount` in the unit `unit`.
+    pub fn new(amount: f64, unit: VolumeUnit) -> Self {
+        Volume { amount, unit }
+    }
+
+    /// Retrieve this `Volume` expressed in litres.
+    pub fn litres(&self) -> f64 {
+        self.amount * self.unit.litres_per_unit()
+    }
+
+    /// Determine if this `Volume` is large enough to hold another `Volume`.
+    ///
+    /// # Notes
+    /// A small tolerance is used when comparing, so that, for example, `5g` is considered large
+    /// enough to hold `18.927l`.
+    pub fn can_hold(&self, other: &Volume) -> bool {
+        self.litres() + 0.001 >= other.litres()
+    }
+}
+
+impl fmt::Display for Volume {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", self.amount, self.unit.suffix())
+    }
+}
+
+/// Try to convert a `&str` into a `Volume`.
+///
+/// # Arguments
+/// * `volume_string`: A string slice containing a non-negative number followed by a unit, e.g.
+///   `10g`, `19.5 l`, `7bbl` or `2hl`. Units are case-insensitive.
+///
+/// # Returns
+/// * An `Option` containing the `Volume`, or `None` if the string does not describe a volume. Note
+///   that capacities such as `2k` (two kegs) are valid capacities, but not volumes.
+///
+/// # Examples
+///
+/// ```
+/// # use chronogrog::volume::{convert_string_to_volume, Volume, VolumeUnit};
+/// assert_eq!(Some(Volume::new(10.0, VolumeUnit::Gallons)), convert_string_to_volume("10g"));
+/// assert_eq!(None, convert_string_to_volume("2k"));
+/// ```
+pub fn convert_string_to_volume(volume_string: &str) -> Option<Volume> {
+    let trimmed = volume_string.trim();
+    let split_index = trimmed.find(|c: char| !(c.is_ascii_digit() || c == '.'))?;
+    let (amount_string, unit_string) = trimmed.split_at(split_index);
+
+    let amount: f64 = amount_string.parse::<f64>().ok()?;
+    let unit = match &unit_string.trim().to_lowercase()[..] {
+        "g" | "gal" => VolumeUnit::Gallons,
+        "l" => VolumeUnit::Litres,
+        "bbl" => VolumeUnit::Barrels,
+        "hl" => VolumeUnit::Hectolitres,
+        _ => return None
+    };
+
+    Some(Volume::new(amount, unit))
+}
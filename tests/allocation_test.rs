@@ -0,0 +1,171 @@
+use std::sync::Arc;
+
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+
+use chrono_period::NaivePeriod;
+
+use chronogrog::{Error, ProductionSchedule};
+use chronogrog::allocation::{get_allocation_strategy, AllocationStrategy,
+                              ALLOCATION_STRATEGY_NAMES};
+use chronogrog::resources::{Resource, ResourceTracker, ResourceType};
+use chronogrog::volume::{Volume, VolumeUnit};
+
+mod common;
+use common::{get_resource_names_used, get_schedule_json};
+
+/// Allocate the free resource with the maximum id.
+#[derive(Debug)]
+struct HighestId;
+
+impl AllocationStrategy for HighestId {
+    fn choose(&self, candidates: &[&Resource], _resources: &[&Resource], _period: NaivePeriod,
+              _batch_size: Option<&Volume>) -> usize {
+        candidates.len() - 1
+    }
+}
+
+fn get_tracker(strategy: &str) -> ResourceTracker {
+    let mut tracker = ResourceTracker::new();
+    tracker.track_resource(Resource::new(1, "FV-001", ResourceType::Fermentor, "20g"));
+    tracker.track_resource(Resource::new(2, "FV-002", ResourceType::Fermentor, "5g"));
+    tracker.track_resource(Resource::new(3, "FV-003", ResourceType::Fermentor, "10g"));
+    tracker.track_resource(Resource::new(4, "Kettle", ResourceType::Kettle, "20g"));
+    tracker.set_allocation_strategy(get_allocation_strategy(strategy).unwrap());
+
+    tracker
+}
+
+fn get_date(day: u32) -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(2020, 1, day).unwrap().and_hms_opt(0, 0, 0).unwrap()
+}
+
+/// Allocate a fermentor for each of several periods, given as a start day and a number of days,
+/// and retrieve the names of the fermentors allocated.
+fn allocate_fermentors(tracker: &mut ResourceTracker, periods: &[(u32, i64)]) -> Vec<String> {
+    periods.iter().map(|(day, days)| {
+        let period = NaivePeriod::from_start_duration(get_date(*day), Duration::days(*days));
+        tracker.allocate_resource_of_type_for_period(&ResourceType::Fermentor, period)
+               .unwrap()
+               .name
+               .clone()
+    }).collect()
+}
+
+#[test]
+fn it_should_retrieve_every_built_in_strategy_by_name() {
+    for name in ALLOCATION_STRATEGY_NAMES.iter() {
+        assert!(get_allocation_strategy(name).is_some(), "missing strategy {}", name);
+    }
+
+    assert!(get_allocation_strategy("random").is_none());
+}
+
+#[test]
+fn it_should_spread_allocations_with_round_robin_and_least_recently_used() {
+    let periods = [(1, 1), (3, 1), (5, 1), (7, 1)];
+
+    let mut tracker = get_tracker("lowest-id");
+    assert_eq!(vec!["FV-001", "FV-001", "FV-001", "FV-001"],
+               allocate_fermentors(&mut tracker, &periods));
+
+    for strategy in ["round-robin", "least-recently-used"].iter() {
+        let mut tracker = get_tracker(strategy);
+        assert_eq!(vec!["FV-001", "FV-002", "FV-003", "FV-001"],
+                   allocate_fermentors(&mut tracker, &periods));
+    }
+}
+
+#[test]
+fn it_should_balance_the_time_each_resource_is_allocated_for() {
+    let periods = [(1, 3), (5, 1), (7, 1), (9, 1)];
+
+    let mut tracker = get_tracker("balanced");
+    assert_eq!(vec!["FV-001", "FV-002", "FV-003", "FV-002"],
+               allocate_fermentors(&mut tracker, &periods));
+
+    let mut tracker = get_tracker("round-robin");
+    assert_eq!(vec!["FV-001", "FV-002", "FV-003", "FV-001"],
+               allocate_fermentors(&mut tracker, &periods));
+}
+
+#[test]
+fn it_should_allocate_the_smallest_resource_that_fits_with_best_fit() {
+    let period = NaivePeriod::from_start_duration(get_date(1), Duration::days(1));
+    let cases = [(4.0, "FV-002"), (8.0, "FV-003"), (15.0, "FV-001")];
+
+    for (gallons, expected) in cases.iter() {
+        let mut tracker = get_tracker("best-fit");
+        let batch_size = Volume::new(*gallons, VolumeUnit::Gallons);
+        let allocated = tracker.allocate_resource_of_type_for_period_with_capacity(
+            &ResourceType::Fermentor, period, Some(&batch_size));
+
+        assert_eq!(*expected, allocated.unwrap().name);
+    }
+}
+
+#[test]
+fn it_should_prefer_the_strategy_of_a_resource_type_over_the_default() {
+    let mut tracker = get_tracker("best-fit");
+    tracker.set_allocation_strategy_for_type(ResourceType::Fermentor, Arc::new(HighestId));
+
+    let periods = [(1, 1), (3, 1)];
+    assert_eq!(vec!["FV-003", "FV-003"], allocate_fermentors(&mut tracker, &periods));
+
+    let period = NaivePeriod::from_start_duration(get_date(1), Duration::days(1));
+    assert_eq!("Kettle", tracker.allocate_resource_of_type_for_period(&ResourceType::Kettle, period)
+                                .unwrap()
+                                .name);
+}
+
+/// Three recipes that each need a fermentor, one week after another.
+fn get_allocation_strategy_json(strategies: &str) -> String {
+    let resources = r#"{ "id": 1, "name": "FV-001", "type": "fermentor", "capacity": "5g" },
+                       { "id": 2, "name": "FV-002", "type": "fermentor", "capacity": "5g" },
+                       { "id": 3, "name": "Kettle", "type": "kettle", "capacity": "5g" }"#;
+    let templates = r#"{ "description": "Primary", "id": "primary", "order": 0,
+                         "defaultDuration": "2d", "resourcesNeeded": [ "fermentor" ] }"#;
+    let recipes = r##"{ "name": "Week 1", "color": "#000000",
+                        "phases": [ { "template": "primary" } ] },
+                      { "name": "Week 2", "color": "#000000", "start": "2020-01-08",
+                        "phases": [ { "template": "primary" } ] },
+                      { "name": "Week 3", "color": "#000000", "start": "2020-01-15",
+                        "phases": [ { "template": "primary" } ] }"##;
+
+    get_schedule_json(strategies, resources, templates, recipes)
+}
+
+#[test]
+fn it_should_allocate_resources_with_the_strategy_named_for_their_type() {
+    let weeks = ["Week 1", "Week 2", "Week 3"];
+
+    let ps = ProductionSchedule::try_new(&get_allocation_strategy_json("")[..]).unwrap();
+    let names: Vec<String> = weeks.iter().flat_map(|x| get_resource_names_used(&ps, x)).collect();
+    assert_eq!(vec!["FV-001", "FV-001", "FV-001"], names);
+
+    let json = get_allocation_strategy_json(r#""allocationStrategy": "best-fit",
+        "allocationStrategies": { "fermentor": "round-robin" },"#);
+    let ps = ProductionSchedule::try_new(&json[..]).unwrap();
+    let names: Vec<String> = weeks.iter().flat_map(|x| get_resource_names_used(&ps, x)).collect();
+    assert_eq!(vec!["FV-001", "FV-002", "FV-001"], names);
+
+    let json = get_allocation_strategy_json(r#""allocationStrategies": { "fermentor": "newest" },"#);
+    match ProductionSchedule::try_new(&json[..]) {
+        Err(e @ Error::UnknownAllocationStrategy { .. }) => {
+            assert_eq!(Some("allocationStrategies[\"fermentor\"]"), e.path());
+            assert_eq!("unknown allocation strategy \"newest\"; expected one of lowest-id, \
+                        best-fit, least-recently-used, round-robin, balanced", e.message());
+        },
+        _ => panic!("Expected an UnknownAllocationStrategy error")
+    }
+}
+
+#[test]
+fn it_should_reschedule_with_a_custom_allocation_strategy() {
+    let mut ps = ProductionSchedule::try_new(&get_allocation_strategy_json("")[..]).unwrap();
+    let strategy = get_allocation_strategy("least-recently-used").unwrap();
+    ps.set_allocation_strategy(Some(ResourceType::Fermentor), strategy).unwrap();
+
+    let weeks = ["Week 1", "Week 2", "Week 3"];
+    let names: Vec<String> = weeks.iter().flat_map(|x| get_resource_names_used(&ps, x)).collect();
+    assert_eq!(vec!["FV-001", "FV-002", "FV-001"], names);
+}
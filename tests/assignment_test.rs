@@ -0,0 +1,78 @@
+use chrono::NaiveDate;
+
+use chronogrog::{Error, ProductionSchedule};
+
+mod common;
+use common::{get_resource_names_used, get_schedule_json};
+
+/// Two recipes that each need a fermentor at the same time, with extra phase spec fields for each.
+fn get_assigned_resource_json(first_fields: &str, second_fields: &str) -> String {
+    let resources = r#"{ "id": 1, "name": "FV-001", "type": "fermentor", "capacity": "5g" },
+                       { "id": 2, "name": "Big Bertha", "type": "fermentor", "capacity": "5g" },
+                       { "id": 3, "name": "FV-003", "type": "fermentor", "capacity": "5g" }"#;
+    let templates = r#"{ "description": "Primary", "id": "primary", "order": 0,
+                         "defaultDuration": "1w", "resourcesNeeded": [ "fermentor" ] }"#;
+    let recipes = format!(r##"{{ "name": "First Lager", "color": "#000000",
+                                 "phases": [ {{ "template": "primary"{} }} ] }},
+                              {{ "name": "Second Lager", "color": "#FFFFFF",
+                                 "phases": [ {{ "template": "primary"{} }} ] }}"##,
+                          first_fields, second_fields);
+
+    get_schedule_json("", resources, templates, &recipes[..])
+}
+
+#[test]
+fn it_should_allocate_resources_assigned_to_a_phase_by_name_or_id() {
+    for assignment in [r#", "resources": ["Big Bertha"]"#, r#", "resources": [2]"#].iter() {
+        let json = get_assigned_resource_json(assignment, "");
+        let ps = ProductionSchedule::try_new(&json[..]).unwrap();
+
+        assert_eq!(vec!["Big Bertha".to_string()], get_resource_names_used(&ps, "First Lager"));
+        assert_eq!(vec!["FV-001".to_string()], get_resource_names_used(&ps, "Second Lager"));
+    }
+
+    let json = get_assigned_resource_json("", r#", "resources": ["FV-003"]"#);
+    let ps = ProductionSchedule::try_new(&json[..]).unwrap();
+    assert_eq!(vec!["FV-003".to_string()], get_resource_names_used(&ps, "Second Lager"));
+}
+
+#[test]
+fn it_should_report_an_assigned_resource_that_is_busy_or_unknown() {
+    let assignment = r#", "resources": ["Big Bertha"]"#;
+    let json = get_assigned_resource_json(assignment, assignment);
+    match ProductionSchedule::try_new(&json[..]) {
+        Err(e @ Error::ResourceBusy { .. }) => {
+            assert_eq!(Some("recipes[\"Second Lager\"].phases[0]"), e.path());
+            assert_eq!("\"Big Bertha\" is taken or out of service at 2020-01-01 00:00, when the \
+                        phase is able to start", e.message());
+        },
+        _ => panic!("Expected a ResourceBusy error")
+    }
+
+    let json = get_assigned_resource_json("", r#", "resources": ["Big Bertha", 7]"#);
+    match ProductionSchedule::try_new(&json[..]) {
+        Err(e @ Error::UnknownResource { .. }) => {
+            assert_eq!(Some("recipes[\"Second Lager\"].phases[0].resources[1]"), e.path());
+            assert_eq!("there is no resource with id 7", e.message());
+        },
+        _ => panic!("Expected an UnknownResource error")
+    }
+}
+
+#[test]
+fn it_should_only_allocate_preferred_resources_that_are_free() {
+    let json = get_assigned_resource_json(r#", "preferredResources": ["Big Bertha"]"#,
+                                          r#", "preferredResources": ["FV-003", "Big Bertha"]"#);
+    let ps = ProductionSchedule::try_new(&json[..]).unwrap();
+    assert_eq!(vec!["Big Bertha".to_string()], get_resource_names_used(&ps, "First Lager"));
+    assert_eq!(vec!["FV-003".to_string()], get_resource_names_used(&ps, "Second Lager"));
+
+    // A preferred resource that is taken is passed over, rather than waited for.
+    let json = get_assigned_resource_json(r#", "resources": ["Big Bertha"]"#,
+                                          r#", "preferredResources": ["Big Bertha"]"#);
+    let ps = ProductionSchedule::try_new(&json[..]).unwrap();
+    let second = ps.get_recipe_by_name("Second Lager").unwrap();
+    assert_eq!(vec!["FV-001".to_string()], get_resource_names_used(&ps, "Second Lager"));
+    assert_eq!(NaiveDate::from_ymd_opt(2020, 1, 1).unwrap().and_hms_opt(0, 0, 0).unwrap(),
+               second.phases[0].start_date);
+}
@@ -0,0 +1,70 @@
+use chrono::{Duration, NaiveDate};
+
+use chronogrog::ProductionSchedule;
+
+#[test]
+fn it_should_start_a_phase_when_all_of_its_resource_types_are_free_at_once() {
+    let json = r##"{
+        "name": "Simultaneous Resources Schedule",
+        "id": 1,
+        "timeline": { "configuration": "calendar", "start": "2020-01-01" },
+        "resources": [
+            { "id": 1, "name": "Large Kettle", "type": "kettle", "capacity": "15g" },
+            { "id": 2, "name": "FV-001", "type": "fermentor", "capacity": "5g" }
+        ],
+        "phaseTemplates": [
+            { "description": "Fermenting", "id": "fermenting", "order": 0, "defaultDuration": "10d",
+              "resourcesNeeded": [ "fermentor" ] },
+            { "description": "Boiling", "id": "boiling", "order": 1, "defaultDuration": "1d",
+              "resourcesNeeded": [ "kettle" ] },
+            { "description": "Both", "id": "both", "order": 2, "defaultDuration": "1d",
+              "resourcesNeeded": [ "kettle", "fermentor" ] }
+        ],
+        "recipes": [
+            { "name": "Fermenting Recipe", "color": "#FFFFFF", "phases": [ { "template": "fermenting" } ] },
+            { "name": "Boiling Recipe", "color": "#FFFFFF", "start": "2020-01-11",
+              "phases": [ { "template": "boiling" } ] },
+            { "name": "Test Recipe", "color": "#FFFFFF", "phases": [ { "template": "both" } ] }
+        ]
+    }"##;
+
+    // The kettle is free when the fermentor isn't, and busy by the time the fermentor is free.
+    let ps = ProductionSchedule::try_new(json).unwrap();
+
+    let recipe = ps.get_recipe_by_name("Test Recipe").unwrap();
+    let expected_start = NaiveDate::from_ymd_opt(2020, 1, 12).unwrap().and_hms_opt(0, 0, 1).unwrap();
+    assert_eq!(expected_start, recipe.phases[0].start_date);
+    assert_eq!(2, recipe.phases[0].resources_used.len());
+}
+
+#[test]
+fn it_should_shift_a_recipe_later_to_keep_resources_carried_over_between_its_phases() {
+    let json = r##"{
+        "name": "Blocked Carry Over Schedule",
+        "id": 1,
+        "timeline": { "configuration": "calendar", "start": "2020-01-01" },
+        "resources": [ { "id": 1, "name": "Keg 001", "type": "keg", "capacity": "5g" } ],
+        "phaseTemplates": [
+            { "description": "Carbonation", "id": "carbonation", "order": 0, "defaultDuration": "10d",
+              "resourcesNeeded": [ "keg" ] },
+            { "description": "Available to Drink", "id": "ready", "order": 1, "defaultDuration": "30d",
+              "carryOver": [ "keg" ] }
+        ],
+        "recipes": [
+            { "name": "Other Recipe", "color": "#FFFFFF", "start": "2020-02-12",
+              "phases": [ { "template": "carbonation", "duration": "5d" } ] },
+            { "name": "Test Recipe", "color": "#FFFFFF",
+              "phases": [ { "template": "carbonation" }, { "template": "ready", "minGap": "5d" } ] }
+        ]
+    }"##;
+
+    // The keg would be free for carbonation and the following thirty days, but not once the
+    // minimum gap is added, so the recipe has to wait until the other recipe is done with it.
+    let ps = ProductionSchedule::try_new(json).unwrap();
+
+    let recipe = ps.get_recipe_by_name("Test Recipe").unwrap();
+    let expected_start = NaiveDate::from_ymd_opt(2020, 2, 17).unwrap().and_hms_opt(0, 0, 1).unwrap();
+    assert_eq!(expected_start, recipe.phases[0].start_date);
+    assert_eq!(expected_start + Duration::days(15), recipe.phases[1].start_date);
+    assert_eq!("Keg 001", recipe.phases[1].resources_used[0].name);
+}
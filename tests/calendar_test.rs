@@ -0,0 +1,114 @@
+use chrono::{NaiveDate, NaiveDateTime, Duration};
+
+use chronogrog::{Error, ProductionSchedule};
+use chronogrog::calendar::WorkingCalendar;
+use chronogrog::timezone::TimeZone;
+
+fn get_date(day: u32, hour: u32) -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(2020, 1, day).unwrap().and_hms_opt(hour, 0, 0).unwrap()
+}
+
+fn get_calendar(json: &str) -> WorkingCalendar {
+    serde_json::from_str(json).unwrap()
+}
+
+#[test]
+fn it_should_default_to_the_whole_of_every_weekday() {
+    let working_time = get_calendar("{}").working_time("calendar").unwrap();
+
+    // 2020-01-04 is a Saturday.
+    assert_eq!(Some(get_date(6, 0)), working_time.get_next_working_date(get_date(4, 12)));
+    assert_eq!(Some(get_date(3, 12)), working_time.get_next_working_date(get_date(3, 12)));
+    assert_eq!(Some(get_date(6, 12)), working_time.get_end_of_work(get_date(3, 12), Duration::days(1)));
+}
+
+#[test]
+fn it_should_stretch_work_across_nights_weekends_and_holidays() {
+    let calendar = get_calendar(r#"{ "hours": "08:00-17:00", "holidays": [ "2020-01-06" ] }"#);
+    let working_time = calendar.working_time("calendar").unwrap();
+
+    assert_eq!(Some(get_date(2, 8)), working_time.get_next_working_date(get_date(1, 18)));
+
+    // Friday afternoon, then Monday is a holiday, so the work continues on Tuesday.
+    assert_eq!(Some(get_date(7, 11)), working_time.get_end_of_work(get_date(3, 14), Duration::hours(6)));
+}
+
+#[test]
+fn it_should_support_shifts_that_run_past_midnight() {
+    let calendar = get_calendar(r#"{ "weekdays": [ "wednesday" ], "hours": "22:00-06:00" }"#);
+    let working_time = calendar.working_time("calendar").unwrap();
+
+    // The Wednesday night shift is still in progress early on Thursday morning.
+    assert_eq!(Some(get_date(2, 3)), working_time.get_next_working_date(get_date(2, 3)));
+    assert_eq!(Some(get_date(8, 22)), working_time.get_next_working_date(get_date(2, 6)));
+    assert_eq!(Some(get_date(9, 2)), working_time.get_end_of_work(get_date(2, 3), Duration::hours(7)));
+}
+
+#[test]
+fn it_should_report_unparseable_calendars_with_their_paths() {
+    let paths: Vec<String> = [
+        r#"{ "weekdays": [ "mon", "someday" ] }"#,
+        r#"{ "hours": "morning" }"#,
+        r#"{ "holidays": [ "christmas" ] }"#,
+        r#"{ "weekdays": [] }"#
+    ].iter().map(|json| {
+        match get_calendar(json).working_time("calendar") {
+            Err(e @ Error::InvalidCalendar { .. }) | Err(e @ Error::DateParse { .. }) => {
+                e.path().unwrap().to_string()
+            },
+            _ => panic!("Expected a calendar error for {}", json)
+        }
+    }).collect();
+
+    assert_eq!(vec!["calendar.weekdays[1]", "calendar.hours", "calendar.holidays[0]",
+                    "calendar.weekdays"], paths);
+}
+
+#[test]
+fn it_should_keep_working_hours_in_the_local_time_of_a_time_zone() {
+    let calendar = get_calendar(r#"{ "hours": "08:00-17:00" }"#);
+    let eastern = TimeZone::from_name("EST5EDT,M3.2.0,M11.1.0").unwrap();
+    let working_time = calendar.working_time("calendar").unwrap().in_time_zone(Some(&eastern));
+
+    // 08:00 local time is 13:00 UTC in winter, but 12:00 UTC in summer.
+    assert_eq!(Some(get_date(2, 13)), working_time.get_next_working_date(get_date(2, 0)));
+    let july_1 = NaiveDate::from_ymd_opt(2020, 7, 1).unwrap();
+    assert_eq!(Some(july_1.and_hms_opt(12, 0, 0).unwrap()),
+               working_time.get_next_working_date(july_1.and_hms_opt(0, 0, 0).unwrap()));
+}
+
+#[test]
+fn it_should_only_work_on_staffed_phases_during_working_time() {
+    let json = r##"{
+        "name": "Calendar Schedule",
+        "id": 1,
+        "timeline": { "configuration": "calendar", "start": "2020-01-01" },
+        "calendar": { "hours": "08:00-17:00", "holidays": [ "2020-01-01" ] },
+        "resources": [
+            { "id": 1, "name": "Kettle", "type": "kettle", "capacity": "5g" },
+            { "id": 2, "name": "FV-001", "type": "fermentor", "capacity": "5g" }
+        ],
+        "phaseTemplates": [
+            { "description": "Brewing", "id": "brewing", "order": 0, "defaultDuration": "10h",
+              "resourcesNeeded": [ "kettle" ], "staffed": true },
+            { "description": "Primary", "id": "primary", "order": 1, "defaultDuration": "10d",
+              "resourcesNeeded": [ "fermentor" ] }
+        ],
+        "recipes": [
+            { "name": "Test Recipe", "color": "#FFFFFF",
+              "phases": [ { "template": "brewing" }, { "template": "primary" } ] }
+        ]
+    }"##;
+    let ps = ProductionSchedule::try_new(json).unwrap();
+    let recipe = ps.get_recipe_by_name("Test Recipe").unwrap();
+
+    // New Year's Day is a holiday, so brewing starts on the 2nd, and is finished on the 3rd.
+    let brewing_start = NaiveDate::from_ymd_opt(2020, 1, 2).unwrap().and_hms_opt(8, 0, 0).unwrap();
+    let brewing_end = NaiveDate::from_ymd_opt(2020, 1, 3).unwrap().and_hms_opt(9, 0, 0).unwrap();
+    assert_eq!(brewing_start, recipe.phases[0].start_date);
+    assert_eq!(brewing_end - brewing_start, recipe.phases[0].duration);
+
+    // Fermentation runs continuously, right after brewing.
+    assert_eq!(brewing_end, recipe.phases[1].start_date);
+    assert_eq!(Duration::days(10), recipe.phases[1].duration);
+}
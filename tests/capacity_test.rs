@@ -0,0 +1,84 @@
+use chronogrog::{Error, ProductionSchedule};
+use chronogrog::phases::PhaseInstance;
+use chronogrog::resources::ResourceType;
+use chronogrog::util::get_json_data_from_file;
+
+mod common;
+use common::{get_schedule_json, FV_001, PRIMARY};
+
+/// A recipe fermented in either a 5 or a 14 gallon fermentor.
+fn get_batch_size_json(batch_size: &str) -> String {
+    let resources = format!(r#"{}, {{ "id": 2, "name": "Big Bertha", "type": "fermentor",
+                                      "capacity": "14g" }}"#, FV_001);
+    let recipe = format!(r##"{{ "name": "Test Recipe", "color": "#FFFFFF", "batchSize": "{}",
+                                "phases": [ {{ "template": "primary" }} ] }}"##, batch_size);
+
+    get_schedule_json("", &resources[..], PRIMARY, &recipe[..])
+}
+
+#[test]
+fn it_should_only_allocate_resources_large_enough_for_the_batch() {
+    let ps = ProductionSchedule::try_new(&get_batch_size_json("10g")[..]).unwrap();
+
+    let recipe = ps.get_recipe_by_name("Test Recipe").unwrap();
+    assert_eq!("Big Bertha", recipe.phases[0].resources_used[0].name);
+}
+
+#[test]
+fn it_should_report_when_no_single_resource_can_hold_the_batch() {
+    let result = ProductionSchedule::try_new(&get_batch_size_json("20g")[..]);
+
+    match result {
+        Err(Error::InsufficientCapacity { path, resource_type, batch_size }) => {
+            assert_eq!("recipes[\"Test Recipe\"].phases[0]", path);
+            assert_eq!(ResourceType::Fermentor, resource_type);
+            assert_eq!("20g", batch_size.to_string());
+        },
+        _ => panic!("Expected an insufficient capacity error")
+    }
+}
+
+#[test]
+fn it_should_split_a_batch_across_kegs_when_no_single_keg_can_hold_it() {
+    let ps = ProductionSchedule::new(&get_json_data_from_file("tests/fixtures/simple_prod_schedule.json").unwrap()[..]);
+
+    // The 10g batch fits in Big Bertha, but not in any one of the 5g kegs.
+    let recipe = ps.get_recipe_by_name("Damned Squirrel Mk. II").unwrap();
+    let get_names = |phase: &PhaseInstance, resource_type: ResourceType| -> Vec<String> {
+        phase.resources_used.iter().filter(|res| res.resource_type == resource_type)
+                            .map(|res| res.name.clone()).collect()
+    };
+
+    assert_eq!(vec!["Big Bertha"], get_names(&recipe.phases[2], ResourceType::Fermentor));
+    assert_eq!(vec!["Keg 001", "Keg 002"], get_names(&recipe.phases[4], ResourceType::Keg));
+    assert_eq!(vec!["Keg 001", "Keg 002"], get_names(&recipe.phases[5], ResourceType::Keg));
+}
+
+#[test]
+fn it_should_report_when_the_vessels_of_a_production_schedule_cannot_hold_its_batch() {
+    // The same schedule as the simple one, but the 20g batch no longer fits in the 15g kettle.
+    let result = ProductionSchedule::try_new(&get_json_data_from_file("tests/fixtures/oversized_batch_prod_schedule.json").unwrap()[..]);
+
+    match result {
+        Err(Error::InsufficientCapacity { path, resource_type, batch_size }) => {
+            assert_eq!("recipes[\"Damned Squirrel Mk. II\"].phases[1]", path);
+            assert_eq!(ResourceType::Kettle, resource_type);
+            assert_eq!("20g", batch_size.to_string());
+        },
+        Err(e) => panic!("Expected an insufficient capacity error, got: {}", e),
+        Ok(_) => panic!("Expected an insufficient capacity error")
+    }
+}
+
+#[test]
+fn it_should_report_an_unparseable_batch_size_with_its_path() {
+    let result = ProductionSchedule::try_new(&get_batch_size_json("lots")[..]);
+
+    match result {
+        Err(Error::VolumeParse { path, value }) => {
+            assert_eq!("recipes[\"Test Recipe\"].batchSize", path);
+            assert_eq!("lots", value);
+        },
+        _ => panic!("Expected a volume parse error")
+    }
+}
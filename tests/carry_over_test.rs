@@ -0,0 +1,71 @@
+use std::fs;
+
+use chrono::Duration;
+
+use chronogrog::{Error, ProductionSchedule};
+use chronogrog::resources::ResourceType;
+use chronogrog::util::get_json_data_from_file;
+
+mod common;
+use common::get_schedule_json;
+
+/// A recipe that carbonates in kegs before being ready to drink, after a recipe that is only
+/// ready to drink, with the same types carried over into each ready phase.
+fn get_carry_over_json(carry_over: &str) -> String {
+    let resources = r#"{ "id": 1, "name": "Keg 001", "type": "keg", "capacity": "5g" },
+                       { "id": 2, "name": "Keg 002", "type": "keg", "capacity": "5g" },
+                       { "id": 3, "name": "Kegerator", "type": "kegerator", "capacity": "2k" }"#;
+    let templates = r#"{ "description": "Carbonation", "id": "carbonation", "order": 0,
+                         "defaultDuration": "10d", "resourcesNeeded": [ "kegerator", "keg" ] },
+                       { "description": "Available to Drink", "id": "ready", "order": 1,
+                         "defaultDuration": "30d", "resourcesNeeded": [ ] }"#;
+    let recipes = format!(r##"{{ "name": "First Recipe", "color": "#FFFFFF",
+                                 "phases": [ {{ "template": "ready", "carryOver": {} }} ] }},
+                              {{ "name": "Test Recipe", "color": "#FFFFFF",
+                                 "phases": [ {{ "template": "carbonation" }},
+                                             {{ "template": "ready", "carryOver": {} }} ] }}"##,
+                          carry_over, carry_over);
+
+    get_schedule_json("", resources, templates, &recipes[..])
+}
+
+#[test]
+fn it_should_report_a_carry_over_with_nothing_to_carry_over() {
+    let result = ProductionSchedule::try_new(&get_carry_over_json("[ \"keg\" ]")[..]);
+
+    match result {
+        Err(Error::NothingToCarryOver { path, resource_type }) => {
+            assert_eq!("recipes[\"First Recipe\"].phases[0]", path);
+            assert_eq!(ResourceType::Keg, resource_type);
+        },
+        _ => panic!("Expected a nothing to carry over error")
+    }
+}
+
+#[test]
+fn it_should_keep_resources_carried_over_from_the_previous_phase() {
+    let json = get_carry_over_json("[ \"keg\" ]")
+                 .replacen(r#""carryOver": [ "keg" ]"#, r#""carryOver": [ ]"#, 1);
+    let ps = ProductionSchedule::try_new(&json[..]).unwrap();
+
+    let recipe = ps.get_recipe_by_name("Test Recipe").unwrap();
+    let carbonation = &recipe.phases[0];
+    let ready = &recipe.phases[1];
+
+    assert_eq!(carbonation.start_date + Duration::days(10), ready.start_date);
+    assert_eq!(1, ready.resources_used.len());
+    assert_eq!(carbonation.resources_used[1].name, ready.resources_used[0].name);
+}
+
+#[test]
+fn it_should_keep_kegs_carried_over_into_the_ready_phase_in_the_pla_file() {
+    // The same schedule as the complicated one, but each recipe is ready to drink from the kegs it
+    // was carbonated in.
+    let ps = ProductionSchedule::new(&get_json_data_from_file("tests/fixtures/carry_over_prod_schedule.json").unwrap()[..]);
+
+    let pla_format: String = ps.get_string_in_pla_format();
+
+    let contents = fs::read_to_string("tests/fixtures/carry_over_prod_schedule.pla")
+                         .expect("Something went wrong reading the file");
+    assert_eq!(contents, pla_format);
+}
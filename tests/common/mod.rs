@@ -0,0 +1,39 @@
+// Each test file only uses some of these.
+#![allow(dead_code)]
+
+use chronogrog::ProductionSchedule;
+
+/// A 5 gallon fermentor, with an id of 1.
+pub const FV_001: &str = r#"{ "id": 1, "name": "FV-001", "type": "fermentor", "capacity": "5g" }"#;
+
+/// A ten day primary fermentation in a fermentor, as the first phase of a recipe.
+pub const PRIMARY: &str = r#"{ "description": "Primary", "id": "primary", "order": 0,
+                               "defaultDuration": "10d", "resourcesNeeded": [ "fermentor" ] }"#;
+
+/// Build the JSON of a production schedule on a calendar timeline starting on 2020-01-01.
+///
+/// # Arguments
+///
+/// * `settings` - Any other top-level fields of the production schedule, each followed by a comma.
+/// * `resources` - The entries of the `resources` array, separated by commas.
+/// * `templates` - The entries of the `phaseTemplates` array, separated by commas.
+/// * `recipes` - The entries of the `recipes` array, separated by commas.
+pub fn get_schedule_json(settings: &str, resources: &str, templates: &str, recipes: &str)
+  -> String {
+    format!(r##"{{
+        "name": "Test Production Schedule",
+        "id": 1,
+        "timeline": {{ "configuration": "calendar", "start": "2020-01-01" }},
+        {}
+        "resources": [ {} ],
+        "phaseTemplates": [ {} ],
+        "recipes": [ {} ]
+    }}"##, settings, resources, templates, recipes)
+}
+
+/// Retrieve the names of the resources used by the first phase of a recipe.
+pub fn get_resource_names_used(ps: &ProductionSchedule, recipe_name: &str) -> Vec<String> {
+    ps.get_recipe_by_name(recipe_name).unwrap().phases[0].resources_used.iter()
+      .map(|res| res.name.clone())
+      .collect()
+}
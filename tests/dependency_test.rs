@@ -0,0 +1,138 @@
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+
+use chronogrog::{Error, ProductionSchedule};
+
+mod common;
+use common::{get_schedule_json, FV_001, PRIMARY};
+
+/// A recipe with a yeast starter, brewing, primary fermentation and dry hopping phases.
+fn get_dependency_json(phases: &str) -> String {
+    let resources = r#"{ "id": 1, "name": "Large Kettle", "type": "kettle", "capacity": "15g" },
+                       { "id": 2, "name": "FV-001", "type": "fermentor", "capacity": "5g" }"#;
+    let templates = r#"{ "description": "Yeast Starter", "id": "starter", "order": 0,
+                         "defaultDuration": "2d" },
+                       { "description": "Brewing", "id": "brewing", "order": 1,
+                         "defaultDuration": "1d", "resourcesNeeded": [ "kettle" ] },
+                       { "description": "Primary", "id": "primary", "order": 2,
+                         "defaultDuration": "10d", "resourcesNeeded": [ "fermentor" ] },
+                       { "description": "Dry Hopping", "id": "dryhop", "order": 3,
+                         "defaultDuration": "3d" }"#;
+    let recipe = format!(r##"{{ "name": "Test Recipe", "color": "#FFFFFF", "phases": [ {} ] }}"##,
+                         phases);
+
+    get_schedule_json("", resources, templates, &recipe[..])
+}
+
+/// A blend of two base recipes, which both need the only fermentor.
+fn get_cross_recipe_json(blend_after: &str, base_after: &str) -> String {
+    let templates = format!(r#"{}, {{ "description": "Blending", "id": "blending", "order": 1,
+                                      "defaultDuration": "1d" }}"#, PRIMARY);
+    let recipes = format!(r##"{{ "name": "Blend", "color": "#FFFFFF",
+                                 "phases": [ {{ "template": "blending", "after": [ {} ] }} ] }},
+                              {{ "name": "Base A", "color": "#FFFFFF",
+                                 "phases": [ {{ "template": "primary", "after": [ {} ] }} ] }},
+                              {{ "name": "Base B", "color": "#FFFFFF",
+                                 "phases": [ {{ "template": "primary" }} ] }}"##,
+                          blend_after, base_after);
+
+    get_schedule_json("", FV_001, &templates[..], &recipes[..])
+}
+
+#[test]
+fn it_should_schedule_phases_from_their_dependencies() {
+    let json = get_dependency_json(r#"
+        { "template": "starter", "after": [] },
+        { "template": "brewing", "after": [ { "phase": "starter", "type": "SS", "lag": "1d" } ] },
+        { "template": "primary", "after": [ { "phase": "starter" }, { "phase": "brewing" } ] },
+        { "template": "dryhop", "after": [ { "phase": "primary", "type": "FF", "lag": "1d" } ] }
+    "#);
+    let ps = ProductionSchedule::try_new(&json[..]).unwrap();
+
+    let recipe = ps.get_recipe_by_name("Test Recipe").unwrap();
+    let start = NaiveDate::from_ymd_opt(2020, 1, 1).unwrap().and_hms_opt(0, 0, 0).unwrap();
+    let starts: Vec<NaiveDateTime> = recipe.phases.iter().map(|x| x.start_date).collect();
+    assert_eq!(vec![start, start + Duration::days(1), start + Duration::days(2),
+                    start + Duration::days(10)], starts);
+
+    // Dependencies are listed on the predecessor.
+    let dependencies: Vec<Vec<usize>> = recipe.phases.iter().map(|x| x.dependencies.clone()).collect();
+    assert_eq!(vec![vec![3, 4], vec![4], vec![5], vec![]], dependencies);
+}
+
+#[test]
+fn it_should_report_a_cycle_of_phase_dependencies() {
+    let json = get_dependency_json(r#"
+        { "template": "starter", "after": [] },
+        { "template": "brewing", "after": [ { "phase": "primary" } ] },
+        { "template": "primary", "after": [ { "phase": "brewing" } ] }
+    "#);
+
+    match ProductionSchedule::try_new(&json[..]) {
+        Err(Error::DependencyCycle { path, phases }) => {
+            assert_eq!("recipes[\"Test Recipe\"]", path);
+            assert_eq!(vec!["brewing".to_string(), "primary".to_string()], phases);
+        },
+        _ => panic!("Expected a dependency cycle error")
+    }
+}
+
+#[test]
+fn it_should_report_a_dependency_on_an_unknown_phase_with_its_path() {
+    let json = get_dependency_json(r#"
+        { "template": "starter" },
+        { "template": "brewing", "after": [ { "phase": "mashing" } ] }
+    "#);
+
+    match ProductionSchedule::try_new(&json[..]) {
+        Err(Error::UnknownPhase { path, phase }) => {
+            assert_eq!("recipes[\"Test Recipe\"].phases[1].after[0].phase", path);
+            assert_eq!("mashing", phase);
+        },
+        _ => panic!("Expected an unknown phase error")
+    }
+}
+
+#[test]
+fn it_should_schedule_recipes_after_the_recipes_they_depend_on() {
+    let json = get_cross_recipe_json(r#"{ "recipe": "Base A", "phase": "primary" },
+                                        { "recipe": "Base B", "phase": "primary" }"#, "");
+    let ps = ProductionSchedule::try_new(&json[..]).unwrap();
+
+    // Both bases need the only fermentor, so the blend has to wait for both of them.
+    let blend = ps.get_recipe_by_name("Blend").unwrap();
+    let base_b = ps.get_recipe_by_name("Base B").unwrap();
+    assert_eq!(base_b.phases[0].start_date + Duration::days(10), blend.phases[0].start_date);
+
+    // Ids still follow the order of the BPD file, and dependencies are listed on the predecessor.
+    let pla = ps.get_string_in_pla_format();
+    assert!(pla.starts_with("[1] Blend\n  child 2\n"));
+    assert!(pla.contains("  [4] Primary\n    start 2020-01-01\n    color #FFFFFF\n    duration 240\n    res FV-001\n    dep 2\n"));
+    assert_eq!(vec![2], base_b.phases[0].dependencies);
+}
+
+#[test]
+fn it_should_report_a_cycle_of_recipe_dependencies() {
+    let json = get_cross_recipe_json(r#"{ "recipe": "Base A", "phase": "primary" }"#,
+                                     r#"{ "recipe": "Blend", "phase": "blending" }"#);
+
+    match ProductionSchedule::try_new(&json[..]) {
+        Err(Error::RecipeCycle { path, recipes }) => {
+            assert_eq!("recipes", path);
+            assert_eq!(vec!["Blend".to_string(), "Base A".to_string()], recipes);
+        },
+        _ => panic!("Expected a recipe cycle error")
+    }
+}
+
+#[test]
+fn it_should_report_a_dependency_on_an_unknown_recipe_with_its_path() {
+    let json = get_cross_recipe_json(r#"{ "recipe": "Base C", "phase": "primary" }"#, "");
+
+    match ProductionSchedule::try_new(&json[..]) {
+        Err(Error::UnknownRecipe { path, recipe }) => {
+            assert_eq!("recipes[\"Blend\"].phases[0].after[0].recipe", path);
+            assert_eq!("Base C", recipe);
+        },
+        _ => panic!("Expected an unknown recipe error")
+    }
+}
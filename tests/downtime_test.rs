@@ -0,0 +1,58 @@
+use chrono::{NaiveDate, NaiveDateTime};
+
+use chronogrog::{Error, ProductionSchedule};
+
+mod common;
+use common::{get_schedule_json, PRIMARY};
+
+/// A recipe that needs the only fermentor, which is out of service during a downtime window.
+fn get_downtime_json(downtime: &str) -> String {
+    let resources = format!(r#"{{ "id": 1, "name": "FV-001", "type": "fermentor", "capacity": "5g",
+                                  "downtime": [ {} ] }}"#, downtime);
+    let recipe = r##"{ "name": "Test Recipe", "color": "#FFFFFF",
+                       "phases": [ { "template": "primary" } ] }"##;
+
+    get_schedule_json("", &resources[..], PRIMARY, recipe)
+}
+
+#[test]
+fn it_should_schedule_around_the_downtime_of_a_resource() {
+    let json = get_downtime_json(r#"{ "description": "gasket replacement", "start": "2020-01-05",
+                                      "end": "2020-01-10" }"#);
+    let ps = ProductionSchedule::try_new(&json[..]).unwrap();
+
+    let recipe = ps.get_recipe_by_name("Test Recipe").unwrap();
+    let expected_start = NaiveDate::from_ymd_opt(2020, 1, 10).unwrap().and_hms_opt(0, 0, 1).unwrap();
+    assert_eq!(expected_start, recipe.phases[0].start_date);
+
+    let pla = ps.get_string_in_pla_format();
+    assert!(pla.contains("[3] Downtime\n  child 4\n"));
+    assert!(pla.contains("  [4] Downtime: FV-001 (gasket replacement)\n    start 2020-01-05\n    color #D32F2F\n    duration 120\n    res FV-001\n"));
+}
+
+#[test]
+fn it_should_expand_repeating_downtime_windows() {
+    let json = get_downtime_json(r#"{ "start": "2020-01-31", "duration": "4h", "every": "monthly",
+                                      "until": "2020-04-30" }"#);
+    let ps = ProductionSchedule::try_new(&json[..]).unwrap();
+
+    let fermentor = ps.resources().into_iter().find(|res| res.id == 1).unwrap();
+    let starts: Vec<NaiveDateTime> = fermentor.downtime_periods.iter().map(|x| x.1.start).collect();
+    let expected: Vec<NaiveDateTime> = vec![(2020, 1, 31), (2020, 2, 29), (2020, 3, 31), (2020, 4, 30)]
+      .into_iter()
+      .map(|(y, m, d)| NaiveDate::from_ymd_opt(y, m, d).unwrap().and_hms_opt(0, 0, 0).unwrap())
+      .collect();
+    assert_eq!(expected, starts);
+}
+
+#[test]
+fn it_should_report_a_repeating_downtime_window_without_an_end() {
+    let json = get_downtime_json(r#"{ "start": "2020-01-05", "every": "2w" }"#);
+
+    match ProductionSchedule::try_new(&json[..]) {
+        Err(Error::InvalidDowntime { path, .. }) => {
+            assert_eq!("resources[0].downtime[0]", path);
+        },
+        _ => panic!("Expected an invalid downtime error")
+    }
+}
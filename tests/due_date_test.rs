@@ -0,0 +1,112 @@
+use chrono::{Duration, NaiveDate};
+
+use chronogrog::{Error, ProductionSchedule};
+
+mod common;
+use common::{get_schedule_json, FV_001, PRIMARY};
+
+/// Two recipes that each need the only fermentor, with extra fields for each recipe.
+fn get_due_date_json(first: &str, second: &str) -> String {
+    let recipes = format!(r##"{{ "name": "Session Ale", "color": "#FFFFFF", {}
+                                 "phases": [ {{ "template": "primary" }} ] }},
+                              {{ "name": "Festival Lager", "color": "#000000", {}
+                                 "phases": [ {{ "template": "primary" }} ] }}"##, first, second);
+
+    get_schedule_json("", FV_001, PRIMARY, &recipes[..])
+}
+
+#[test]
+fn it_should_give_higher_priority_recipes_the_first_choice_of_resources() {
+    let json = get_due_date_json(r#""priority": 1,"#, r#""priority": 5,"#);
+    let ps = ProductionSchedule::try_new(&json[..]).unwrap();
+
+    let jan_1 = NaiveDate::from_ymd_opt(2020, 1, 1).unwrap().and_hms_opt(0, 0, 0).unwrap();
+    assert_eq!(jan_1, ps.get_recipe_by_name("Festival Lager").unwrap().phases[0].start_date);
+    assert_eq!(jan_1 + Duration::days(10) + Duration::seconds(1),
+               ps.get_recipe_by_name("Session Ale").unwrap().phases[0].start_date);
+
+    // Ids still follow the order of the BPD file.
+    assert_eq!(1, ps.get_recipe_by_name("Session Ale").unwrap().id);
+}
+
+#[test]
+fn it_should_schedule_recipes_that_are_due_earlier_first() {
+    let json = get_due_date_json(r#""dueDate": "2020-02-01","#, r#""dueDate": "2020-01-15","#);
+    let ps = ProductionSchedule::try_new(&json[..]).unwrap();
+
+    assert!(ps.get_recipe_by_name("Festival Lager").unwrap().phases[0].start_date
+              < ps.get_recipe_by_name("Session Ale").unwrap().phases[0].start_date);
+    assert!(ps.get_late_recipes().is_empty());
+}
+
+#[test]
+fn it_should_report_and_annotate_late_recipes() {
+    let json = get_due_date_json(r#""priority": 1,"#, r#""dueDate": "2020-01-15","#);
+    let ps = ProductionSchedule::try_new(&json[..]).unwrap();
+
+    let late_recipes = ps.get_late_recipes();
+    assert_eq!(1, late_recipes.len());
+    assert_eq!("Festival Lager", late_recipes[0].name);
+
+    let pla = ps.get_string_in_pla_format();
+    assert!(pla.contains("[3] Festival Lager (late: due 2020-01-15 00:00)\n"));
+    assert!(pla.contains("  [4] Primary\n    start 2020-01-11 00\n    color #FF6F00\n"));
+    assert!(pla.contains("  [2] Primary\n    start 2020-01-01\n    color #FFFFFF\n"));
+}
+
+#[test]
+fn it_should_report_an_unparseable_due_date_with_its_path() {
+    let json = get_due_date_json(r#""dueDate": "whenever","#, "");
+
+    match ProductionSchedule::try_new(&json[..]) {
+        Err(Error::DateParse { path, value, .. }) => {
+            assert_eq!("recipes[\"Session Ale\"].dueDate", path);
+            assert_eq!("whenever", value);
+        },
+        _ => panic!("Expected a date parse error")
+    }
+}
+
+#[test]
+fn it_should_schedule_a_recipe_backwards_from_its_ready_by_date() {
+    let json = get_due_date_json(r#""readyBy": "2020-02-01","#, "");
+    let ps = ProductionSchedule::try_new(&json[..]).unwrap();
+
+    let session_ale = ps.get_recipe_by_name("Session Ale").unwrap();
+    let feb_1 = NaiveDate::from_ymd_opt(2020, 2, 1).unwrap().and_hms_opt(0, 0, 0).unwrap();
+    assert_eq!(feb_1 - Duration::days(10), session_ale.phases[0].start_date);
+    assert_eq!(Some(feb_1), session_ale.end_date());
+
+    // The other recipe still starts at the start of the timeline.
+    let festival_lager = ps.get_recipe_by_name("Festival Lager").unwrap();
+    assert_eq!(NaiveDate::from_ymd_opt(2020, 1, 1).unwrap().and_hms_opt(0, 0, 0).unwrap(),
+               festival_lager.phases[0].start_date);
+}
+
+#[test]
+fn it_should_move_a_recipe_earlier_to_be_ready_by_its_date_despite_other_recipes() {
+    let json = get_due_date_json(r#""start": "2020-01-12", "priority": 1,"#,
+                                 r#""readyBy": "2020-01-25","#);
+    let ps = ProductionSchedule::try_new(&json[..]).unwrap();
+
+    // The fermentor is busy from the 12th to the 22nd, so the lager has to ferment before that.
+    let festival_lager = ps.get_recipe_by_name("Festival Lager").unwrap();
+    assert!(festival_lager.end_date().unwrap()
+              < NaiveDate::from_ymd_opt(2020, 1, 12).unwrap().and_hms_opt(0, 0, 0).unwrap());
+    assert!(ps.get_late_recipes().is_empty());
+}
+
+#[test]
+fn it_should_report_a_ready_by_date_that_cannot_be_reached() {
+    let json = get_due_date_json(r#""start": "2020-01-10", "priority": 1,"#,
+                                 r#""readyBy": "2020-01-25","#);
+
+    match ProductionSchedule::try_new(&json[..]) {
+        Err(e @ Error::ReadyByUnreachable { .. }) => {
+            assert_eq!(Some("recipes[\"Festival Lager\"].readyBy"), e.path());
+            assert_eq!("unable to be ready by 2020-01-25 00:00; starting at 2020-01-01 00:00, the \
+                        recipe can be ready by 2020-01-30 00:00 at the earliest", e.message());
+        },
+        _ => panic!("Expected an unreachable ready-by date error")
+    }
+}
@@ -0,0 +1,83 @@
+use chrono::Duration;
+
+use chronogrog::{Error, ProductionSchedule};
+
+mod common;
+use common::{get_schedule_json, FV_001, PRIMARY};
+
+/// A recipe with a single primary fermentation phase.
+fn get_primary_json(start: &str, duration: &str) -> String {
+    let recipe = format!(r##"{{ "name": "Test Recipe", "color": "#FFFFFF", "start": "{}",
+                                "phases": [ {{ "template": "primary", "duration": "{}" }} ] }}"##,
+                         start, duration);
+
+    get_schedule_json("", FV_001, PRIMARY, &recipe[..])
+}
+
+#[test]
+fn it_should_reject_phase_durations_that_are_not_longer_than_zero() {
+    for duration in ["-2h", "0d", "-1mo"].iter() {
+        let json: String = get_primary_json("2020-01-01", duration);
+
+        match ProductionSchedule::try_new(&json[..]) {
+            Err(Error::DurationParse { path, value, reason }) => {
+                assert_eq!("recipes[\"Test Recipe\"].phases[0].duration", path);
+                assert_eq!(*duration, value);
+                assert_eq!("it must be longer than zero", reason);
+            },
+            _ => panic!("Expected a duration parse error for \"{}\"", duration)
+        }
+    }
+}
+
+#[test]
+fn it_should_reject_phase_durations_of_more_than_ten_thousand_years() {
+    let json: String = get_primary_json("2020-01-01", "100000000mo");
+
+    match ProductionSchedule::try_new(&json[..]) {
+        Err(Error::DurationParse { path, reason, .. }) => {
+            assert_eq!("recipes[\"Test Recipe\"].phases[0].duration", path);
+            assert_eq!("it is too long", reason);
+        },
+        _ => panic!("Expected a duration parse error")
+    }
+}
+
+#[test]
+fn it_should_report_phases_that_end_beyond_the_latest_date_that_can_be_represented() {
+    // Each phase lasts 10,000 years, and keeps the fermentor of the phase before it.
+    let phases: Vec<String> = (0..30).map(|index| match index {
+        0 => r#"{ "template": "primary", "duration": "120000mo" }"#.to_string(),
+        _ => r#"{ "template": "primary", "duration": "120000mo", "carryOver": [ "fermentor" ] }"#
+               .to_string()
+    }).collect();
+    let recipe = format!(r##"{{ "name": "Test Recipe", "color": "#FFFFFF", "phases": [ {} ] }}"##,
+                         phases.join(", "));
+    let json: String = get_schedule_json("", FV_001, PRIMARY, &recipe[..]);
+
+    match ProductionSchedule::try_new(&json[..]) {
+        Err(e @ Error::DateOutOfRange { .. }) => {
+            assert_eq!(Some("recipes[\"Test Recipe\"].phases[0]"), e.path());
+        },
+        _ => panic!("Expected a date out of range error")
+    }
+}
+
+#[test]
+fn it_should_count_calendar_months_in_phase_durations_from_the_start_of_the_phase() {
+    let json = get_primary_json("2020-02-01", "1mo");
+    let ps = ProductionSchedule::try_new(&json[..]).unwrap();
+    let phase = &ps.get_recipe_by_name("Test Recipe").unwrap().phases[0];
+
+    // February 2020 has 29 days.
+    assert_eq!(Duration::days(29), phase.duration);
+
+    let json = get_primary_json("2020-02-01", "1.5mo");
+    match ProductionSchedule::try_new(&json[..]) {
+        Err(e @ Error::DurationParse { .. }) => {
+            assert_eq!("unable to parse duration \"1.5mo\": calendar months and years must be \
+                        whole numbers, but got 1.5", e.message());
+        },
+        _ => panic!("Expected a duration parse error")
+    }
+}
@@ -0,0 +1,68 @@
+use chrono::Duration;
+
+use chronogrog::{Error, ProductionSchedule};
+
+mod common;
+use common::get_schedule_json;
+
+/// Two recipes that brew, then ferment in the only fermentor, with extra fields for the primary
+/// fermentation of the second recipe.
+fn get_gap_json(gaps: &str) -> String {
+    let resources = r#"{ "id": 1, "name": "Large Kettle", "type": "kettle", "capacity": "15g" },
+                       { "id": 2, "name": "FV-001", "type": "fermentor", "capacity": "5g" }"#;
+    let templates = r#"{ "description": "Brewing", "id": "brewing", "order": 0,
+                         "defaultDuration": "1d", "resourcesNeeded": [ "kettle" ] },
+                       { "description": "Primary", "id": "primary", "order": 1,
+                         "defaultDuration": "10d", "resourcesNeeded": [ "fermentor" ] }"#;
+    let recipes = format!(r##"{{ "name": "First Recipe", "color": "#FFFFFF",
+                                 "phases": [ {{ "template": "brewing" }},
+                                             {{ "template": "primary" }} ] }},
+                              {{ "name": "Second Recipe", "color": "#000000",
+                                 "phases": [ {{ "template": "brewing" }},
+                                             {{ "template": "primary", {} }} ] }}"##, gaps);
+
+    get_schedule_json("", resources, templates, &recipes[..])
+}
+
+#[test]
+fn it_should_shift_a_recipe_later_rather_than_exceed_a_maximum_gap() {
+    let ps = ProductionSchedule::try_new(&get_gap_json(r#""maxGap": "1d""#)[..]).unwrap();
+
+    // Without the maximum gap, the second recipe would brew right after the first, then wait nine
+    // days for the fermentor.
+    let first = ps.get_recipe_by_name("First Recipe").unwrap();
+    let second = ps.get_recipe_by_name("Second Recipe").unwrap();
+    let fermentor_free_date = first.phases[1].start_date + Duration::days(10) + Duration::seconds(1);
+    assert!(second.phases[1].start_date - (second.phases[0].start_date + Duration::days(1))
+              <= Duration::days(1));
+    assert!(second.phases[1].start_date >= fermentor_free_date);
+    assert_eq!(second.start_date, second.phases[0].start_date);
+}
+
+#[test]
+fn it_should_wait_for_a_minimum_gap() {
+    let ps = ProductionSchedule::try_new(&get_gap_json(r#""minGap": "12h""#)[..]).unwrap();
+
+    let first = ps.get_recipe_by_name("First Recipe").unwrap();
+    let second = ps.get_recipe_by_name("Second Recipe").unwrap();
+    assert_eq!(first.phases[1].start_date + Duration::days(10) + Duration::seconds(1),
+               second.phases[1].start_date);
+
+    let ps = ProductionSchedule::try_new(&get_gap_json(r#""minGap": "12d""#)[..]).unwrap();
+    let second = ps.get_recipe_by_name("Second Recipe").unwrap();
+    assert_eq!(second.phases[0].start_date + Duration::days(13), second.phases[1].start_date);
+}
+
+#[test]
+fn it_should_report_a_maximum_gap_shorter_than_the_minimum_gap() {
+    let result = ProductionSchedule::try_new(&get_gap_json(r#""minGap": "2d", "maxGap": "1d""#)[..]);
+
+    match result {
+        Err(Error::InvalidGap { path, min_gap, max_gap }) => {
+            assert_eq!("recipes[\"Second Recipe\"].phases[1].maxGap", path);
+            assert_eq!("2d", min_gap);
+            assert_eq!("1d", max_gap);
+        },
+        _ => panic!("Expected an invalid gap error")
+    }
+}
@@ -1,12 +1,19 @@
+// The original tests are kept as they were written, against the original chrono constructors.
+#![allow(deprecated)]
+#![allow(dead_code, clippy::assertions_on_constants, clippy::self_assignment)]
+
 use std::fs;
 
 use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime};
 
-use chronogrog::ProductionSchedule;
+use chronogrog::{Error, ProductionSchedule};
 use chronogrog::resources::ResourceType;
 
 use chronogrog::util::get_json_data_from_file;
 
+mod common;
+use common::{get_schedule_json, FV_001, PRIMARY};
+
 #[test]
 fn it_should_load_a_json_file_into_a_new_production_schedule() {
 
@@ -119,8 +126,105 @@ fn it_should_be_able_to_convert_a_complicated_bpd_file_to_a_pla_file() {
     assert_eq!(contents, pla_format);
 }
 
+#[test]
+fn it_should_convert_a_bpd_file_without_recipes_to_an_empty_pla_file() {
+    let ps = ProductionSchedule::try_new(r##"{
+        "name": "Empty Production Schedule",
+        "id": 1,
+        "timeline": { "configuration": "calendar", "start": "2020-01-01" },
+        "resources": [],
+        "phaseTemplates": [],
+        "recipes": []
+    }"##).unwrap();
+
+    assert_eq!("", ps.get_string_in_pla_format());
+}
+
 #[test]
 #[should_panic]
 fn it_should_panic_on_an_unparseable_json_file() {
     ProductionSchedule::new(&get_json_data_from_file("tests/fixtures/bad_production_schedule.json").unwrap()[..]);
 }
+
+#[test]
+fn it_should_return_a_json_error_for_an_unparseable_json_file() {
+    let result = ProductionSchedule::try_new(&get_json_data_from_file("tests/fixtures/bad_production_schedule.json").unwrap()[..]);
+
+    match result {
+        Err(Error::Json(_)) => {},
+        _ => panic!("Expected a JSON error")
+    }
+}
+
+#[test]
+fn it_should_load_a_production_schedule_from_a_reader() {
+    let file = fs::File::open("tests/fixtures/simple_prod_schedule.json").unwrap();
+    let ps = ProductionSchedule::from_reader(file).unwrap();
+
+    assert_eq!("Simple Production Schedule", ps.name);
+}
+
+/// A recipe with a single phase, and a brewing template that needs a kettle, which isn't defined.
+fn get_single_phase_json(start: &str, template: &str, duration: &str) -> String {
+    let templates = format!(r#"{}, {{ "description": "Brewing", "id": "brewing", "order": 1,
+                                      "defaultDuration": "1d", "resourcesNeeded": [ "kettle" ] }}"#,
+                            PRIMARY);
+    let recipe = format!(r##"{{ "name": "Test Recipe", "color": "#FFFFFF", "start": "{}",
+                                "phases": [ {{ "template": "{}", "duration": "{}" }} ] }}"##,
+                         start, template, duration);
+
+    get_schedule_json("", FV_001, &templates[..], &recipe[..])
+}
+
+#[test]
+fn it_should_report_an_unknown_template_with_its_path() {
+    let result = ProductionSchedule::try_new(&get_single_phase_json("2020-01-01", "tertiary", "")[..]);
+
+    match result {
+        Err(Error::UnknownTemplate { path, template }) => {
+            assert_eq!("recipes[\"Test Recipe\"].phases[0].template", path);
+            assert_eq!("tertiary", template);
+        },
+        _ => panic!("Expected an unknown template error")
+    }
+}
+
+#[test]
+fn it_should_report_an_unparseable_duration_with_its_path() {
+    let result = ProductionSchedule::try_new(&get_single_phase_json("2020-01-01", "primary", "10x")[..]);
+
+    match result {
+        Err(Error::DurationParse { path, value, .. }) => {
+            assert_eq!("recipes[\"Test Recipe\"].phases[0].duration", path);
+            assert_eq!("10x", value);
+        },
+        _ => panic!("Expected a duration parse error")
+    }
+}
+
+#[test]
+fn it_should_report_an_unparseable_start_date_with_its_path() {
+    let result = ProductionSchedule::try_new(&get_single_phase_json("2020-13-45", "primary", "")[..]);
+
+    match result {
+        Err(Error::DateParse { path, value, .. }) => {
+            assert_eq!("recipes[\"Test Recipe\"].start", path);
+            assert_eq!("2020-13-45", value);
+        },
+        _ => panic!("Expected a date parse error")
+    }
+}
+
+#[test]
+fn it_should_report_a_missing_resource_type_with_its_path() {
+    let result = ProductionSchedule::try_new(&get_single_phase_json("2020-01-01", "brewing", "")[..]);
+
+    match result {
+        Err(Error::MissingResourceType { path, resource_type }) => {
+            assert_eq!("recipes[\"Test Recipe\"].phases[0]", path);
+            assert_eq!(ResourceType::Kettle, resource_type);
+        },
+        Err(e) => panic!("Expected a missing resource type error, got: {}", e),
+        Ok(_) => panic!("Expected a missing resource type error")
+    }
+}
@@ -0,0 +1,167 @@
+use chrono::NaiveDate;
+
+use chronogrog::ProductionSchedule;
+use chronogrog::optimize::{optimize, Objective};
+
+/// Two recipes sharing a kettle and a fermentor. Scheduling the short brew first lets the second
+/// recipe brew while the first one ferments.
+fn get_two_recipe_json() -> String {
+    r##"{
+        "name": "Optimization Schedule",
+        "id": 1,
+        "timeline": { "configuration": "calendar", "start": "2020-01-01" },
+        "resources": [
+            { "id": 1, "name": "Kettle", "type": "kettle", "capacity": "5g" },
+            { "id": 2, "name": "FV-001", "type": "fermentor", "capacity": "5g" }
+        ],
+        "phaseTemplates": [
+            { "description": "Brewing", "id": "brewing", "order": 0, "defaultDuration": "1d",
+              "resourcesNeeded": [ "kettle" ] },
+            { "description": "Primary", "id": "primary", "order": 1, "defaultDuration": "1d",
+              "resourcesNeeded": [ "fermentor" ] }
+        ],
+        "recipes": [
+            { "name": "Long Brew", "color": "#FFFFFF",
+              "phases": [ { "template": "brewing", "duration": "5d" },
+                          { "template": "primary", "duration": "1d" } ] },
+            { "name": "Long Ferment", "color": "#000000",
+              "phases": [ { "template": "brewing", "duration": "1d" },
+                          { "template": "primary", "duration": "5d" } ] }
+        ]
+    }"##.to_string()
+}
+
+/// Assert that a number of hours is equal to an expected number of days, ignoring the single
+/// seconds that separate consecutive allocations of a resource.
+fn assert_days(expected: f64, hours: f64) {
+    assert!((expected * 24.0 - hours).abs() < 0.01, "expected {} days, got {} hours", expected,
+            hours);
+}
+
+#[test]
+fn it_should_parse_objectives_from_strings() {
+    assert_eq!(Ok(Objective::Makespan), "makespan".parse::<Objective>());
+    assert_eq!(Ok(Objective::Tardiness), "tardiness".parse::<Objective>());
+    assert_eq!(Ok(Objective::Utilization), "utilization".parse::<Objective>());
+    assert!("speed".parse::<Objective>().is_err());
+}
+
+#[test]
+fn it_should_reduce_the_makespan_by_reordering_recipes() {
+    let mut ps = ProductionSchedule::new(&get_two_recipe_json()[..]);
+    assert_days(11.0, Objective::Makespan.evaluate(&ps));
+
+    let report = optimize(&mut ps, Objective::Makespan, 0, 20).unwrap();
+
+    assert_days(11.0, report.before);
+    assert_days(7.0, report.after);
+    assert_eq!(vec![1, 0], report.order);
+    assert_days(7.0, Objective::Makespan.evaluate(&ps));
+
+    // The recipes keep their ids and their position in the output.
+    let long_brew = ps.get_recipe_by_name("Long Brew").unwrap();
+    assert_eq!(1, long_brew.id);
+    assert_eq!(NaiveDate::from_ymd_opt(2020, 1, 2).unwrap().and_hms_opt(0, 0, 1).unwrap(),
+               long_brew.phases[0].start_date);
+}
+
+#[test]
+fn it_should_never_make_the_objective_worse() {
+    for objective in [Objective::Makespan, Objective::Tardiness, Objective::Utilization].iter() {
+        let mut ps = ProductionSchedule::new(&get_two_recipe_json()[..]);
+        let report = optimize(&mut ps, *objective, 7, 20).unwrap();
+
+        assert!(!objective.is_improvement(report.before, report.after));
+        assert_eq!(report.after, objective.evaluate(&ps));
+    }
+}
+
+#[test]
+fn it_should_produce_the_same_schedule_for_the_same_seed() {
+    let mut first = ProductionSchedule::new(&get_two_recipe_json()[..]);
+    let mut second = ProductionSchedule::new(&get_two_recipe_json()[..]);
+
+    let first_report = optimize(&mut first, Objective::Tardiness, 42, 50).unwrap();
+    let second_report = optimize(&mut second, Objective::Tardiness, 42, 50).unwrap();
+
+    assert_eq!(first_report, second_report);
+    assert_eq!(first.get_string_in_pla_format(), second.get_string_in_pla_format());
+}
+
+/// A small batch whose primary fermentation can use either fermentor, and a large batch that
+/// depends on it and only fits in the larger one. The order of the recipes is fixed by the
+/// dependency, so only choosing the smaller fermentor for the small batch helps.
+fn get_resource_choice_json() -> String {
+    r##"{
+        "name": "Resource Choice Schedule",
+        "id": 1,
+        "timeline": { "configuration": "calendar", "start": "2020-01-01" },
+        "resources": [
+            { "id": 1, "name": "Kettle", "type": "kettle", "capacity": "15g" },
+            { "id": 2, "name": "Big Bertha", "type": "fermentor", "capacity": "14g" },
+            { "id": 3, "name": "FV-001", "type": "fermentor", "capacity": "5g" }
+        ],
+        "phaseTemplates": [
+            { "description": "Brewing", "id": "brewing", "order": 0, "defaultDuration": "1d",
+              "resourcesNeeded": [ "kettle" ] },
+            { "description": "Primary", "id": "primary", "order": 1, "defaultDuration": "2d",
+              "resourcesNeeded": [ "fermentor" ] }
+        ],
+        "recipes": [
+            { "name": "Small Batch", "color": "#FFFFFF", "batchSize": "5g",
+              "phases": [ { "template": "brewing" },
+                          { "template": "primary", "duration": "5d" } ] },
+            { "name": "Large Batch", "color": "#000000", "batchSize": "10g",
+              "phases": [ { "template": "primary",
+                            "after": [ { "recipe": "Small Batch", "phase": "brewing" } ] } ] }
+        ]
+    }"##.to_string()
+}
+
+#[test]
+fn it_should_reduce_the_makespan_by_choosing_other_resources() {
+    let mut ps = ProductionSchedule::new(&get_resource_choice_json()[..]);
+    assert_days(8.0, Objective::Makespan.evaluate(&ps));
+
+    let report = optimize(&mut ps, Objective::Makespan, 0, 20).unwrap();
+
+    assert_days(8.0, report.before);
+    assert_days(6.0, report.after);
+    assert_eq!(vec![(3, 3)], report.resource_choices.into_iter().collect::<Vec<_>>());
+
+    let small_batch = ps.get_recipe_by_name("Small Batch").unwrap();
+    let large_batch = ps.get_recipe_by_name("Large Batch").unwrap();
+    assert_eq!("FV-001", small_batch.phases[1].resources_used[0].name);
+    assert_eq!("Big Bertha", large_batch.phases[0].resources_used[0].name);
+}
+
+#[test]
+fn it_should_measure_utilization_by_the_time_resources_are_held() {
+    // The batch is split across both kegs, which are held through the gap before serving. The
+    // member of staff is left out.
+    let ps = ProductionSchedule::new(r##"{
+        "name": "Utilization Schedule",
+        "id": 1,
+        "timeline": { "configuration": "calendar", "start": "2020-01-01" },
+        "resources": [
+            { "id": 1, "name": "Keg 001", "type": "keg", "capacity": "5g" },
+            { "id": 2, "name": "Keg 002", "type": "keg", "capacity": "5g" },
+            { "id": 3, "name": "Kettle", "type": "kettle", "capacity": "15g" },
+            { "id": 4, "name": "Scott", "type": "staff" }
+        ],
+        "phaseTemplates": [
+            { "description": "Carbonation", "id": "carbonation", "order": 0, "defaultDuration": "1d",
+              "resourcesNeeded": [ "keg" ] },
+            { "description": "Available to Drink", "id": "ready", "order": 1,
+              "defaultDuration": "1d", "resourcesNeeded": [ "keg" ], "carryOver": [ "keg" ] }
+        ],
+        "recipes": [
+            { "name": "Test Recipe", "color": "#FFFFFF", "batchSize": "10g",
+              "phases": [ { "template": "carbonation" },
+                          { "template": "ready", "minGap": "1d" } ] }
+        ]
+    }"##);
+
+    let utilization = Objective::Utilization.evaluate(&ps);
+    assert!((2.0 / 3.0 - utilization).abs() < 0.01, "got {}", utilization);
+}
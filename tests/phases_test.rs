@@ -1,6 +1,6 @@
 use chrono::Duration;
 
-use chronogrog::phases::{PhaseInstance, ProductionPhaseTemplate};
+use chronogrog::phases::{DependencyType, PhaseDependencySpec, PhaseInstance, ProductionPhaseTemplate};
 use chronogrog::resources::ResourceType;
 use chronogrog::util::get_naive_date_time_from_string;
 
@@ -111,3 +111,29 @@ fn it_should_output_standard_pla_for_a_phaseinstance_with_two_dependencies() {
     assert_eq!("[0] A phase instance\n  start 2020-01-01\n  color #FFFFFF\n  duration 240\n    dep 2\n    dep 3\n\n",
                phase_instance.get_string_in_pla_format(0));
 }
+
+#[test]
+fn it_should_compute_the_earliest_start_date_for_each_dependency_type() {
+    let predecessor_start = get_naive_date_time_from_string("2020-01-01").unwrap();
+    let ten_days = Duration::days(10);
+    let two_days = Duration::days(2);
+    let lag = Duration::hours(6);
+
+    assert_eq!(predecessor_start + ten_days + lag,
+               DependencyType::FinishToStart.get_earliest_start_date(predecessor_start, ten_days,
+                                                                     two_days, lag));
+    assert_eq!(predecessor_start + lag,
+               DependencyType::StartToStart.get_earliest_start_date(predecessor_start, ten_days,
+                                                                    two_days, lag));
+    assert_eq!(predecessor_start + Duration::days(8) + lag,
+               DependencyType::FinishToFinish.get_earliest_start_date(predecessor_start, ten_days,
+                                                                      two_days, lag));
+}
+
+#[test]
+fn it_should_deserialize_a_phase_dependency_with_defaults() {
+    let result: PhaseDependencySpec = serde_json::from_str(r#"{ "phase": "primary" }"#).unwrap();
+
+    assert_eq!(DependencyType::FinishToStart, result.dependency_type);
+    assert_eq!(Some(Duration::zero()), result.lag());
+}
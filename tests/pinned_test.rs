@@ -0,0 +1,75 @@
+use chrono::{Duration, NaiveDate};
+
+use chronogrog::{Error, ProductionSchedule};
+
+mod common;
+use common::get_schedule_json;
+
+/// A recipe whose brew day is booked in advance, after a recipe that keeps the kettle busy.
+fn get_pinned_phase_json(start: &str, busy_duration: &str) -> String {
+    let resources = r#"{ "id": 1, "name": "Kettle", "type": "kettle", "capacity": "5g" },
+                       { "id": 2, "name": "FV-001", "type": "fermentor", "capacity": "5g" }"#;
+    let templates = r#"{ "description": "Cleaning", "id": "cleaning", "order": 0,
+                         "defaultDuration": "1d", "resourcesNeeded": [ "kettle" ] },
+                       { "description": "Brewing", "id": "brewing", "order": 1,
+                         "defaultDuration": "6h", "resourcesNeeded": [ "kettle" ] },
+                       { "description": "Primary", "id": "primary", "order": 2,
+                         "defaultDuration": "1w", "resourcesNeeded": [ "fermentor" ] }"#;
+    let recipes = format!(r##"{{ "name": "Busy Brew", "color": "#000000",
+                                 "phases": [ {{ "template": "cleaning", "duration": "{}" }} ] }},
+                              {{ "name": "Collaboration Brew", "color": "#FFFFFF",
+                                 "phases": [ {{ "template": "cleaning" }},
+                                             {{ "template": "brewing", "start": "{}" }},
+                                             {{ "template": "primary" }} ] }}"##,
+                          busy_duration, start);
+
+    get_schedule_json("", resources, templates, &recipes[..])
+}
+
+#[test]
+fn it_should_schedule_phases_backwards_and_forwards_from_a_pinned_phase() {
+    let json = get_pinned_phase_json("2020-01-10 08:00:00", "2d");
+    let ps = ProductionSchedule::try_new(&json[..]).unwrap();
+    let recipe = ps.get_recipe_by_name("Collaboration Brew").unwrap();
+
+    let pinned_start = NaiveDate::from_ymd_opt(2020, 1, 10).unwrap().and_hms_opt(8, 0, 0).unwrap();
+    assert_eq!(pinned_start, recipe.phases[1].start_date);
+
+    // Cleaning ends right before the brew day, as it shares the kettle with brewing.
+    let cleaning_end = recipe.phases[0].start_date + recipe.phases[0].duration;
+    assert!(cleaning_end < pinned_start);
+    assert!(cleaning_end >= pinned_start - Duration::seconds(1));
+    assert_eq!(recipe.phases[0].start_date, recipe.start_date);
+
+    assert_eq!(pinned_start + Duration::hours(6), recipe.phases[2].start_date);
+
+    // Each instance of a repeated recipe has its own brew day.
+    let json = json.replace(r##""color": "#FFFFFF","##,
+                            r##""color": "#FFFFFF", "repeat": { "every": "weekly", "count": 2 },"##);
+    let ps = ProductionSchedule::try_new(&json[..]).unwrap();
+    let second = ps.get_recipe_by_name("Collaboration Brew #2").unwrap();
+    assert_eq!(pinned_start + Duration::weeks(1), second.phases[1].start_date);
+}
+
+#[test]
+fn it_should_explain_why_a_pinned_phase_cannot_start_at_its_date() {
+    let json = get_pinned_phase_json("2020-01-10 08:00:00", "15d");
+    match ProductionSchedule::try_new(&json[..]) {
+        Err(e @ Error::PinnedPhaseUnavailable { .. }) => {
+            assert_eq!(Some("recipes[\"Collaboration Brew\"].phases[1]"), e.path());
+            assert_eq!("unable to start at 2020-01-10 08:00, as every resource of type Kettle is \
+                        taken or out of service then", e.message());
+        },
+        _ => panic!("Expected a PinnedPhaseUnavailable error")
+    }
+
+    let json = get_pinned_phase_json("2020-01-01 06:00:00", "1h");
+    match ProductionSchedule::try_new(&json[..]) {
+        Err(e @ Error::PinnedPhaseUnreachable { .. }) => {
+            assert_eq!(Some("recipes[\"Collaboration Brew\"].phases[1]"), e.path());
+            assert_eq!("unable to start at 2020-01-01 06:00, as the phases before it cannot end by \
+                        then, even if the recipe starts at 2020-01-01 00:00", e.message());
+        },
+        _ => panic!("Expected a PinnedPhaseUnreachable error")
+    }
+}
@@ -0,0 +1,132 @@
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+
+use chronogrog::{Error, ProductionSchedule};
+
+mod common;
+use common::{get_schedule_json, FV_001, PRIMARY};
+
+/// A repeated recipe, and a party after the release of its second instance.
+fn get_repeat_json(repeat: &str) -> String {
+    let templates = format!(r#"{}, {{ "description": "Release", "id": "release", "order": 1,
+                                      "defaultDuration": "1d" }}"#, PRIMARY);
+    let recipes = format!(r##"{{ "name": "House IPA", "color": "#FFFFFF", "dueDate": "2020-01-12",
+                                 "repeat": {},
+                                 "phases": [ {{ "template": "primary" }},
+                                             {{ "template": "release" }} ] }},
+                              {{ "name": "Taproom Party", "color": "#000000",
+                                 "phases": [ {{ "template": "release",
+                                               "after": [ {{ "recipe": "House IPA #2",
+                                                             "phase": "release" }} ] }} ] }}"##,
+                          repeat);
+
+    get_schedule_json("", FV_001, &templates[..], &recipes[..])
+}
+
+#[test]
+fn it_should_expand_a_repeated_recipe_into_numbered_instances() {
+    let json = get_repeat_json(r#"{ "every": "3w", "count": 3 }"#);
+    let ps = ProductionSchedule::try_new(&json[..]).unwrap();
+
+    let names: Vec<&str> = ps.get_recipe_iterator().map(|x| &x.name[..]).collect();
+    assert_eq!(vec!["House IPA #1", "House IPA #2", "House IPA #3", "Taproom Party"], names);
+
+    let ids: Vec<usize> = ps.get_recipe_iterator().map(|x| x.id).collect();
+    assert_eq!(vec![1, 4, 7, 10], ids);
+
+    let jan_1 = NaiveDate::from_ymd_opt(2020, 1, 1).unwrap().and_hms_opt(0, 0, 0).unwrap();
+    let third = ps.get_recipe_by_name("House IPA #3").unwrap();
+    assert_eq!(jan_1 + Duration::weeks(6), third.phases[0].start_date);
+    assert_eq!(Some(NaiveDate::from_ymd_opt(2020, 2, 23).unwrap().and_hms_opt(0, 0, 0).unwrap()),
+               third.due_date);
+    assert!(ps.get_late_recipes().is_empty());
+
+    // Other recipes refer to an instance by its number.
+    let party = ps.get_recipe_by_name("Taproom Party").unwrap();
+    let second = ps.get_recipe_by_name("House IPA #2").unwrap();
+    assert_eq!(second.end_date(), Some(party.phases[0].start_date));
+    assert_eq!(vec![11], second.phases[1].dependencies);
+}
+
+#[test]
+fn it_should_repeat_a_recipe_until_a_date() {
+    let json = get_repeat_json(r#"{ "every": "monthly", "until": "2020-04-15" }"#);
+    let ps = ProductionSchedule::try_new(&json[..]).unwrap();
+
+    let starts: Vec<NaiveDateTime> = ps.get_recipe_iterator()
+                                       .filter(|x| x.name.starts_with("House IPA"))
+                                       .map(|x| x.phases[0].start_date)
+                                       .collect();
+    let expected: Vec<NaiveDateTime> = (1..5).map(|month| {
+        NaiveDate::from_ymd_opt(2020, month, 1).unwrap().and_hms_opt(0, 0, 0).unwrap()
+    }).collect();
+    assert_eq!(expected, starts);
+}
+
+#[test]
+fn it_should_report_a_repeat_without_an_end() {
+    let json = get_repeat_json(r#"{ "every": "3w" }"#);
+
+    match ProductionSchedule::try_new(&json[..]) {
+        Err(Error::InvalidRepeat { path, .. }) => {
+            assert_eq!("recipes[\"House IPA\"].repeat", path);
+        },
+        _ => panic!("Expected an invalid repeat error")
+    }
+}
+
+#[test]
+fn it_should_report_dependencies_on_repeated_recipes_without_a_matching_instance() {
+    let json = get_repeat_json(r#"{ "every": "3w", "count": 2 }"#);
+    let dependencies = [
+        ("House IPA", "\"House IPA\" is repeated; depend on one of its instances, e.g. \
+                       \"House IPA #1\""),
+        ("House IPA #7", "unknown recipe \"House IPA #7\"")
+    ];
+
+    for (recipe, message) in dependencies.iter() {
+        let json: String = json.replace("House IPA #2", recipe);
+
+        match ProductionSchedule::try_new(&json[..]) {
+            Err(e) => {
+                assert_eq!(Some("recipes[\"Taproom Party\"].phases[0].after[0].recipe"), e.path());
+                assert_eq!(*message, e.message());
+            },
+            _ => panic!("Expected an error for a dependency on {}", recipe)
+        }
+    }
+
+    // Each instance of a recipe repeated three times needs an instance of House IPA to follow.
+    let repeat: &str = r##""color": "#000000", "repeat": { "every": "3w", "count": 3 },"##;
+    let json: String = json.replace(r##""color": "#000000","##, repeat)
+                           .replace("House IPA #2", "House IPA");
+
+    match ProductionSchedule::try_new(&json[..]) {
+        Err(Error::UnknownRecipe { path, recipe }) => {
+            assert_eq!("recipes[\"Taproom Party\"].phases[0].after[0].recipe", path);
+            assert_eq!("House IPA #3", recipe);
+        },
+        _ => panic!("Expected an unknown recipe error")
+    }
+}
+
+#[test]
+fn it_should_report_a_repeat_that_recurs_too_often_or_too_far() {
+    let repeats = [
+        (r#"{ "every": "3w", "count": 1000000 }"#,
+         "invalid repeat: it recurs more than 10000 times"),
+        (r#"{ "every": "1h", "until": "2100-01-01" }"#,
+         "invalid repeat: it recurs more than 10000 times before its \"until\" date"),
+        (r#"{ "every": "120000mo", "count": 30 }"#,
+         "invalid repeat: it recurs beyond the latest date that can be represented")
+    ];
+
+    for (repeat, message) in repeats.iter() {
+        match ProductionSchedule::try_new(&get_repeat_json(repeat)[..]) {
+            Err(e @ Error::InvalidRepeat { .. }) => {
+                assert_eq!(Some("recipes[\"House IPA\"].repeat"), e.path());
+                assert_eq!(*message, e.message());
+            },
+            _ => panic!("Expected an invalid repeat error for {}", repeat)
+        }
+    }
+}
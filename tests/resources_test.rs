@@ -1,3 +1,6 @@
+// The tests written against the original chrono constructors keep using them.
+#![allow(deprecated)]
+
 extern crate chrono;
 use chrono::Duration;
 use chrono::{NaiveDate, NaiveTime, NaiveDateTime};
@@ -8,6 +11,9 @@ use chrono_period::NaivePeriod;
 use chronogrog::resources::Resource;
 use chronogrog::resources::ResourceTracker;
 use chronogrog::resources::ResourceType;
+use chronogrog::resources::StaffRequirement;
+use chronogrog::timezone::TimeZone;
+use chronogrog::volume::{Volume, VolumeUnit};
 
 extern crate serde_test;
 use serde_test::{Token, assert_tokens};
@@ -54,6 +60,12 @@ fn test_ser_de_resourcetype() {
         Token::Str("kegerator")
     ]);
 
+    let staff = ResourceType::Staff;
+
+    assert_tokens(&staff, &[
+        Token::Str("staff")
+    ]);
+
     let other = ResourceType::Other("fancythingy".to_string());
 
     assert_tokens(&other, &[
@@ -333,3 +345,342 @@ fn test_long_term_allocation() {
     assert!(first_available_date.is_some());
     assert_eq!(NaiveDate::from_ymd(2020, 10, 15).and_hms(4, 0, 1), first_available_date.unwrap());
 }
+
+#[test]
+fn test_resource_capacity_only_applies_to_vessels() {
+    let fermentor = Resource::new(1, "FV-001", ResourceType::Fermentor, "5g");
+    let gas_tank = Resource::new(2, "Scott's CO2 Tank", ResourceType::GasTank, "5g");
+    let kegerator = Resource::new(3, "Scott's Kegerator", ResourceType::Kegerator, "2k");
+
+    let ten_gallons = Volume::new(10.0, VolumeUnit::Gallons);
+
+    assert_eq!(Some(Volume::new(5.0, VolumeUnit::Gallons)), fermentor.capacity());
+    assert!(!fermentor.can_hold_batch(Some(&ten_gallons)));
+    assert!(fermentor.can_hold_batch(None));
+    assert!(gas_tank.can_hold_batch(Some(&ten_gallons)));
+    assert!(kegerator.can_hold_batch(Some(&ten_gallons)));
+}
+
+#[test]
+fn test_allocate_resource_of_type_with_capacity_skips_undersized_resources() {
+    let mut tracker = ResourceTracker::new();
+    tracker.track_resource(Resource::new(1, "FV-001", ResourceType::Fermentor, "5g"));
+    tracker.track_resource(Resource::new(2, "Big Bertha", ResourceType::Fermentor, "14g"));
+
+    let ten_gallons = Volume::new(10.0, VolumeUnit::Gallons);
+    let desired_start = NaiveDateTime::parse_from_str("2020-01-07 00:00:00",
+                                                      "%Y-%m-%d %H:%M:%S").unwrap();
+    let period = NaivePeriod::from_start_duration(desired_start, Duration::days(5));
+
+    let allocated = tracker.allocate_resource_of_type_for_period_with_capacity(&ResourceType::Fermentor,
+                                                                               period,
+                                                                               Some(&ten_gallons));
+    assert_eq!(2, allocated.unwrap().id);
+
+    // FV-001 is still free, but is too small for the batch.
+    assert!(tracker.is_resource_of_type_free_for_period(&ResourceType::Fermentor, period));
+    assert!(!tracker.is_resource_of_type_free_for_period_with_capacity(&ResourceType::Fermentor,
+                                                                       period,
+                                                                       Some(&ten_gallons)));
+
+    let fifteen_gallons = Volume::new(15.0, VolumeUnit::Gallons);
+    assert!(tracker.has_resource_of_type(&ResourceType::Fermentor));
+    assert!(!tracker.has_resource_of_type_with_capacity(&ResourceType::Fermentor,
+                                                        Some(&fifteen_gallons)));
+    assert!(tracker.get_next_available_resource_date_for_type_over_period_with_capacity(
+        &ResourceType::Fermentor, period, Some(&fifteen_gallons)).is_none());
+}
+
+#[test]
+fn test_allocate_resources_of_type_splits_a_batch_that_no_single_resource_can_hold() {
+    let mut tracker = ResourceTracker::new();
+    tracker.track_resource(Resource::new(6, "Keg 001", ResourceType::Keg, "5g"));
+    tracker.track_resource(Resource::new(7, "Keg 002", ResourceType::Keg, "5g"));
+    tracker.track_resource(Resource::new(8, "Keg 003", ResourceType::Keg, "5g"));
+
+    let ten_gallons = Volume::new(10.0, VolumeUnit::Gallons);
+    let start = NaiveDate::from_ymd_opt(2020, 4, 8).unwrap().and_hms_opt(4, 0, 0).unwrap();
+    let period = NaivePeriod::from_start_duration(start, Duration::days(10));
+
+    assert!(tracker.can_hold_batch_with_resources_of_type(&ResourceType::Keg, Some(&ten_gallons)));
+    assert!(!tracker.can_hold_batch_with_resources_of_type(&ResourceType::Keg,
+                                                           Some(&Volume::new(20.0, VolumeUnit::Gallons))));
+
+    let allocated = tracker.allocate_resources_of_type_for_period(&ResourceType::Keg, period,
+                                                                  Some(&ten_gallons)).unwrap();
+    let ids: Vec<usize> = allocated.iter().map(|res| res.id).collect();
+    assert_eq!(vec![6, 7], ids);
+
+    // Only Keg 003 is left, so a second batch has to wait until the first batch is done.
+    assert_eq!(Some(start + Duration::days(10) + Duration::seconds(1)),
+               tracker.get_next_available_date_for_resources_of_type_over_period(&ResourceType::Keg,
+                                                                                 period,
+                                                                                 Some(&ten_gallons)));
+    assert!(tracker.allocate_resources_of_type_for_period(&ResourceType::Keg, period,
+                                                          Some(&ten_gallons)).is_none());
+
+    // A batch that fits within a single keg is not split.
+    let five_gallons = Volume::new(5.0, VolumeUnit::Gallons);
+    let allocated = tracker.allocate_resources_of_type_for_period(&ResourceType::Keg, period,
+                                                                  Some(&five_gallons)).unwrap();
+    assert_eq!(1, allocated.len());
+    assert_eq!(8, allocated[0].id);
+}
+
+#[test]
+fn test_resources_of_type_that_cannot_hold_a_batch_when_combined_are_never_available() {
+    let mut tracker = ResourceTracker::new();
+    tracker.track_resource(Resource::new(6, "Keg 001", ResourceType::Keg, "5g"));
+
+    let start = NaiveDate::from_ymd_opt(2020, 4, 8).unwrap().and_hms_opt(4, 0, 0).unwrap();
+    let period = NaivePeriod::from_start_duration(start, Duration::days(10));
+    let ten_gallons = Volume::new(10.0, VolumeUnit::Gallons);
+
+    assert!(tracker.get_next_available_date_for_resources_of_type_over_period(&ResourceType::Keg,
+                                                                              period,
+                                                                              Some(&ten_gallons))
+                   .is_none());
+}
+
+#[test]
+fn test_extend_allocation_of_resource() {
+    let mut tracker = ResourceTracker::new();
+    tracker.track_resource(Resource::new(6, "Keg 001", ResourceType::Keg, "5g"));
+
+    let start = NaiveDate::from_ymd_opt(2020, 4, 8).unwrap().and_hms_opt(4, 0, 0).unwrap();
+    let period = NaivePeriod::from_start_duration(start, Duration::days(10));
+    tracker.allocate_resource_of_type_for_period(&ResourceType::Keg, period).unwrap();
+
+    let later_start = start + Duration::days(20);
+    let later_period = NaivePeriod::from_start_duration(later_start, Duration::days(10));
+    tracker.allocate_resource_of_type_for_period(&ResourceType::Keg, later_period).unwrap();
+
+    // Extending into free time, or within the existing allocation, succeeds.
+    assert!(tracker.extend_allocation_of_resource(6, start, start + Duration::days(15)).is_some());
+    assert!(tracker.extend_allocation_of_resource(6, start, start + Duration::days(5)).is_some());
+    assert!(!tracker.is_resource_of_type_free_for_period(&ResourceType::Keg,
+        NaivePeriod::from_start_duration(start + Duration::days(12), Duration::days(1))));
+
+    // Extending into the later allocation, or extending an allocation that doesn't exist, fails.
+    assert!(tracker.extend_allocation_of_resource(6, start, start + Duration::days(25)).is_none());
+    assert!(tracker.extend_allocation_of_resource(6, later_start + Duration::days(1),
+                                                  later_start + Duration::days(15)).is_none());
+    assert!(tracker.extend_allocation_of_resource(7, start, start + Duration::days(15)).is_none());
+}
+
+#[test]
+fn test_turnaround_is_kept_free_after_each_allocation() {
+    let mut fermentor = Resource::new(1, "FV-001", ResourceType::Fermentor, "5g");
+    fermentor.turnaround = Duration::days(1);
+
+    let start = NaiveDate::from_ymd_opt(2020, 4, 8).unwrap().and_hms_opt(4, 0, 0).unwrap();
+    fermentor.allocate_over_start_duration(start, Duration::days(10)).unwrap();
+
+    // The fermentor is busy until the turnaround after the allocation is done...
+    let first_end = start + Duration::days(10);
+    assert!(fermentor.is_allocated_over_start_duration(first_end + Duration::hours(12),
+                                                       Duration::days(1)));
+    assert_eq!(first_end + Duration::days(1) + Duration::seconds(1),
+               fermentor.get_earliest_free_date_for_period(
+                   NaivePeriod::from_start_duration(first_end, Duration::days(1))));
+
+    // ... and an allocation before it must leave time for its own turnaround.
+    assert!(fermentor.is_allocated_over_start_duration(start - Duration::days(2),
+                                                       Duration::days(1) + Duration::hours(12)));
+    assert!(!fermentor.is_allocated_over_start_duration(start - Duration::days(3),
+                                                        Duration::days(1)));
+
+    assert_eq!(vec![NaivePeriod::from_start_duration(first_end, Duration::days(1))],
+               fermentor.get_turnaround_periods());
+}
+
+#[test]
+fn test_resource_is_not_allocated_during_downtime() {
+    let mut tracker = ResourceTracker::new();
+    let mut fermentor = Resource::new(1, "FV-001", ResourceType::Fermentor, "5g");
+
+    let start = NaiveDate::from_ymd_opt(2020, 3, 1).unwrap().and_hms_opt(0, 0, 0).unwrap();
+    fermentor.add_downtime("gasket replacement", NaivePeriod::from_start_duration(start, Duration::days(9)));
+    assert_eq!(1, fermentor.downtime_periods.len());
+    tracker.track_resource(fermentor);
+
+    let period = NaivePeriod::from_start_duration(start - Duration::days(1), Duration::days(2));
+    assert!(!tracker.is_resource_of_type_free_for_period(&ResourceType::Fermentor, period));
+    assert_eq!(Some(start + Duration::days(9) + Duration::seconds(1)),
+               tracker.get_next_available_resource_date_for_type_over_period(&ResourceType::Fermentor,
+                                                                              period));
+}
+
+#[test]
+fn test_end_of_allocations_blocking_extension() {
+    let mut keg = Resource::new(6, "Keg 001", ResourceType::Keg, "5g");
+    keg.turnaround = Duration::hours(2);
+
+    let start = NaiveDate::from_ymd_opt(2020, 4, 8).unwrap().and_hms_opt(4, 0, 0).unwrap();
+    keg.allocate_over_start_duration(start, Duration::days(10)).unwrap();
+    keg.allocate_over_start_duration(start + Duration::days(20), Duration::days(5)).unwrap();
+
+    assert_eq!(None, keg.get_end_of_allocations_blocking_extension(start, start + Duration::days(15)));
+    assert_eq!(Some(start + Duration::days(25) + Duration::hours(2)),
+               keg.get_end_of_allocations_blocking_extension(start, start + Duration::days(21)));
+}
+
+#[test]
+fn test_working_periods_are_summarized_as_the_resource_is_allocated() {
+    let mut keg = Resource::new(6, "Keg 001", ResourceType::Keg, "5g");
+
+    let start = NaiveDate::from_ymd_opt(2020, 4, 8).unwrap().and_hms_opt(4, 0, 0).unwrap();
+    keg.add_downtime("leak test", NaivePeriod::from_start_duration(start, Duration::days(1)));
+    keg.allocate_over_start_duration(start + Duration::days(20), Duration::days(5)).unwrap();
+    keg.allocate_over_start_duration(start + Duration::days(2), Duration::days(10)).unwrap();
+    keg.extend_allocation(start + Duration::days(2), start + Duration::days(15)).unwrap();
+    keg.extend_allocation(start, start + Duration::hours(36)).unwrap();
+
+    // Downtime is not working time, even after it is extended.
+    assert_eq!(Duration::days(18), keg.get_working_duration());
+    assert_eq!(None, keg.get_last_working_period_before(start + Duration::days(1)));
+    assert_eq!(Some(NaivePeriod::new(start + Duration::days(2), start + Duration::days(15))),
+               keg.get_last_working_period_before(start + Duration::days(19)));
+    assert_eq!(Some(NaivePeriod::from_start_duration(start + Duration::days(20),
+                                                     Duration::days(5))),
+               keg.get_last_working_period_before(start + Duration::days(20)));
+}
+
+#[test]
+fn test_staff_are_only_allocated_within_their_maximum_working_hours() {
+    let mut brewer = Resource::new(1, "Anna", ResourceType::Staff, "");
+    brewer.max_hours_per_day = Some(8.0);
+    brewer.max_hours_per_week = Some(12.0);
+
+    // 2020-01-06 is a Monday.
+    let monday = NaiveDate::from_ymd_opt(2020, 1, 6).unwrap().and_hms_opt(8, 0, 0).unwrap();
+    brewer.allocate_over_start_duration(monday, Duration::hours(6)).unwrap();
+
+    let monday_evening = vec![NaivePeriod::from_start_duration(monday + Duration::hours(8),
+                                                               Duration::hours(3))];
+    let tuesday = vec![NaivePeriod::from_start_duration(monday + Duration::days(1),
+                                                        Duration::hours(6))];
+    let tuesday_and_wednesday = vec![tuesday[0],
+                                     NaivePeriod::from_start_duration(monday + Duration::days(2),
+                                                                      Duration::hours(1))];
+    assert!(!brewer.is_free_for_periods(&monday_evening));
+    assert!(brewer.is_free_for_periods(&tuesday));
+    assert!(!brewer.is_free_for_periods(&tuesday_and_wednesday));
+
+    assert_eq!(vec![(monday - Duration::hours(8), 6.0)], brewer.get_hours_worked_per_week());
+}
+
+#[test]
+fn test_staff_working_hours_are_limited_per_day_and_week_of_their_local_time() {
+    let mut brewer = Resource::new(1, "Anna", ResourceType::Staff, "");
+    brewer.max_hours_per_day = Some(8.0);
+    brewer.max_hours_per_week = Some(12.0);
+    brewer.time_zone = Some(TimeZone::from_name("MST7MDT,M3.2.0,M11.1.0").unwrap());
+
+    // A shift from 18:00 to 23:00 on Sunday, 2020-01-05, in Denver, which is after midnight UTC.
+    let sunday_evening = NaiveDate::from_ymd_opt(2020, 1, 6).unwrap()
+                                   .and_hms_opt(1, 0, 0).unwrap();
+    brewer.allocate_over_start_duration(sunday_evening, Duration::hours(5)).unwrap();
+
+    // The shift counts against Sunday, so Monday still has its full eight hours, and the week that
+    // starts on Monday still has all twelve.
+    let monday = vec![NaivePeriod::from_start_duration(sunday_evening + Duration::hours(14),
+                                                       Duration::hours(8))];
+    assert!(brewer.is_free_for_periods(&monday));
+
+    // The week of the shift starts at midnight on Monday, 2019-12-30, in Denver.
+    let week_start = NaiveDate::from_ymd_opt(2019, 12, 30).unwrap().and_hms_opt(7, 0, 0).unwrap();
+    assert_eq!(vec![(week_start, 5.0)], brewer.get_hours_worked_per_week());
+}
+
+#[test]
+fn test_allocate_staff_fills_requirements_for_rare_skills_first() {
+    let mut tracker = ResourceTracker::new();
+    let mut anna = Resource::new(1, "Anna", ResourceType::Staff, "");
+    anna.skills = vec!["brewing".to_string(), "canning".to_string()];
+    let mut ben = Resource::new(2, "Ben", ResourceType::Staff, "");
+    ben.skills = vec!["brewing".to_string()];
+    tracker.track_resource(anna);
+    tracker.track_resource(ben);
+
+    let requirements: Vec<StaffRequirement> = serde_json::from_str(
+        r#"[ { "skill": "brewing" }, { "skill": "canning" } ]"#).unwrap();
+    let start = NaiveDate::from_ymd_opt(2020, 1, 6).unwrap().and_hms_opt(8, 0, 0).unwrap();
+    let periods = vec![NaivePeriod::from_start_duration(start, Duration::hours(4))];
+
+    assert_eq!(1, tracker.count_staff_with_skill(Some("canning")));
+    assert_eq!(2, tracker.count_staff_with_skill(None));
+
+    let allocated = tracker.allocate_staff_for_periods(&requirements, &periods).unwrap();
+    assert_eq!(vec![1, 2], allocated.iter().map(|res| res.id).collect::<Vec<usize>>());
+
+    assert_eq!(None, tracker.allocate_staff_for_periods(&requirements, &periods));
+    assert_eq!(start + Duration::hours(4) + Duration::seconds(1),
+               tracker.get_next_staff_change_date(start));
+}
+
+#[test]
+fn test_substitutes_are_only_allocated_when_no_exact_match_is_free() {
+    let mut tracker = ResourceTracker::new();
+    let unitank = ResourceType::from("unitank");
+    tracker.track_resource(Resource::new(1, "UT-001", unitank.clone(), "5g"));
+    tracker.track_resource(Resource::new(2, "FV-002", ResourceType::Fermentor, "5g"));
+    tracker.add_substitute(unitank.clone(), ResourceType::Fermentor);
+
+    let start = NaiveDate::from_ymd_opt(2020, 1, 6).unwrap().and_hms_opt(0, 0, 0).unwrap();
+    let period = NaivePeriod::from_start_duration(start, Duration::days(7));
+
+    assert!(tracker.has_resource_of_type(&unitank));
+    assert!(!tracker.has_resource_of_type(&ResourceType::from("brighttank")));
+
+    let allocated = tracker.allocate_resource_of_type_for_period(&ResourceType::Fermentor, period);
+    assert_eq!(2, allocated.unwrap().id);
+    let allocated = tracker.allocate_resource_of_type_for_period(&ResourceType::Fermentor, period);
+    assert_eq!(1, allocated.unwrap().id);
+    assert!(tracker.allocate_resource_of_type_for_period(&unitank, period).is_none());
+}
+
+#[test]
+fn test_find_a_resource_type_that_a_single_substitute_cannot_fill_twice() {
+    let mut tracker = ResourceTracker::new();
+    let combi = ResourceType::from("combi");
+    tracker.track_resource(Resource::new(1, "Combi", combi.clone(), "5g"));
+    tracker.add_substitute(combi.clone(), ResourceType::MashTun);
+    tracker.add_substitute(combi, ResourceType::LauterTun);
+
+    let needed = [&ResourceType::MashTun, &ResourceType::LauterTun];
+    assert!(tracker.has_substitutes());
+    assert_eq!(None, tracker.find_unfillable_resource_type(&needed[..1], None, &[]));
+    assert_eq!(Some(ResourceType::LauterTun),
+               tracker.find_unfillable_resource_type(&needed, None, &[]));
+
+    tracker.track_resource(Resource::new(2, "LT-002", ResourceType::LauterTun, "5g"));
+    assert_eq!(None, tracker.find_unfillable_resource_type(&needed, None, &[]));
+    assert_eq!(Some(ResourceType::MashTun),
+               tracker.find_unfillable_resource_type(&needed, None, &[1]));
+}
+
+#[test]
+fn test_a_resource_of_several_types_fills_all_of_them_at_once() {
+    let resource: Resource = serde_json::from_str(
+        r#"{ "id": 1, "name": "All-in-One", "type": "kettle",
+             "additionalTypes": ["mashtun", "lautertun"], "capacity": "5g" }"#).unwrap();
+    assert!(resource.is_of_type(&ResourceType::Kettle));
+    assert!(resource.is_of_type(&ResourceType::LauterTun));
+    assert!(!resource.is_of_type(&ResourceType::Fermentor));
+
+    let mut tracker = ResourceTracker::new();
+    tracker.track_resource(resource);
+    tracker.track_resource(Resource::new(2, "MT-002", ResourceType::MashTun, "5g"));
+
+    let needed = [&ResourceType::MashTun, &ResourceType::LauterTun, &ResourceType::Kettle];
+    assert_eq!(None, tracker.find_unfillable_resource_type(&needed, None, &[]));
+    assert_eq!(Some(ResourceType::LauterTun),
+               tracker.find_unfillable_resource_type(&needed, None, &[1]));
+
+    let start = NaiveDate::from_ymd_opt(2020, 1, 6).unwrap().and_hms_opt(0, 0, 0).unwrap();
+    let period = NaivePeriod::from_start_duration(start, Duration::hours(6));
+    let allocated = tracker.allocate_resource_of_type_for_period(&ResourceType::MashTun, period);
+    assert_eq!(1, allocated.unwrap().id);
+    assert!(!tracker.is_resource_of_type_free_for_period(&ResourceType::Kettle, period));
+}
@@ -0,0 +1,77 @@
+use chrono::{Duration, NaiveDate};
+
+use chronogrog::{Error, ProductionSchedule};
+
+mod common;
+use common::get_schedule_json;
+
+/// Two recipes that each need to be canned by the only brewer who is certified on the canning
+/// line.
+fn get_staff_json(canner: &str) -> String {
+    let resources = format!(r#"{{ "id": 1, "name": "Anna", "type": "staff",
+                                  "skills": [ "brewing", "canning" ] {} }},
+                               {{ "id": 2, "name": "Ben", "type": "staff",
+                                  "skills": [ "brewing" ] }}"#, canner);
+    let templates = r#"{ "description": "Canning", "id": "canning", "order": 0,
+                         "defaultDuration": "4h",
+                         "staffNeeded": [ { "skill": "canning" }, { "count": 1 } ] }"#;
+    let recipes = r##"{ "name": "Session Ale", "color": "#FFFFFF",
+                        "phases": [ { "template": "canning" } ] },
+                      { "name": "Festival Lager", "color": "#000000",
+                        "phases": [ { "template": "canning" } ] }"##;
+
+    get_schedule_json("", &resources[..], templates, recipes)
+}
+
+#[test]
+fn it_should_allocate_staff_with_the_skills_a_phase_needs() {
+    let ps = ProductionSchedule::try_new(&get_staff_json("")[..]).unwrap();
+
+    let session_ale = ps.get_recipe_by_name("Session Ale").unwrap();
+    let names: Vec<&str> = session_ale.phases[0].resources_used.iter()
+                                      .map(|res| &res.name[..])
+                                      .collect();
+    assert_eq!(vec!["Anna", "Ben"], names);
+
+    // Only Anna can can, so the second recipe waits for her.
+    let jan_1 = NaiveDate::from_ymd_opt(2020, 1, 1).unwrap().and_hms_opt(0, 0, 0).unwrap();
+    assert_eq!(jan_1 + Duration::hours(4) + Duration::seconds(1),
+               ps.get_recipe_by_name("Festival Lager").unwrap().phases[0].start_date);
+
+    let pla = ps.get_string_in_pla_format();
+    assert!(pla.contains("res Anna"));
+    assert!(pla.contains("res Ben"));
+}
+
+#[test]
+fn it_should_not_allocate_staff_beyond_their_maximum_working_hours() {
+    let ps = ProductionSchedule::try_new(&get_staff_json(r#", "maxHoursPerDay": 6"#)[..]).unwrap();
+
+    let jan_2 = NaiveDate::from_ymd_opt(2020, 1, 2).unwrap().and_hms_opt(0, 0, 0).unwrap();
+    assert_eq!(jan_2, ps.get_recipe_by_name("Festival Lager").unwrap().phases[0].start_date);
+
+    // 2020-01-01 is a Wednesday, so both days fall in the week starting on 2019-12-30.
+    let report = ps.get_staff_load_report();
+    assert_eq!("Anna (brewing, canning)\n  week of 2019-12-30 00:00: 8.00 hours\n  total: 8.00 hours\n\
+                Ben (brewing)\n  week of 2019-12-30 00:00: 8.00 hours\n  total: 8.00 hours\n",
+               report);
+}
+
+#[test]
+fn it_should_report_phases_that_need_more_staff_than_are_defined() {
+    let json = get_staff_json("").replace(r#"{ "count": 1 }"#, r#"{ "count": 2 }"#);
+
+    match ProductionSchedule::try_new(&json[..]) {
+        Err(e @ Error::MissingStaff { .. }) => {
+            assert_eq!(Some("recipes[\"Session Ale\"].phases[0]"), e.path());
+            assert_eq!("3 staff are needed, but only 2 are defined", e.message());
+        },
+        _ => panic!("Expected a MissingStaff error")
+    }
+
+    let json = get_staff_json(r#", "maxHoursPerDay": 3"#);
+    match ProductionSchedule::try_new(&json[..]) {
+        Err(Error::InsufficientStaff { .. }) => {},
+        _ => panic!("Expected an InsufficientStaff error")
+    }
+}
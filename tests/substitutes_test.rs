@@ -0,0 +1,95 @@
+use chrono::Duration;
+
+use chronogrog::{Error, ProductionSchedule};
+use chronogrog::phases::PhaseInstance;
+
+mod common;
+use common::{get_resource_names_used, get_schedule_json};
+
+/// Two recipes that brew and ferment at the same time, with a combi vessel that serves as either
+/// a mash tun or a lauter tun, and a unitank that serves as either a fermentor or a bright tank.
+fn get_substitutes_json(resources: &str) -> String {
+    let settings = r#""resourceTypeAliases": { "brite": "brighttank", "fv": "fermentor" },
+                      "substitutes": { "combi": ["mashtun", "lautertun"],
+                                       "unitank": ["fv", "brite"] },"#;
+    let resources = format!(r#"{}
+                               {{ "id": 2, "name": "UT-001", "type": "unitank", "capacity": "5g" }},
+                               {{ "id": 3, "name": "FV-001", "type": "fv", "capacity": "5g" }},
+                               {{ "id": 4, "name": "Combi", "type": "combi", "capacity": "5g" }}"#,
+                            resources);
+    let templates = r#"{ "description": "Brew", "id": "brew", "order": 0, "defaultDuration": "1d",
+                         "resourcesNeeded": [ "mashtun", "lautertun" ] },
+                       { "description": "Primary", "id": "primary", "order": 1,
+                         "defaultDuration": "1w", "resourcesNeeded": [ "fermentor" ] }"#;
+    let recipes = r##"{ "name": "First Lager", "color": "#000000",
+                        "phases": [ { "template": "brew" }, { "template": "primary" } ] },
+                      { "name": "Second Lager", "color": "#FFFFFF",
+                        "phases": [ { "template": "brew" }, { "template": "primary" } ] }"##;
+
+    get_schedule_json(settings, &resources[..], templates, recipes)
+}
+
+#[test]
+fn it_should_use_substitutes_only_when_no_exact_match_is_free() {
+    let json = get_substitutes_json(r#"{ "id": 1, "name": "LT-001", "type": "lautertun" },"#);
+    let ps = ProductionSchedule::try_new(&json[..]).unwrap();
+
+    let first = ps.get_recipe_by_name("First Lager").unwrap();
+    let second = ps.get_recipe_by_name("Second Lager").unwrap();
+    let names = |phase: &PhaseInstance| -> Vec<String> {
+        phase.resources_used.iter().map(|res| res.name.clone()).collect()
+    };
+    assert_eq!(vec!["Combi", "LT-001"], names(&first.phases[0]));
+    assert_eq!(vec!["FV-001"], names(&first.phases[1]));
+
+    // The combi vessel only fills one need of each brew, so the second brew waits for both.
+    assert_eq!(vec!["Combi", "LT-001"], names(&second.phases[0]));
+    assert_eq!(first.phases[0].start_date + Duration::days(1) + Duration::seconds(1),
+               second.phases[0].start_date);
+    assert_eq!(vec!["UT-001"], names(&second.phases[1]));
+}
+
+#[test]
+fn it_should_report_needs_that_a_single_substitute_cannot_fill_together() {
+    match ProductionSchedule::try_new(&get_substitutes_json("")[..]) {
+        Err(e @ Error::Allocation { .. }) => {
+            assert_eq!(Some("recipes[\"First Lager\"].phases[0]"), e.path());
+        },
+        _ => panic!("Expected an Allocation error")
+    }
+}
+
+#[test]
+fn it_should_list_a_resource_that_fills_several_needs_of_a_phase_once() {
+    let json = r##"{
+        "name": "All-in-One Schedule",
+        "id": 1,
+        "timeline": { "configuration": "calendar", "start": "2020-01-01" },
+        "resources": [
+            { "id": 1, "name": "All-in-One", "type": "kettle",
+              "additionalTypes": ["mashtun", "lautertun"], "capacity": "5g" }
+        ],
+        "phaseTemplates": [
+            { "description": "Brew", "id": "brew", "order": 0, "defaultDuration": "6h",
+              "resourcesNeeded": [ "mashtun", "lautertun", "kettle" ] }
+        ],
+        "recipes": [
+            { "name": "First Pale Ale", "color": "#000000", "phases": [ { "template": "brew" } ] },
+            { "name": "Second Pale Ale", "color": "#FFFFFF", "phases": [ { "template": "brew" } ] }
+        ]
+    }"##;
+    let ps = ProductionSchedule::try_new(json).unwrap();
+
+    let first = ps.get_recipe_by_name("First Pale Ale").unwrap();
+    let second = ps.get_recipe_by_name("Second Pale Ale").unwrap();
+    assert_eq!(vec!["All-in-One".to_string()], get_resource_names_used(&ps, "First Pale Ale"));
+    assert_eq!(vec!["All-in-One".to_string()], get_resource_names_used(&ps, "Second Pale Ale"));
+    assert_eq!(first.phases[0].start_date + Duration::hours(6) + Duration::seconds(1),
+               second.phases[0].start_date);
+
+    // It's also taken before substitutes, which would only fill a single need each.
+    let json = get_substitutes_json(r#"{ "id": 1, "name": "Grainfather", "type": "mashtun",
+                                         "additionalTypes": ["lautertun"] },"#);
+    let ps = ProductionSchedule::try_new(&json[..]).unwrap();
+    assert_eq!(vec!["Grainfather".to_string()], get_resource_names_used(&ps, "First Lager"));
+}
@@ -0,0 +1,45 @@
+use chrono::NaiveDate;
+
+use chronogrog::{Error, ProductionSchedule};
+
+mod common;
+use common::{get_schedule_json, FV_001, PRIMARY};
+
+/// Two recipes that each need the only fermentor, the second of which is due on 2020-01-15, on a
+/// timeline with the given configuration.
+fn get_timeline_json(configuration: &str) -> String {
+    let recipes = r##"{ "name": "Session Ale", "color": "#FFFFFF", "priority": 1,
+                        "phases": [ { "template": "primary" } ] },
+                      { "name": "Festival Lager", "color": "#000000", "dueDate": "2020-01-15",
+                        "phases": [ { "template": "primary" } ] }"##;
+
+    get_schedule_json("", FV_001, PRIMARY, recipes)
+      .replace("\"calendar\"", &format!("\"{}\"", configuration)[..])
+}
+
+#[test]
+fn it_should_express_dates_as_days_since_the_start_of_a_relative_timeline() {
+    let json = get_timeline_json("relative");
+    let ps = ProductionSchedule::try_new(&json[..]).unwrap();
+
+    let pla = ps.get_string_in_pla_format();
+    assert!(pla.contains("  [2] Primary\n    start 0\n"));
+    assert!(pla.contains("  [4] Primary\n    start 10 00\n"));
+    assert!(pla.contains("[3] Festival Lager (late: due day 14 00:00)\n"));
+    assert_eq!("day 10 00:00",
+               ps.get_date_string(NaiveDate::from_ymd_opt(2020, 1, 11).unwrap()
+                                    .and_hms_opt(0, 0, 1).unwrap()));
+}
+
+#[test]
+fn it_should_report_an_unknown_timeline_configuration_with_its_path() {
+    let json = get_timeline_json("lunar");
+
+    match ProductionSchedule::try_new(&json[..]) {
+        Err(Error::UnknownTimelineConfiguration { path, configuration }) => {
+            assert_eq!("timeline.configuration", path);
+            assert_eq!("lunar", configuration);
+        },
+        _ => panic!("Expected an unknown timeline configuration error")
+    }
+}
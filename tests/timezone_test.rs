@@ -0,0 +1,172 @@
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+
+use chronogrog::{Error, ProductionSchedule};
+use chronogrog::timezone::TimeZone;
+
+mod common;
+use common::get_schedule_json;
+
+fn get_date(month: u32, day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(2020, month, day).unwrap().and_hms_opt(hour, minute, 0).unwrap()
+}
+
+#[test]
+fn it_should_support_utc_and_fixed_offsets() {
+    let utc = TimeZone::from_name("UTC").unwrap();
+    assert_eq!(get_date(3, 8, 12, 0), utc.to_utc(get_date(3, 8, 12, 0)));
+
+    let india = TimeZone::from_name("+05:30").unwrap();
+    assert_eq!(get_date(3, 8, 6, 30), india.to_utc(get_date(3, 8, 12, 0)));
+    assert_eq!(get_date(3, 8, 12, 0), india.to_local(get_date(3, 8, 6, 30)));
+    assert_eq!(19800, india.get_offset_at(get_date(3, 8, 6, 30)).local_minus_utc());
+}
+
+#[test]
+fn it_should_move_the_clock_for_daylight_saving_time() {
+    // Daylight saving time starts on the second Sunday of March, and ends on the first Sunday of
+    // November, at 02:00 local time.
+    let mountain = TimeZone::from_name("MST7MDT,M3.2.0,M11.1.0").unwrap();
+
+    assert_eq!(get_date(3, 8, 8, 0), mountain.to_utc(get_date(3, 8, 1, 0)));
+    assert_eq!(get_date(3, 8, 9, 0), mountain.to_utc(get_date(3, 8, 3, 0)));
+    assert_eq!(get_date(7, 1, 6, 0), mountain.to_local(get_date(7, 1, 12, 0)));
+
+    // 02:30 never happens on the day the clock is set forward.
+    assert_eq!(get_date(3, 8, 9, 30), mountain.to_utc(get_date(3, 8, 2, 30)));
+
+    // 01:30 happens twice on the day the clock is set back; the first one is used.
+    assert_eq!(get_date(11, 1, 7, 30), mountain.to_utc(get_date(11, 1, 1, 30)));
+    assert_eq!(get_date(11, 1, 1, 30), mountain.to_local(get_date(11, 1, 8, 30)));
+}
+
+#[test]
+fn it_should_support_daylight_saving_time_across_the_turn_of_the_year() {
+    let sydney = TimeZone::from_name("AEST-10AEDT,M10.1.0,M4.1.0/3").unwrap();
+
+    assert_eq!(39600, sydney.get_offset_at(get_date(1, 15, 0, 0)).local_minus_utc());
+    assert_eq!(36000, sydney.get_offset_at(get_date(7, 15, 0, 0)).local_minus_utc());
+    assert_eq!(39600, sydney.get_offset_at(get_date(12, 15, 0, 0)).local_minus_utc());
+}
+
+#[test]
+fn it_should_follow_the_past_rules_of_zones_in_the_time_zone_database() {
+    let denver = TimeZone::from_name("America/Denver").unwrap();
+
+    // Until 2007, daylight saving time started on the first Sunday of April, rather than on the
+    // second Sunday of March.
+    let march_20_2006 = NaiveDate::from_ymd_opt(2006, 3, 20).unwrap()
+                                  .and_hms_opt(12, 0, 0).unwrap();
+    assert_eq!(-25200, denver.get_offset_at(march_20_2006).local_minus_utc());
+    assert_eq!(-21600, denver.get_offset_at(get_date(3, 20, 12, 0)).local_minus_utc());
+
+    assert_eq!(get_date(3, 8, 9, 30), denver.to_utc(get_date(3, 8, 2, 30)));
+    assert_eq!(get_date(11, 1, 7, 30), denver.to_utc(get_date(11, 1, 1, 30)));
+    assert_eq!(get_date(7, 1, 6, 0), denver.to_local(get_date(7, 1, 12, 0)));
+}
+
+#[test]
+fn it_should_reject_offsets_of_a_day_or_more() {
+    assert!(TimeZone::from_name("ABC100").is_err());
+    assert!(TimeZone::from_name("ABC-24").is_err());
+    assert!(TimeZone::from_name("ABC-23:59DEF,M3.2.0,M11.1.0").is_err());
+    assert!(TimeZone::from_name("ABC23DEF-30,M3.2.0,M11.1.0").is_err());
+    assert_eq!(-82800, TimeZone::from_name("ABC23").unwrap()
+                                 .get_offset_at(get_date(1, 1, 0, 0)).local_minus_utc());
+}
+
+#[test]
+fn it_should_reject_unknown_time_zones() {
+    assert!(TimeZone::from_name("Nowhere/Atlantis").is_err());
+    assert!(TimeZone::from_name("../../etc/passwd").is_err());
+    assert!(TimeZone::from_name("MST7MDT,M13.2.0,M11.1.0").is_err());
+    assert!(TimeZone::from_name("").is_err());
+}
+
+/// A brew day that spans the start of daylight saving time, followed by fermentation.
+fn get_time_zone_json(time_zone: &str, sites: &str, site: &str) -> String {
+    let resources = r#"{ "id": 1, "name": "Kettle", "type": "kettle", "capacity": "5g" },
+                       { "id": 2, "name": "FV-001", "type": "fermentor", "capacity": "5g" }"#;
+    let templates = r#"{ "description": "Brewing", "id": "brewing", "order": 0,
+                         "defaultDuration": "6h", "resourcesNeeded": [ "kettle" ] },
+                       { "description": "Primary", "id": "primary", "order": 1,
+                         "defaultDuration": "1w", "resourcesNeeded": [ "fermentor" ] }"#;
+    let recipe = format!(r##"{{ "name": "Test Recipe", "color": "#FFFFFF" {},
+                                "phases": [ {{ "template": "brewing" }},
+                                            {{ "template": "primary" }} ] }}"##, site);
+    let sites = format!(r#""sites": [ {} ],"#, sites);
+    let timeline_start = format!(r#""start": "2020-03-08" {} }}"#, time_zone);
+
+    get_schedule_json(&sites[..], resources, templates, &recipe[..])
+      .replace(r#""start": "2020-01-01" }"#, &timeline_start[..])
+}
+
+#[test]
+fn it_should_schedule_phases_in_local_time_across_daylight_saving_time() {
+    // The clock is set forward from 02:00 to 03:00 on 2020-03-08, so six hours of brewing from
+    // midnight end at 07:00 local time.
+    let json = get_time_zone_json(r#", "timezone": "MST7MDT,M3.2.0,M11.1.0""#, "", "");
+    let ps = ProductionSchedule::try_new(&json[..]).unwrap();
+    let recipe = ps.get_recipe_by_name("Test Recipe").unwrap();
+
+    let midnight = NaiveDate::from_ymd_opt(2020, 3, 8).unwrap().and_hms_opt(0, 0, 0).unwrap();
+    assert_eq!(midnight + Duration::hours(7), recipe.phases[0].start_date);
+    assert_eq!(midnight, ps.get_local_date(recipe.phases[0].start_date));
+    assert_eq!(midnight + Duration::hours(7),
+               ps.get_local_date(recipe.phases[1].start_date));
+    assert_eq!("2020-03-08 07:00", ps.get_date_string(recipe.phases[1].start_date));
+
+    let offset_start = ps.get_date_time_with_offset(recipe.phases[1].start_date).unwrap();
+    assert_eq!("2020-03-08T07:00:00-06:00", offset_start.to_rfc3339());
+
+    assert!(ps.get_string_in_pla_format().contains("start 2020-03-08 07"));
+
+    // Without a time zone, every day lasts 24 hours.
+    let naive_ps = ProductionSchedule::try_new(&get_time_zone_json("", "", "")[..]).unwrap();
+    let naive_recipe = naive_ps.get_recipe_by_name("Test Recipe").unwrap();
+    assert_eq!(midnight + Duration::hours(6), naive_recipe.phases[1].start_date);
+    assert_eq!(None, naive_ps.get_date_time_with_offset(naive_recipe.phases[1].start_date));
+}
+
+#[test]
+fn it_should_interpret_the_dates_of_a_recipe_in_the_time_zone_of_its_site() {
+    let json = get_time_zone_json(r#", "timezone": "EST5EDT,M3.2.0,M11.1.0""#,
+                                  r#"{ "name": "Denver", "timezone": "MST7MDT,M3.2.0,M11.1.0" }"#,
+                                  r#", "site": "Denver", "start": "2020-01-06 08:00:00""#);
+    let ps = ProductionSchedule::try_new(&json[..]).unwrap();
+    let recipe = ps.get_recipe_by_name("Test Recipe").unwrap();
+
+    // 08:00 in Denver is 10:00 on the timeline.
+    assert_eq!("2020-01-06 10:00", ps.get_date_string(recipe.phases[0].start_date));
+
+    let json = get_time_zone_json("", r#"{ "name": "Denver", "timezone": "Mountain/Denver" }"#,
+                                  "");
+    match ProductionSchedule::try_new(&json[..]) {
+        Err(e @ Error::InvalidTimeZone { .. }) => {
+            assert_eq!(Some("sites[0].timezone"), e.path());
+        },
+        _ => panic!("Expected an InvalidTimeZone error")
+    }
+
+    let json = get_time_zone_json("", "", r#", "site": "Boulder""#);
+    match ProductionSchedule::try_new(&json[..]) {
+        Err(e @ Error::UnknownSite { .. }) => {
+            assert_eq!(Some("recipes[\"Test Recipe\"].site"), e.path());
+        },
+        _ => panic!("Expected an UnknownSite error")
+    }
+}
+
+#[test]
+fn it_should_report_an_unreachable_ready_by_date_in_the_time_zone_of_its_site() {
+    let json = get_time_zone_json(r#", "timezone": "EST5EDT,M3.2.0,M11.1.0""#,
+                                  r#"{ "name": "Denver", "timezone": "MST7MDT,M3.2.0,M11.1.0" }"#,
+                                  r#", "site": "Denver", "readyBy": "2020-03-10 12:00:00""#);
+
+    match ProductionSchedule::try_new(&json[..]) {
+        Err(e @ Error::ReadyByUnreachable { .. }) => {
+            assert_eq!("unable to be ready by 2020-03-10 12:00; starting at 2020-03-07 22:00, the \
+                        recipe can be ready by 2020-03-15 05:00 at the earliest", e.message());
+        },
+        _ => panic!("Expected an unreachable ready-by date error")
+    }
+}
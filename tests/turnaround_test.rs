@@ -0,0 +1,51 @@
+use chrono::Duration;
+
+use chronogrog::{Error, ProductionSchedule};
+
+mod common;
+use common::{get_schedule_json, FV_001, PRIMARY};
+
+/// Two recipes that each need the only fermentor.
+fn get_turnaround_json(fermentor_turnaround: &str) -> String {
+    let turnarounds = format!(r#""turnarounds": {{ "fermentor": "{}" }},"#, fermentor_turnaround);
+    let recipes = r##"{ "name": "First Recipe", "color": "#FFFFFF",
+                        "phases": [ { "template": "primary" } ] },
+                      { "name": "Second Recipe", "color": "#000000",
+                        "phases": [ { "template": "primary" } ] }"##;
+
+    get_schedule_json(&turnarounds[..], FV_001, PRIMARY, recipes)
+}
+
+#[test]
+fn it_should_wait_for_the_turnaround_of_a_resource_before_reusing_it() {
+    let ps = ProductionSchedule::try_new(&get_turnaround_json("1d")[..]).unwrap();
+
+    let first = ps.get_recipe_by_name("First Recipe").unwrap();
+    let second = ps.get_recipe_by_name("Second Recipe").unwrap();
+    assert_eq!(first.phases[0].start_date + Duration::days(11) + Duration::seconds(1),
+               second.phases[0].start_date);
+}
+
+#[test]
+fn it_should_report_an_unparseable_turnaround_with_its_path() {
+    let result = ProductionSchedule::try_new(&get_turnaround_json("soon")[..]);
+
+    match result {
+        Err(Error::DurationParse { path, value, .. }) => {
+            assert_eq!("turnarounds[\"fermentor\"]", path);
+            assert_eq!("soon", value);
+        },
+        _ => panic!("Expected a duration parse error")
+    }
+}
+
+#[test]
+fn it_should_show_turnarounds_as_tasks_in_pla_output_when_requested() {
+    let mut ps = ProductionSchedule::try_new(&get_turnaround_json("1d")[..]).unwrap();
+    assert!(!ps.get_string_in_pla_format().contains("Turnaround"));
+
+    ps.show_turnarounds = true;
+    let pla = ps.get_string_in_pla_format();
+    assert!(pla.contains("[5] Turnaround\n  child 6\n  child 7\n"));
+    assert!(pla.contains("  [6] Turnaround: FV-001\n    start 2020-01-11\n    color #9E9E9E\n    duration 24\n    res FV-001\n"));
+}
@@ -0,0 +1,83 @@
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+
+use chronogrog::util::{convert_string_to_calendar_duration, convert_string_to_duration,
+                       CalendarDuration};
+
+#[test]
+fn it_should_convert_single_durations_in_every_unit() {
+    assert_eq!(Ok(Duration::days(10)), convert_string_to_duration("10"));
+    assert_eq!(Ok(Duration::days(10)), convert_string_to_duration("10d"));
+    assert_eq!(Ok(Duration::weeks(3)), convert_string_to_duration("3w"));
+    assert_eq!(Ok(Duration::hours(6)), convert_string_to_duration("6h"));
+    assert_eq!(Ok(Duration::minutes(30)), convert_string_to_duration("30min"));
+    assert_eq!(Ok(Duration::seconds(45)), convert_string_to_duration("45s"));
+    assert_eq!(Ok(Duration::days(180)), convert_string_to_duration("6m"));
+    assert_eq!(Ok(Duration::weeks(2)), convert_string_to_duration("2 weeks"));
+    assert_eq!(Ok(Duration::hours(-2)), convert_string_to_duration("-2h"));
+}
+
+#[test]
+fn it_should_convert_compound_and_fractional_durations() {
+    assert_eq!(Ok(Duration::weeks(1) + Duration::days(3) + Duration::hours(4)),
+               convert_string_to_duration("1w 3d 4h"));
+    assert_eq!(Ok(Duration::hours(36)), convert_string_to_duration("1.5d"));
+    assert_eq!(Ok(Duration::minutes(90)), convert_string_to_duration("1h30min"));
+    assert_eq!(Ok(Duration::minutes(-90)), convert_string_to_duration("-1h 30min"));
+}
+
+#[test]
+fn it_should_convert_iso_8601_durations() {
+    assert_eq!(Ok(CalendarDuration::new(0, Duration::days(9) + Duration::hours(3))),
+               convert_string_to_calendar_duration("P1W2DT3H"));
+    assert_eq!(Ok(CalendarDuration::new(0, Duration::minutes(90))),
+               convert_string_to_calendar_duration("PT90M"));
+    assert_eq!(Ok(CalendarDuration::new(14, Duration::zero())),
+               convert_string_to_calendar_duration("P1Y2M"));
+    assert_eq!(Ok(CalendarDuration::new(0, Duration::hours(12))),
+               convert_string_to_calendar_duration("P0.5D"));
+}
+
+#[test]
+fn it_should_count_calendar_months_from_the_date_they_start() {
+    let two_months = convert_string_to_calendar_duration("2mo").unwrap();
+    assert_eq!(CalendarDuration::new(2, Duration::zero()), two_months);
+
+    let january = NaiveDate::from_ymd_opt(2021, 1, 1).unwrap().and_hms_opt(0, 0, 0).unwrap();
+    let july = NaiveDate::from_ymd_opt(2021, 7, 1).unwrap().and_hms_opt(0, 0, 0).unwrap();
+    assert_eq!(Some(Duration::days(59)), two_months.get_duration_from(january));
+    assert_eq!(Some(Duration::days(62)), two_months.get_duration_from(july));
+    assert_eq!(Duration::days(60), two_months.approximate());
+
+    // A month from the end of January ends at the end of February.
+    let end_of_january = NaiveDate::from_ymd_opt(2020, 1, 31).unwrap().and_hms_opt(0, 0, 0)
+                                                                   .unwrap();
+    let duration = convert_string_to_calendar_duration("1 month 12h").unwrap();
+    assert_eq!(Some(Duration::days(29) + Duration::hours(12)),
+               duration.get_duration_from(end_of_january));
+
+    // Nothing can end beyond the latest date that can be represented.
+    assert_eq!(None, two_months.get_duration_from(NaiveDateTime::MAX - Duration::days(30)));
+}
+
+#[test]
+fn it_should_describe_why_a_duration_cannot_be_parsed() {
+    let reasons = [
+        ("", "it is empty"),
+        ("10x", "unknown unit \"x\"; expected e.g. \"mo\", \"w\", \"d\", \"h\" or \"min\""),
+        ("1w 3", "3 has no unit; expected e.g. \"mo\", \"w\", \"d\", \"h\" or \"min\""),
+        ("h", "expected a number before \"h\""),
+        ("1..5d", "\"1..5\" is not a number"),
+        ("1.5mo", "calendar months and years must be whole numbers, but got 1.5"),
+        ("3d!", "unexpected \"!\""),
+        ("P", "the ISO 8601 duration has no parts"),
+        ("P1DT", "nothing follows \"T\" in the ISO 8601 duration"),
+        ("P1D2W", "unexpected \"W\" in the ISO 8601 duration"),
+        ("PT5", "5 has no unit in the ISO 8601 duration"),
+        ("99999999999w", "it is too long"),
+        ("100000000mo", "it is too long")
+    ];
+
+    for (value, reason) in reasons.iter() {
+        assert_eq!(Err(reason.to_string()), convert_string_to_duration(value), "{}", value);
+    }
+}
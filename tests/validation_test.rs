@@ -0,0 +1,647 @@
+use chronogrog::util::get_json_data_from_file;
+use chronogrog::validation::{validate, Severity};
+
+#[test]
+fn it_should_report_no_issues_for_the_simple_production_schedule() {
+    let report = validate(&get_json_data_from_file("tests/fixtures/simple_prod_schedule.json").unwrap()[..]);
+
+    assert!(report.issues.is_empty());
+    assert!(!report.has_errors());
+}
+
+#[test]
+fn it_should_report_a_single_issue_for_an_unparseable_json_file() {
+    let report = validate(&get_json_data_from_file("tests/fixtures/bad_production_schedule.json").unwrap()[..]);
+
+    assert_eq!(1, report.issues.len());
+    assert_eq!(Severity::Error, report.issues[0].severity);
+    assert_eq!("", report.issues[0].path);
+}
+
+#[test]
+fn it_should_report_every_problem_in_a_bpd_file() {
+    let json = r##"{
+        "name": "Broken Production Schedule",
+        "id": 1,
+        "timeline": { "configuration": "calendar", "start": "2020-01-0x" },
+        "resources": [
+            { "id": 1, "name": "FV-001", "type": "fermentor", "capacity": "5g" },
+            { "id": 1, "name": "Large Kettle", "type": "kettle", "capacity": "15g" }
+        ],
+        "phaseTemplates": [
+            { "description": "Primary", "id": "primary", "order": 0, "defaultDuration": "10x",
+              "resourcesNeeded": [ "fermentor", "keg" ] },
+            { "description": "Primary Again", "id": "primary", "order": 1 }
+        ],
+        "recipes": [
+            { "name": "Test Recipe", "color": "#FFFFFF", "start": "someday",
+              "phases": [
+                  { "template": "primary", "duration": "abc" },
+                  { "template": "tertiary" }
+              ] }
+        ]
+    }"##;
+
+    let report = validate(json);
+    let paths: Vec<&str> = report.issues.iter().map(|x| &x.path[..]).collect();
+
+    assert_eq!(vec![
+        "timeline.start",
+        "resources[1].id",
+        "phaseTemplates[\"primary\"].defaultDuration",
+        "phaseTemplates[\"primary\"].resourcesNeeded[1]",
+        "phaseTemplates[1].id",
+        "recipes[\"Test Recipe\"].start",
+        "recipes[\"Test Recipe\"].phases[0].duration",
+        "recipes[\"Test Recipe\"].phases[1].template"
+    ], paths);
+
+    assert_eq!(8, report.error_count());
+    assert_eq!(0, report.warning_count());
+}
+
+#[test]
+fn it_should_report_phase_durations_of_more_than_ten_thousand_years() {
+    let json = r##"{
+        "name": "Long Production Schedule",
+        "id": 1,
+        "timeline": { "configuration": "calendar", "start": "2020-01-01" },
+        "resources": [ { "id": 1, "name": "FV-001", "type": "fermentor", "capacity": "5g" } ],
+        "phaseTemplates": [
+            { "description": "Primary", "id": "primary", "order": 0, "defaultDuration": "10d",
+              "resourcesNeeded": [ "fermentor" ] }
+        ],
+        "recipes": [
+            { "name": "Test Recipe", "color": "#FFFFFF",
+              "phases": [ { "template": "primary", "duration": "100000000mo" } ] }
+        ]
+    }"##;
+
+    let report = validate(json);
+
+    assert_eq!(1, report.issues.len());
+    assert_eq!(Severity::Error, report.issues[0].severity);
+    assert_eq!("recipes[\"Test Recipe\"].phases[0].duration", report.issues[0].path);
+}
+
+#[test]
+fn it_should_report_unparseable_and_unused_turnarounds() {
+    let json = r##"{
+        "name": "Turnaround Production Schedule",
+        "id": 1,
+        "timeline": { "configuration": "calendar", "start": "2020-01-01" },
+        "resources": [
+            { "id": 1, "name": "FV-001", "type": "fermentor", "capacity": "5g", "turnaround": "1x" }
+        ],
+        "turnarounds": { "fermentor": "1d", "kettle": "2y" },
+        "phaseTemplates": [],
+        "recipes": []
+    }"##;
+
+    let report = validate(json);
+    let paths: Vec<(Severity, &str)> = report.issues.iter()
+                                             .map(|x| (x.severity, &x.path[..]))
+                                             .collect();
+
+    assert_eq!(vec![
+        (Severity::Error, "resources[0].turnaround"),
+        (Severity::Error, "turnarounds[\"kettle\"]"),
+        (Severity::Warning, "turnarounds[\"kettle\"]")
+    ], paths);
+}
+
+#[test]
+fn it_should_warn_about_downtime_during_a_pinned_phase() {
+    let json = r##"{
+        "name": "Downtime Production Schedule",
+        "id": 1,
+        "timeline": { "configuration": "calendar", "start": "2020-01-01" },
+        "resources": [
+            { "id": 1, "name": "FV-001", "type": "fermentor", "capacity": "5g",
+              "downtime": [ { "start": "2020-03-05", "end": "2020-03-10" },
+                            { "start": "2020-03-05", "end": "2020-03-01" } ] },
+            { "id": 2, "name": "FV-002", "type": "fermentor", "capacity": "5g",
+              "downtime": [ { "start": "2020-03-01", "every": "weekly", "count": 2 } ] }
+        ],
+        "phaseTemplates": [
+            { "description": "Primary", "id": "primary", "order": 0, "defaultDuration": "10d",
+              "resourcesNeeded": [ "fermentor" ] }
+        ],
+        "recipes": [
+            { "name": "Pinned Recipe", "color": "#FFFFFF",
+              "phases": [ { "template": "primary", "start": "2020-03-02" } ] },
+            { "name": "Unpinned Recipe", "color": "#FFFFFF", "start": "2020-03-02",
+              "phases": [ { "template": "primary" } ] }
+        ]
+    }"##;
+
+    let report = validate(json);
+    let paths: Vec<(Severity, &str)> = report.issues.iter()
+                                             .map(|x| (x.severity, &x.path[..]))
+                                             .collect();
+
+    assert_eq!(vec![
+        (Severity::Error, "resources[0].downtime[1]"),
+        (Severity::Warning, "recipes[\"Pinned Recipe\"].phases[0]")
+    ], paths);
+}
+
+#[test]
+fn it_should_warn_about_downtime_during_a_phase_pinned_within_a_recipe() {
+    let json = r##"{
+        "name": "Downtime Production Schedule",
+        "id": 1,
+        "timeline": { "configuration": "calendar", "start": "2020-01-01" },
+        "resources": [
+            { "id": 1, "name": "Kettle", "type": "kettle", "capacity": "5g",
+              "downtime": [ { "start": "2020-03-02", "end": "2020-03-03" } ] }
+        ],
+        "phaseTemplates": [
+            { "description": "Cleaning", "id": "cleaning", "order": 0, "defaultDuration": "1d",
+              "resourcesNeeded": [ "kettle" ] },
+            { "description": "Brewing", "id": "brewing", "order": 1, "defaultDuration": "6h",
+              "resourcesNeeded": [ "kettle" ] }
+        ],
+        "recipes": [
+            { "name": "Pinned Recipe", "color": "#FFFFFF", "start": "2020-03-02",
+              "phases": [ { "template": "cleaning" },
+                          { "template": "brewing", "start": "2020-03-02 08:00:00" } ] },
+            { "name": "Unparseable Recipe", "color": "#FFFFFF",
+              "phases": [ { "template": "brewing", "start": "soon" } ] }
+        ]
+    }"##;
+
+    let report = validate(json);
+    let paths: Vec<(Severity, &str)> = report.issues.iter()
+                                             .map(|x| (x.severity, &x.path[..]))
+                                             .collect();
+
+    // The start of a recipe doesn't pin its first phase.
+    assert_eq!(vec![
+        (Severity::Error, "recipes[\"Unparseable Recipe\"].phases[0].start"),
+        (Severity::Warning, "recipes[\"Pinned Recipe\"].phases[1]")
+    ], paths);
+}
+
+#[test]
+fn it_should_warn_about_downtime_of_substitutes_and_sites_in_other_time_zones() {
+    let json = r##"{
+        "name": "Downtime Production Schedule",
+        "id": 1,
+        "timeline": { "configuration": "calendar", "start": "2020-01-01", "timezone": "UTC" },
+        "sites": [ { "name": "Denver", "timezone": "America/Denver" } ],
+        "substitutes": { "unitank": ["fermentor"] },
+        "resources": [
+            { "id": 1, "name": "UT-001", "type": "unitank", "capacity": "5g",
+              "downtime": [ { "start": "2020-03-05", "end": "2020-03-10" } ] },
+            { "id": 2, "name": "Combi", "type": "kettle", "additionalTypes": ["mashtun"],
+              "capacity": "5g", "site": "Denver",
+              "downtime": [ { "start": "2020-03-02 00:00:00", "end": "2020-03-02 06:00:00" } ] }
+        ],
+        "phaseTemplates": [
+            { "description": "Primary", "id": "primary", "order": 0, "defaultDuration": "10d",
+              "resourcesNeeded": [ "fermentor" ] },
+            { "description": "Mashing", "id": "mashing", "order": 1, "defaultDuration": "1h",
+              "resourcesNeeded": [ "mashtun" ] }
+        ],
+        "recipes": [
+            { "name": "Substituted Recipe", "color": "#FFFFFF",
+              "phases": [ { "template": "primary", "start": "2020-03-06" } ] },
+            { "name": "Early Recipe", "color": "#FFFFFF",
+              "phases": [ { "template": "mashing", "start": "2020-03-02 03:00:00" } ] },
+            { "name": "Late Recipe", "color": "#FFFFFF",
+              "phases": [ { "template": "mashing", "start": "2020-03-02 10:00:00" } ] },
+            { "name": "Denver Recipe", "color": "#FFFFFF", "site": "Denver",
+              "phases": [ { "template": "mashing", "start": "2020-03-02 03:00:00" } ] }
+        ]
+    }"##;
+
+    let report = validate(json);
+    let paths: Vec<(Severity, &str)> = report.issues.iter()
+                                             .map(|x| (x.severity, &x.path[..]))
+                                             .collect();
+
+    // The downtime of Combi is from 07:00 to 13:00 UTC, during which only the mashing of the late
+    // recipe and of the recipe in Denver are pinned.
+    assert_eq!(vec![
+        (Severity::Warning, "recipes[\"Substituted Recipe\"].phases[0]"),
+        (Severity::Warning, "recipes[\"Late Recipe\"].phases[0]"),
+        (Severity::Warning, "recipes[\"Denver Recipe\"].phases[0]")
+    ], paths);
+}
+
+#[test]
+fn it_should_report_unknown_and_cyclic_phase_dependencies() {
+    let json = r##"{
+        "name": "Dependency Production Schedule",
+        "id": 1,
+        "timeline": { "configuration": "calendar", "start": "2020-01-01" },
+        "resources": [],
+        "phaseTemplates": [
+            { "description": "Planning", "id": "planning", "order": 0 },
+            { "description": "Cleaning", "id": "cleaning", "order": 1 }
+        ],
+        "recipes": [
+            { "name": "Unknown Recipe", "color": "#FFFFFF",
+              "phases": [ { "template": "planning", "after": [ { "phase": "brewing", "lag": "1x" } ] } ] },
+            { "name": "Cyclic Recipe", "color": "#FFFFFF",
+              "phases": [ { "template": "planning", "after": [ { "phase": "cleaning" } ] },
+                          { "template": "cleaning" } ] }
+        ]
+    }"##;
+
+    let report = validate(json);
+    let paths: Vec<&str> = report.issues.iter().map(|x| &x.path[..]).collect();
+
+    assert_eq!(vec![
+        "recipes[\"Unknown Recipe\"].phases[0].after[0].phase",
+        "recipes[\"Unknown Recipe\"].phases[0].after[0].lag",
+        "recipes[\"Cyclic Recipe\"]"
+    ], paths);
+    assert_eq!("phases depend on each other in a cycle: \"planning\" -> \"cleaning\" -> \"planning\"",
+               report.issues[2].message);
+}
+
+#[test]
+fn it_should_report_unknown_and_cyclic_recipe_dependencies() {
+    let json = r##"{
+        "name": "Cross Recipe Production Schedule",
+        "id": 1,
+        "timeline": { "configuration": "calendar", "start": "2020-01-01" },
+        "resources": [],
+        "phaseTemplates": [ { "description": "Planning", "id": "planning", "order": 0 } ],
+        "recipes": [
+            { "name": "First Recipe", "color": "#FFFFFF",
+              "phases": [ { "template": "planning",
+                            "after": [ { "recipe": "Second Recipe", "phase": "planning" } ] } ] },
+            { "name": "Second Recipe", "color": "#FFFFFF",
+              "phases": [ { "template": "planning",
+                            "after": [ { "recipe": "First Recipe", "phase": "planning" } ] } ] },
+            { "name": "Third Recipe", "color": "#FFFFFF",
+              "phases": [ { "template": "planning",
+                            "after": [ { "recipe": "Fourth Recipe", "phase": "planning" } ] } ] }
+        ]
+    }"##;
+
+    let report = validate(json);
+    let paths: Vec<&str> = report.issues.iter().map(|x| &x.path[..]).collect();
+
+    assert_eq!(vec!["recipes[\"Third Recipe\"].phases[0].after[0].recipe", "recipes"], paths);
+    assert_eq!("recipes depend on each other in a cycle: \"First Recipe\" -> \"Second Recipe\" -> \
+                \"First Recipe\"", report.issues[1].message);
+}
+
+#[test]
+fn it_should_report_unparseable_and_inconsistent_gaps() {
+    let json = r##"{
+        "name": "Gap Production Schedule",
+        "id": 1,
+        "timeline": { "configuration": "calendar", "start": "2020-01-01" },
+        "resources": [],
+        "phaseTemplates": [ { "description": "Planning", "id": "planning", "order": 0 } ],
+        "recipes": [
+            { "name": "Test Recipe", "color": "#FFFFFF",
+              "phases": [ { "template": "planning", "minGap": "soon" },
+                          { "template": "planning", "id": "replanning", "minGap": "2d",
+                            "maxGap": "1d" } ] }
+        ]
+    }"##;
+
+    let report = validate(json);
+    let paths: Vec<&str> = report.issues.iter().map(|x| &x.path[..]).collect();
+
+    assert_eq!(vec![
+        "recipes[\"Test Recipe\"].phases[0].minGap",
+        "recipes[\"Test Recipe\"].phases[1].maxGap"
+    ], paths);
+}
+
+#[test]
+fn it_should_report_unparseable_and_unreachable_due_and_ready_by_dates() {
+    let json = r##"{
+        "name": "Due Date Production Schedule",
+        "id": 1,
+        "timeline": { "configuration": "calendar", "start": "2020-01-01" },
+        "resources": [],
+        "phaseTemplates": [ { "description": "Planning", "id": "planning", "order": 0 } ],
+        "recipes": [
+            { "name": "First Recipe", "color": "#FFFFFF", "dueDate": "soon",
+              "phases": [ { "template": "planning" } ] },
+            { "name": "Second Recipe", "color": "#FFFFFF", "dueDate": "2019-12-31",
+              "phases": [ { "template": "planning" } ] },
+            { "name": "Third Recipe", "color": "#FFFFFF", "readyBy": "later",
+              "phases": [ { "template": "planning" } ] }
+        ]
+    }"##;
+
+    let report = validate(json);
+    let issues: Vec<(Severity, &str)> = report.issues.iter()
+                                              .map(|x| (x.severity, &x.path[..]))
+                                              .collect();
+
+    assert_eq!(vec![
+        (Severity::Error, "recipes[\"First Recipe\"].dueDate"),
+        (Severity::Warning, "recipes[\"Second Recipe\"].dueDate"),
+        (Severity::Error, "recipes[\"Third Recipe\"].readyBy")
+    ], issues);
+}
+
+#[test]
+fn it_should_report_invalid_repeats() {
+    let json = r##"{
+        "name": "Repeat Production Schedule",
+        "id": 1,
+        "timeline": { "configuration": "calendar", "start": "2020-01-01" },
+        "resources": [],
+        "phaseTemplates": [ { "description": "Planning", "id": "planning", "order": 0 } ],
+        "recipes": [
+            { "name": "House IPA", "color": "#FFFFFF", "repeat": { "every": "often", "count": 3 },
+              "phases": [ { "template": "planning" } ] },
+            { "name": "House Stout", "color": "#FFFFFF", "repeat": { "every": "2w" },
+              "phases": [ { "template": "planning" } ] },
+            { "name": "Tasting", "color": "#FFFFFF",
+              "phases": [ { "template": "planning",
+                            "after": [ { "recipe": "House Stout #2", "phase": "planning" } ] } ] }
+        ]
+    }"##;
+
+    let report = validate(json);
+    let paths: Vec<&str> = report.issues.iter().map(|x| &x.path[..]).collect();
+
+    assert_eq!(vec![
+        "recipes[\"House IPA\"].repeat.every",
+        "recipes[\"House Stout\"].repeat"
+    ], paths);
+}
+
+#[test]
+fn it_should_report_dependencies_on_repeated_recipes_without_a_matching_instance() {
+    let json = r##"{
+        "name": "Repeat Production Schedule",
+        "id": 1,
+        "timeline": { "configuration": "calendar", "start": "2020-01-01" },
+        "resources": [],
+        "phaseTemplates": [ { "description": "Brewing", "id": "brewing", "order": 0 } ],
+        "recipes": [
+            { "name": "House IPA", "color": "#FFFFFF", "repeat": { "every": "2w", "count": 2 },
+              "phases": [ { "template": "brewing" } ] },
+            { "name": "Blend", "color": "#FFFFFF",
+              "phases": [ { "template": "brewing",
+                            "after": [ { "recipe": "House IPA", "phase": "brewing" },
+                                       { "recipe": "House IPA #7", "phase": "brewing" },
+                                       { "recipe": "House IPA #2", "phase": "brewing" } ] } ] },
+            { "name": "House Stout", "color": "#FFFFFF", "repeat": { "every": "2w", "count": 3 },
+              "phases": [ { "template": "brewing",
+                            "after": [ { "recipe": "House IPA", "phase": "brewing" } ] } ] }
+        ]
+    }"##;
+
+    let report = validate(json);
+    let paths: Vec<&str> = report.issues.iter().map(|x| &x.path[..]).collect();
+
+    assert_eq!(vec![
+        "recipes[\"Blend\"].phases[0].after[0].recipe",
+        "recipes[\"Blend\"].phases[0].after[1].recipe",
+        "recipes[\"House Stout\"].phases[0].after[0].recipe"
+    ], paths);
+}
+
+#[test]
+fn it_should_report_missing_staff_and_invalid_working_hours() {
+    let json = r##"{
+        "name": "Staff Production Schedule",
+        "id": 1,
+        "timeline": { "configuration": "calendar", "start": "2020-01-01" },
+        "resources": [
+            { "id": 1, "name": "Anna", "type": "staff", "skills": [ "brewing" ], "maxHoursPerDay": 0 }
+        ],
+        "phaseTemplates": [
+            { "description": "Brewing", "id": "brewing", "order": 0,
+              "staffNeeded": [ { "skill": "brewing" } ] },
+            { "description": "Canning", "id": "canning", "order": 1,
+              "staffNeeded": [ { "skill": "canning" } ] }
+        ],
+        "recipes": []
+    }"##;
+
+    let report = validate(json);
+    let paths: Vec<&str> = report.issues.iter().map(|x| &x.path[..]).collect();
+
+    assert_eq!(vec![
+        "resources[0].maxHoursPerDay",
+        "phaseTemplates[\"canning\"].staffNeeded"
+    ], paths);
+}
+
+#[test]
+fn it_should_report_invalid_time_zones_and_unknown_sites() {
+    let json = r##"{
+        "name": "Time Zone Production Schedule",
+        "id": 1,
+        "timeline": { "configuration": "calendar", "start": "2020-01-01",
+                      "timezone": "Nowhere/Atlantis" },
+        "sites": [ { "name": "Denver", "timezone": "MST7MDT,M13.2.0,M11.1.0" } ],
+        "resources": [ { "id": 1, "name": "Kettle", "type": "kettle", "site": "Boulder" } ],
+        "phaseTemplates": [ { "description": "Planning", "id": "planning", "order": 0 } ],
+        "recipes": [
+            { "name": "House IPA", "color": "#FFFFFF", "site": "Boulder",
+              "phases": [ { "template": "planning" } ] }
+        ]
+    }"##;
+
+    let report = validate(json);
+    let paths: Vec<&str> = report.issues.iter().map(|x| &x.path[..]).collect();
+
+    assert_eq!(vec![
+        "timeline.timezone",
+        "sites[0].timezone",
+        "resources[0].site",
+        "recipes[\"House IPA\"].site"
+    ], paths);
+}
+
+#[test]
+fn it_should_report_unknown_and_unsuitable_assigned_resources() {
+    let json = r##"{
+        "name": "Assigned Resource Production Schedule",
+        "id": 1,
+        "timeline": { "configuration": "calendar", "start": "2020-01-01" },
+        "resources": [
+            { "id": 1, "name": "FV-001", "type": "fermentor", "capacity": "5g" },
+            { "id": 2, "name": "Big Bertha", "type": "fermentor", "capacity": "20g" }
+        ],
+        "phaseTemplates": [
+            { "description": "Primary", "id": "primary", "order": 0,
+              "resourcesNeeded": [ "fermentor" ] },
+            { "description": "Lagering", "id": "lagering", "order": 1, "carryOver": [ "fermentor" ] }
+        ],
+        "recipes": [
+            { "name": "Helles", "color": "#FFFFFF", "batchSize": "10g",
+              "phases": [ { "template": "primary", "resources": [ "Big Bertha", "Little Bertha" ],
+                            "preferredResources": [ 1, 9 ] },
+                          { "template": "lagering", "resources": [ 2 ] } ] }
+        ]
+    }"##;
+
+    let report = validate(json);
+    let paths: Vec<(Severity, &str)> = report.issues.iter()
+                                             .map(|x| (x.severity, &x.path[..]))
+                                             .collect();
+
+    assert_eq!(vec![
+        (Severity::Error, "recipes[\"Helles\"].phases[0].resources[1]"),
+        (Severity::Warning, "recipes[\"Helles\"].phases[0].preferredResources[0]"),
+        (Severity::Error, "recipes[\"Helles\"].phases[0].preferredResources[1]"),
+        (Severity::Warning, "recipes[\"Helles\"].phases[1].resources[0]")
+    ], paths);
+    assert_eq!("there is no resource \"Little Bertha\"", report.issues[0].message);
+}
+
+#[test]
+fn it_should_report_unknown_allocation_strategies() {
+    let json = r##"{
+        "name": "Allocation Strategy Production Schedule",
+        "id": 1,
+        "timeline": { "configuration": "calendar", "start": "2020-01-01" },
+        "allocationStrategy": "random",
+        "allocationStrategies": { "fermentor": "best-fit", "kettle": "least-recently-used" },
+        "resources": [ { "id": 1, "name": "FV-001", "type": "fermentor" } ],
+        "phaseTemplates": [],
+        "recipes": []
+    }"##;
+
+    let report = validate(json);
+    let paths: Vec<(Severity, &str)> = report.issues.iter()
+                                             .map(|x| (x.severity, &x.path[..]))
+                                             .collect();
+
+    assert_eq!(vec![
+        (Severity::Error, "allocationStrategy"),
+        (Severity::Warning, "allocationStrategies[\"kettle\"]")
+    ], paths);
+}
+
+#[test]
+fn it_should_count_substitutes_and_aliases_as_resources_of_the_types_they_stand_for() {
+    let json = r##"{
+        "name": "Substitutes Production Schedule",
+        "id": 1,
+        "timeline": { "configuration": "calendar", "start": "2020-01-01" },
+        "resourceTypeAliases": { "brite": "brighttank" },
+        "substitutes": { "unitank": ["fermentor", "brite"], "combi": ["lautertun"] },
+        "resources": [ { "id": 1, "name": "UT-001", "type": "unitank" } ],
+        "phaseTemplates": [
+            { "description": "Conditioning", "id": "conditioning", "order": 0,
+              "defaultDuration": "1w", "resourcesNeeded": [ "brighttank" ] },
+            { "description": "Lautering", "id": "lautering", "order": 1,
+              "defaultDuration": "1h", "resourcesNeeded": [ "lautertun" ] }
+        ],
+        "recipes": []
+    }"##;
+
+    let report = validate(json);
+    let paths: Vec<(Severity, &str)> = report.issues.iter()
+                                             .map(|x| (x.severity, &x.path[..]))
+                                             .collect();
+
+    assert_eq!(vec![
+        (Severity::Warning, "substitutes[\"combi\"]"),
+        (Severity::Error, "phaseTemplates[\"lautering\"].resourcesNeeded[0]")
+    ], paths);
+}
+
+#[test]
+fn it_should_count_resources_of_several_types_as_resources_of_each_of_them() {
+    let json = r##"{
+        "name": "All-in-One Production Schedule",
+        "id": 1,
+        "timeline": { "configuration": "calendar", "start": "2020-01-01" },
+        "resources": [
+            { "id": 1, "name": "All-in-One", "type": "kettle",
+              "additionalTypes": ["mashtun", "lautertun"] }
+        ],
+        "phaseTemplates": [
+            { "description": "Brew", "id": "brew", "order": 0, "defaultDuration": "6h",
+              "resourcesNeeded": [ "mashtun", "lautertun", "kettle", "fermentor" ] }
+        ],
+        "recipes": []
+    }"##;
+
+    let report = validate(json);
+    let paths: Vec<(Severity, &str)> = report.issues.iter()
+                                             .map(|x| (x.severity, &x.path[..]))
+                                             .collect();
+
+    assert_eq!(vec![(Severity::Error, "phaseTemplates[\"brew\"].resourcesNeeded[3]")], paths);
+}
+
+#[test]
+fn it_should_ignore_assigned_resources_carried_over_through_an_additional_type() {
+    let json = r##"{
+        "name": "Unitank Production Schedule",
+        "id": 1,
+        "timeline": { "configuration": "calendar", "start": "2020-01-01" },
+        "resources": [
+            { "id": 1, "name": "Unitank", "type": "fermentor", "capacity": "10g",
+              "additionalTypes": ["brite"] }
+        ],
+        "phaseTemplates": [
+            { "description": "Primary", "id": "primary", "order": 0,
+              "resourcesNeeded": [ "brite" ] },
+            { "description": "Conditioning", "id": "conditioning", "order": 1,
+              "carryOver": [ "brite" ] }
+        ],
+        "recipes": [
+            { "name": "Helles", "color": "#FFFFFF", "batchSize": "10g",
+              "phases": [ { "template": "primary" },
+                          { "template": "conditioning", "resources": [ "Unitank" ] } ] }
+        ]
+    }"##;
+
+    let report = validate(json);
+    let paths: Vec<(Severity, &str)> = report.issues.iter()
+                                             .map(|x| (x.severity, &x.path[..]))
+                                             .collect();
+
+    assert_eq!(vec![(Severity::Warning, "recipes[\"Helles\"].phases[1].resources[0]")], paths);
+    assert_eq!("resources of type Other(\"brite\") are carried over from the previous phase, \
+                so \"Unitank\" is ignored", report.issues[0].message);
+}
+
+#[test]
+fn it_should_check_the_capacity_and_existence_of_types_carried_over_by_a_phase() {
+    let json = r##"{
+        "name": "Carry Over Production Schedule",
+        "id": 1,
+        "timeline": { "configuration": "calendar", "start": "2020-01-01" },
+        "resources": [
+            { "id": 1, "name": "FV-001", "type": "fermentor", "capacity": "20g" },
+            { "id": 2, "name": "Keg 001", "type": "keg", "capacity": "5g" }
+        ],
+        "phaseTemplates": [
+            { "description": "Carbonation", "id": "carbonation", "order": 0,
+              "resourcesNeeded": [ "fermentor", "keg" ] },
+            { "description": "Ready", "id": "ready", "order": 1 }
+        ],
+        "recipes": [
+            { "name": "Helles", "color": "#FFFFFF", "batchSize": "10g",
+              "phases": [ { "template": "carbonation" },
+                          { "template": "ready", "carryOver": [ "keg", "brite" ] } ] }
+        ]
+    }"##;
+
+    let report = validate(json);
+    let paths: Vec<(Severity, &str)> = report.issues.iter()
+                                             .map(|x| (x.severity, &x.path[..]))
+                                             .collect();
+
+    assert_eq!(vec![
+        (Severity::Error, "recipes[\"Helles\"].phases[0]"),
+        (Severity::Error, "recipes[\"Helles\"].phases[1].carryOver[1]"),
+        (Severity::Error, "recipes[\"Helles\"].phases[1]"),
+        (Severity::Error, "recipes[\"Helles\"].phases[1]")
+    ], paths);
+    assert_eq!("no resources of type Other(\"brite\") are defined", report.issues[1].message);
+    assert_eq!("resources of type Keg cannot hold a batch of 10g, even when combined",
+               report.issues[3].message);
+}
@@ -0,0 +1,31 @@
+use chronogrog::volume::{convert_string_to_volume, Volume, VolumeUnit};
+
+#[test]
+fn it_should_convert_strings_to_volumes_in_all_supported_units() {
+    assert_eq!(Some(Volume::new(5.0, VolumeUnit::Gallons)), convert_string_to_volume("5g"));
+    assert_eq!(Some(Volume::new(5.0, VolumeUnit::Gallons)), convert_string_to_volume("5 gal"));
+    assert_eq!(Some(Volume::new(19.5, VolumeUnit::Litres)), convert_string_to_volume("19.5l"));
+    assert_eq!(Some(Volume::new(7.0, VolumeUnit::Barrels)), convert_string_to_volume("7BBL"));
+    assert_eq!(Some(Volume::new(2.0, VolumeUnit::Hectolitres)), convert_string_to_volume("2hl"));
+}
+
+#[test]
+fn it_should_not_convert_capacities_that_are_not_volumes() {
+    assert_eq!(None, convert_string_to_volume("2k"));
+    assert_eq!(None, convert_string_to_volume("5p"));
+    assert_eq!(None, convert_string_to_volume("g"));
+    assert_eq!(None, convert_string_to_volume("15"));
+    assert_eq!(None, convert_string_to_volume(""));
+}
+
+#[test]
+fn it_should_compare_volumes_across_units() {
+    let five_gallons = Volume::new(5.0, VolumeUnit::Gallons);
+    let twenty_litres = Volume::new(20.0, VolumeUnit::Litres);
+    let one_barrel = Volume::new(1.0, VolumeUnit::Barrels);
+
+    assert!(twenty_litres.can_hold(&five_gallons));
+    assert!(!five_gallons.can_hold(&twenty_litres));
+    assert!(one_barrel.can_hold(&Volume::new(31.0, VolumeUnit::Gallons)));
+    assert!(Volume::new(1.0, VolumeUnit::Hectolitres).can_hold(&Volume::new(100.0, VolumeUnit::Litres)));
+}